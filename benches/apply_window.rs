@@ -0,0 +1,49 @@
+use criterion::{ black_box, Criterion };
+use hann_rs::{ get_hann_window, windows::{ apply_window, apply_window_array, calculate_hann_window_into, SparseWindow, WindowLength, WindowSpec } };
+
+pub fn bench_apply_window(criterion: &mut Criterion) {
+  const WINDOW_LENGTH: usize = 1024;
+  let window = get_hann_window(WINDOW_LENGTH).expect("Failed to get the Hann window from the lookup table");
+  let mut signal = vec![0.5; WINDOW_LENGTH];
+
+  criterion.bench_function("apply_window", |bencher| {
+    bencher.iter(|| apply_window(black_box(&mut signal), black_box(&window)).expect("Lengths match"));
+  });
+}
+
+pub fn bench_apply_window_array(criterion: &mut Criterion) {
+  const WINDOW_LENGTH: usize = 1024;
+  let window: [f32; WINDOW_LENGTH] = get_hann_window(WINDOW_LENGTH)
+    .expect("Failed to get the Hann window from the lookup table")
+    .as_slice()
+    .try_into()
+    .expect("The window has the array length");
+  let mut signal = [0.5; WINDOW_LENGTH];
+
+  criterion.bench_function("apply_window_array", |bencher| {
+    bencher.iter(|| apply_window_array(black_box(&mut signal), black_box(&window)));
+  });
+}
+
+pub fn bench_calculate_hann_window_into(criterion: &mut Criterion) {
+  let window_length = WindowLength::new(4000).expect("Valid window length");
+  let mut window = vec![0.0; window_length.get()];
+
+  criterion.bench_function("calculate_hann_window_into", |bencher| {
+    bencher.iter(|| calculate_hann_window_into(black_box(window_length), black_box(&mut window)).expect("Lengths match"));
+  });
+}
+
+pub fn bench_sparse_window(criterion: &mut Criterion) {
+  const RECORD_LENGTH: usize = 1_000_000;
+  let window = WindowSpec::Tukey { alpha: 0.02 }.generate(RECORD_LENGTH).expect("Valid window parameters");
+  let sparse = SparseWindow::new(&window);
+  let mut record = vec![0.5; RECORD_LENGTH];
+
+  criterion.bench_function("apply_window_dense_1m", |bencher| {
+    bencher.iter(|| apply_window(black_box(&mut record), black_box(&window)).expect("Lengths match"));
+  });
+  criterion.bench_function("apply_window_sparse_1m", |bencher| {
+    bencher.iter(|| sparse.apply(black_box(&mut record)).expect("Lengths match"));
+  });
+}
@@ -1,10 +1,15 @@
 use criterion::{ criterion_main, criterion_group };
 
+mod apply_window;
 mod hann_window;
 mod sum_of_hann_window_squares;
 
 criterion_group!(
   benches,
+  apply_window::bench_apply_window,
+  apply_window::bench_apply_window_array,
+  apply_window::bench_calculate_hann_window_into,
+  apply_window::bench_sparse_window,
   hann_window::bench_get_hann_window,
   sum_of_hann_window_squares::bench_get_hann_window_sum_squares
 );
@@ -0,0 +1,68 @@
+#ifndef HANN_RS_H
+#define HANN_RS_H
+
+/* This file is generated by cbindgen from bindings/c/src/lib.rs, do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The status returned by the functions of the C API.
+typedef enum HannStatus {
+  // The call succeeded.
+  HANN_STATUS_OK = 0,
+  // The window length is less than or equal to 1.
+  HANN_STATUS_WINDOW_LENGTH_TOO_SMALL = 1,
+  // The window length is too large.
+  HANN_STATUS_WINDOW_LENGTH_TOO_LARGE = 2,
+  // The window could not be allocated.
+  HANN_STATUS_MEMORY_ALLOCATION_ERROR = 3,
+  // A buffer length does not match the window length.
+  HANN_STATUS_LENGTH_MISMATCH = 4,
+  // A pointer is null.
+  HANN_STATUS_NULL_POINTER = 5,
+  // Any other invalid argument.
+  HANN_STATUS_INVALID_ARGUMENT = 6,
+} HannStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Get a static, null-terminated description of a status.
+//
+// The status is taken as an `int`, since a value outside of the enum passed from C would be undefined
+// behavior as a `HannStatus`, and such a value is described as an unknown status.
+const char *hann_status_message(int status);
+
+// Write the Hann window of `window_length` values into `output`, which holds `output_length` values.
+//
+// # Safety
+//
+// `output` must be valid for writes of `output_length` floats.
+enum HannStatus hann_get_window(size_t window_length,
+                                float *output,
+                                size_t output_length);
+
+// Multiply the `length` samples of `signal` by the `length` values of `window` in place.
+//
+// # Safety
+//
+// `signal` must be valid for reads and writes of `length` floats and `window` for reads of `length`
+// floats, and they must not overlap.
+enum HannStatus hann_apply_window(float *signal, const float *window, size_t length);
+
+// Compute the sum of the squares of the `length` values of `window` into `sum`.
+//
+// # Safety
+//
+// `window` must be valid for reads of `length` floats and `sum` for a write of one float.
+enum HannStatus hann_window_sum_squares(const float *window, size_t length, float *sum);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HANN_RS_H */
@@ -0,0 +1,178 @@
+//! C bindings of `hann-rs`.
+//!
+//! The functions write into buffers owned by the caller and return a [`HannStatus`], so that no memory
+//! crosses the boundary. The header `include/hann_rs.h` is generated from this file by cbindgen.
+
+use std::{ ffi::{ c_char, c_int }, slice };
+
+use hann_rs::HannWindowError;
+
+/// The status returned by the functions of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HannStatus {
+  /// The call succeeded.
+  Ok = 0,
+  /// The window length is less than or equal to 1.
+  WindowLengthTooSmall = 1,
+  /// The window length is too large.
+  WindowLengthTooLarge = 2,
+  /// The window could not be allocated.
+  MemoryAllocationError = 3,
+  /// A buffer length does not match the window length.
+  LengthMismatch = 4,
+  /// A pointer is null.
+  NullPointer = 5,
+  /// Any other invalid argument.
+  InvalidArgument = 6,
+}
+
+// Convert the errors of the crate into statuses
+impl From<HannWindowError> for HannStatus {
+  fn from(error: HannWindowError) -> Self {
+    match error {
+      HannWindowError::WindowLengthTooSmall => HannStatus::WindowLengthTooSmall,
+      HannWindowError::WindowLengthTooLarge => HannStatus::WindowLengthTooLarge,
+      HannWindowError::MemoryAllocationError => HannStatus::MemoryAllocationError,
+      HannWindowError::LengthMismatch => HannStatus::LengthMismatch,
+      _ => HannStatus::InvalidArgument,
+    }
+  }
+}
+
+// Every status, to look up the value of a status received from C
+const STATUSES: [HannStatus; 7] = [
+  HannStatus::Ok,
+  HannStatus::WindowLengthTooSmall,
+  HannStatus::WindowLengthTooLarge,
+  HannStatus::MemoryAllocationError,
+  HannStatus::LengthMismatch,
+  HannStatus::NullPointer,
+  HannStatus::InvalidArgument,
+];
+
+/// Get a static, null-terminated description of a status.
+///
+/// The status is taken as an `int`, since a value outside of the enum passed from C would be undefined
+/// behavior as a `HannStatus`, and such a value is described as an unknown status.
+#[no_mangle]
+pub extern "C" fn hann_status_message(status: c_int) -> *const c_char {
+  let message: &'static [u8] = match STATUSES.into_iter().find(|&known| known as c_int == status) {
+    Some(HannStatus::Ok) => b"Success.\0",
+    Some(HannStatus::WindowLengthTooSmall) => b"Window length must be greater than 1.\0",
+    Some(HannStatus::WindowLengthTooLarge) => b"Window length is too large.\0",
+    Some(HannStatus::MemoryAllocationError) => b"Window length is too large to allocate memory.\0",
+    Some(HannStatus::LengthMismatch) => b"Buffer length does not match the window length.\0",
+    Some(HannStatus::NullPointer) => b"A pointer is null.\0",
+    Some(HannStatus::InvalidArgument) => b"Invalid argument.\0",
+    None => b"Unknown status.\0",
+  };
+  message.as_ptr().cast()
+}
+
+/// Write the Hann window of `window_length` values into `output`, which holds `output_length` values.
+///
+/// # Safety
+///
+/// `output` must be valid for writes of `output_length` floats.
+#[no_mangle]
+pub unsafe extern "C" fn hann_get_window(window_length: usize, output: *mut f32, output_length: usize) -> HannStatus {
+  // The output must hold the window exactly
+  if output.is_null() {
+    return HannStatus::NullPointer;
+  }
+  if output_length != window_length {
+    return HannStatus::LengthMismatch;
+  }
+
+  match hann_rs::get_hann_window(window_length) {
+    Ok(window) => {
+      // The caller guarantees that the output holds output_length floats
+      unsafe { slice::from_raw_parts_mut(output, output_length) }.copy_from_slice(&window);
+      HannStatus::Ok
+    }
+    Err(error) => error.into(),
+  }
+}
+
+/// Multiply the `length` samples of `signal` by the `length` values of `window` in place.
+///
+/// # Safety
+///
+/// `signal` must be valid for reads and writes of `length` floats and `window` for reads of `length`
+/// floats, and they must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn hann_apply_window(signal: *mut f32, window: *const f32, length: usize) -> HannStatus {
+  if signal.is_null() || window.is_null() {
+    return HannStatus::NullPointer;
+  }
+
+  // The caller guarantees the lengths of both buffers
+  let (signal, window) = unsafe { (slice::from_raw_parts_mut(signal, length), slice::from_raw_parts(window, length)) };
+  match hann_rs::windows::apply_window(signal, window) {
+    Ok(()) => HannStatus::Ok,
+    Err(error) => error.into(),
+  }
+}
+
+/// Compute the sum of the squares of the `length` values of `window` into `sum`.
+///
+/// # Safety
+///
+/// `window` must be valid for reads of `length` floats and `sum` for a write of one float.
+#[no_mangle]
+pub unsafe extern "C" fn hann_window_sum_squares(window: *const f32, length: usize, sum: *mut f32) -> HannStatus {
+  if window.is_null() || sum.is_null() {
+    return HannStatus::NullPointer;
+  }
+
+  // The caller guarantees the length of the window
+  let window = unsafe { slice::from_raw_parts(window, length) };
+  unsafe { sum.write(hann_rs::get_hann_window_sum_squares(window)) };
+  HannStatus::Ok
+}
+
+#[cfg(test)]
+mod test_c {
+  use std::{ ffi::CStr, ptr };
+
+  use super::*;
+
+  #[test]
+  fn test_hann_get_window() {
+    let mut window = [0.0f32; 5];
+
+    let status = unsafe { hann_get_window(5, window.as_mut_ptr(), window.len()) };
+
+    assert_eq!(status, HannStatus::Ok);
+    assert_eq!(window, [0.0, 0.5, 1.0, 0.5, 0.0]);
+    assert_eq!(unsafe { hann_get_window(4, window.as_mut_ptr(), window.len()) }, HannStatus::LengthMismatch);
+    assert_eq!(unsafe { hann_get_window(1, window.as_mut_ptr(), 1) }, HannStatus::WindowLengthTooSmall);
+    assert_eq!(unsafe { hann_get_window(5, ptr::null_mut(), 5) }, HannStatus::NullPointer);
+  }
+
+  #[test]
+  fn test_hann_apply_window_and_sum_squares() {
+    let window = [0.0f32, 0.5, 1.0, 0.5, 0.0];
+    let mut signal = [2.0f32; 5];
+    let mut sum = 0.0;
+
+    assert_eq!(unsafe { hann_apply_window(signal.as_mut_ptr(), window.as_ptr(), 5) }, HannStatus::Ok);
+    assert_eq!(unsafe { hann_window_sum_squares(window.as_ptr(), 5, &mut sum) }, HannStatus::Ok);
+
+    assert_eq!(signal, [0.0, 1.0, 2.0, 1.0, 0.0]);
+    assert_eq!(sum, 1.5);
+    assert_eq!(unsafe { hann_window_sum_squares(ptr::null(), 5, &mut sum) }, HannStatus::NullPointer);
+  }
+
+  #[test]
+  fn test_hann_status_message() {
+    let message = |status: c_int| unsafe { CStr::from_ptr(hann_status_message(status)) }.to_str().unwrap();
+
+    assert_eq!(message(HannStatus::Ok as c_int), "Success.");
+    assert_eq!(message(HannStatus::LengthMismatch as c_int), "Buffer length does not match the window length.");
+    assert_eq!(message(HannStatus::InvalidArgument as c_int), "Invalid argument.");
+    assert_eq!(message(7), "Unknown status.");
+    assert_eq!(message(-1), "Unknown status.");
+  }
+}
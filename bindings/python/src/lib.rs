@@ -0,0 +1,128 @@
+//! Python bindings of `hann-rs`.
+//!
+//! The functions take and return NumPy `float32` arrays and compute exactly what the Rust functions of
+//! the same names compute. Errors of the crate are raised as `ValueError`.
+
+use std::borrow::Cow;
+
+use ::hann_rs::{ stft::PaddingMode, windows::WindowSpec, HannWindowError };
+use numpy::{ IntoPyArray, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1 };
+use pyo3::{ exceptions::PyValueError, prelude::* };
+
+// Raise the errors of the crate as ValueError
+fn value_error(error: HannWindowError) -> PyErr {
+  PyValueError::new_err(error.to_string())
+}
+
+// Borrow the values of an array, copying them only if the array is not contiguous
+fn values<'a>(array: &'a PyReadonlyArray1<'_, f32>) -> Cow<'a, [f32]> {
+  array.as_slice().map_or_else(|_error| Cow::Owned(array.as_array().to_vec()), Cow::Borrowed)
+}
+
+// Parse the name of a padding mode
+fn padding_mode(name: &str) -> PyResult<PaddingMode> {
+  match name {
+    "none" => Ok(PaddingMode::None),
+    "zero" => Ok(PaddingMode::Zero),
+    "center_zero" => Ok(PaddingMode::CenterZero),
+    "center_reflect" => Ok(PaddingMode::CenterReflect),
+    _ => Err(PyValueError::new_err(format!("Unknown padding mode {}", name))),
+  }
+}
+
+/// Get the Hann window of `window_length` values, served from the lookup table.
+#[pyfunction]
+fn get_hann_window(py: Python<'_>, window_length: usize) -> PyResult<Bound<'_, PyArray1<f32>>> {
+  Ok(::hann_rs::get_hann_window(window_length).map_err(value_error)?.into_pyarray(py))
+}
+
+/// Generate the window `name` of `window_length` values, one of `WINDOW_NAMES`, with its optional
+/// shape `parameter`, e.g. the `beta` of "kaiser".
+#[pyfunction]
+#[pyo3(signature = (name, window_length, parameter = None))]
+fn window<'py>(
+  py: Python<'py>,
+  name: &str,
+  window_length: usize,
+  parameter: Option<f32>
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+  let spec = WindowSpec::from_name(name, parameter).map_err(value_error)?;
+  Ok(spec.generate(window_length).map_err(value_error)?.into_pyarray(py))
+}
+
+/// Return the signal multiplied by a window of the same length.
+#[pyfunction]
+fn apply_window<'py>(
+  py: Python<'py>,
+  signal: PyReadonlyArray1<'py, f32>,
+  window: PyReadonlyArray1<'py, f32>
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+  let mut signal = values(&signal).into_owned();
+  ::hann_rs::windows::apply_window(&mut signal, &values(&window)).map_err(value_error)?;
+  Ok(signal.into_pyarray(py))
+}
+
+/// The sum of the squares of the values of a window.
+#[pyfunction]
+fn get_hann_window_sum_squares(window: PyReadonlyArray1<'_, f32>) -> f32 {
+  ::hann_rs::get_hann_window_sum_squares(&values(&window))
+}
+
+/// Estimate the one-sided power spectral density of a signal with Welch's method.
+#[pyfunction]
+fn welch<'py>(
+  py: Python<'py>,
+  signal: PyReadonlyArray1<'py, f32>,
+  window: PyReadonlyArray1<'py, f32>,
+  hop_size: usize
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+  let density = ::hann_rs::stft::welch(&values(&signal), &values(&window), hop_size).map_err(value_error)?;
+  Ok(density.into_pyarray(py))
+}
+
+/// Compute the power spectrogram of a signal, one spectrum per row.
+///
+/// The `padding` is "none", "zero", "center_zero" or "center_reflect".
+#[pyfunction]
+#[pyo3(signature = (signal, window, hop_size, padding = "center_reflect"))]
+fn spectrogram<'py>(
+  py: Python<'py>,
+  signal: PyReadonlyArray1<'py, f32>,
+  window: PyReadonlyArray1<'py, f32>,
+  hop_size: usize,
+  padding: &str
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+  let window = values(&window);
+  let spectra = ::hann_rs::stft::spectrogram(&values(&signal), &window, hop_size, padding_mode(padding)?)
+    .map_err(value_error)?;
+
+  // Copy the spectra into one contiguous matrix, keeping the number of bins when there are no frames
+  let bin_count = window.len() / 2 + 1;
+  let matrix = PyArray1::from_vec(py, spectra.concat());
+  matrix.reshape([spectra.len(), bin_count])
+}
+
+/// Windows and spectral analysis with the exact numerics of the Rust crate.
+#[pymodule]
+#[pyo3(name = "hann_rs")]
+fn hann_rs_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+  module.add_function(wrap_pyfunction!(get_hann_window, module)?)?;
+  module.add_function(wrap_pyfunction!(window, module)?)?;
+  module.add_function(wrap_pyfunction!(apply_window, module)?)?;
+  module.add_function(wrap_pyfunction!(get_hann_window_sum_squares, module)?)?;
+  module.add_function(wrap_pyfunction!(welch, module)?)?;
+  module.add_function(wrap_pyfunction!(spectrogram, module)?)?;
+  module.add("WINDOW_NAMES", ::hann_rs::windows::WINDOW_NAMES.to_vec())?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_python {
+  use super::*;
+
+  #[test]
+  fn test_padding_mode() {
+    assert_eq!(padding_mode("center_zero").unwrap(), PaddingMode::CenterZero);
+    assert!(padding_mode("reflect").is_err());
+  }
+}
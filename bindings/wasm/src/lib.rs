@@ -0,0 +1,126 @@
+//! WebAssembly bindings of `hann-rs`.
+//!
+//! Windows are returned as `Float32Array`s and the spectra of a [`StreamingSpectrogram`] are exposed
+//! as a view into the memory of the module, without copying. Errors of the crate are thrown as
+//! JavaScript `Error`s. Build with `wasm-pack build` from this directory, whose Cargo configuration
+//! enables the wasm SIMD paths of the crate.
+
+use hann_rs::{ stft::WindowedRealFft, streaming::StreamingWindower, windows::{ WINDOW_NAMES, WindowSpec } };
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+
+/// Get the Hann window of `windowLength` values, served from the lookup table.
+#[wasm_bindgen(js_name = getHannWindow)]
+pub fn get_hann_window(window_length: usize) -> Result<Vec<f32>, JsError> {
+  Ok(hann_rs::get_hann_window(window_length)?)
+}
+
+/// Generate the window `name` of `windowLength` values, one of the `WINDOW_NAMES` of the crate, with
+/// its optional shape `parameter`, e.g. the `beta` of "kaiser".
+#[wasm_bindgen]
+pub fn window(name: &str, window_length: usize, parameter: Option<f32>) -> Result<Vec<f32>, JsError> {
+  Ok(WindowSpec::from_name(name, parameter)?.generate(window_length)?)
+}
+
+/// The names of the windows accepted by [`window`].
+#[wasm_bindgen(js_name = windowNames)]
+pub fn window_names() -> Vec<String> {
+  WINDOW_NAMES.iter().map(|name| name.to_string()).collect()
+}
+
+/// Multiply `signal` by a window of the same length in place.
+#[wasm_bindgen(js_name = applyWindow)]
+pub fn apply_window(signal: &mut [f32], window: &[f32]) -> Result<(), JsError> {
+  Ok(hann_rs::windows::apply_window(signal, window)?)
+}
+
+/// A streaming power spectrogram for visualizers.
+///
+/// Chunks of samples of any length are fed to [`StreamingSpectrogram::process`], which cuts them into
+/// frames with a `StreamingWindower`, windows them by the Hann window and computes the power of the
+/// `frameLength / 2 + 1` bins of every completed frame. The spectra of the last chunk are read with
+/// [`StreamingSpectrogram::spectra`].
+#[wasm_bindgen]
+pub struct StreamingSpectrogram {
+  windower: StreamingWindower,
+  fft: WindowedRealFft,
+  spectra: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl StreamingSpectrogram {
+  /// Create a spectrogram of frames of `frameLength` samples, `hopSize` samples apart.
+  #[wasm_bindgen(constructor)]
+  pub fn new(frame_length: usize, hop_size: usize) -> Result<StreamingSpectrogram, JsError> {
+    // The windower cuts the frames and the transform windows them
+    let windower = StreamingWindower::new(frame_length, hop_size)?;
+    let fft = WindowedRealFft::new(frame_length)?;
+    Ok(StreamingSpectrogram { windower, fft, spectra: Vec::new() })
+  }
+
+  /// The number of bins of every spectrum, `frameLength / 2 + 1`.
+  #[wasm_bindgen(getter, js_name = binCount)]
+  pub fn bin_count(&self) -> usize {
+    self.fft.bin_count()
+  }
+
+  /// Feed a chunk of samples and compute the spectra of the frames it completes.
+  ///
+  /// Returns the number of spectra, replacing those of the previous chunk.
+  pub fn process(&mut self, samples: &[f32]) -> usize {
+    self.spectra.clear();
+    let fft = &mut self.fft;
+    let spectra = &mut self.spectra;
+    self.windower.process(samples, |frame| {
+      let spectrum = fft.process(frame).expect("Frames match the FFT length");
+      spectra.extend(spectrum.iter().map(|bin| bin.norm_sqr()));
+    })
+  }
+
+  /// The spectra of the last chunk, one after the other, as a view into the memory of the module.
+  ///
+  /// The view is not a copy: it is only valid until the next call to any function of the module, which
+  /// may overwrite or move the spectra. Copy it with `slice()` to keep it longer.
+  pub fn spectra(&self) -> Float32Array {
+    // The view is only read before the spectra are modified, as documented
+    unsafe { Float32Array::view(&self.spectra) }
+  }
+
+  /// Discard the buffered samples, to start a new stream.
+  pub fn reset(&mut self) {
+    self.windower.reset();
+    self.spectra.clear();
+  }
+}
+
+#[cfg(test)]
+mod test_wasm {
+  use hann_rs::{ get_hann_window, stft::{ frames, PaddingMode } };
+
+  use super::*;
+
+  #[test]
+  fn test_window() {
+    assert_eq!(window("kaiser", 16, Some(8.6)).unwrap(), WindowSpec::Kaiser { beta: 8.6 }.generate(16).unwrap());
+    assert_eq!(window_names().len(), WINDOW_NAMES.len());
+  }
+
+  #[test]
+  fn test_streaming_spectrogram_matches_spectrogram() {
+    let signal: Vec<f32> = (0..1000).map(|n| ((n * n) % 13) as f32).collect();
+    let mut streaming = StreamingSpectrogram::new(64, 16).unwrap();
+
+    // Feed the signal in uneven chunks
+    let mut spectra = Vec::new();
+    for chunk in signal.chunks(100) {
+      streaming.process(chunk);
+      spectra.extend_from_slice(&streaming.spectra);
+    }
+
+    let expected = hann_rs::stft::spectrogram(&signal, &get_hann_window(64).unwrap(), 16, PaddingMode::None).unwrap();
+    assert_eq!(spectra.len(), frames(&signal, 64, 16, PaddingMode::None).unwrap().len() * streaming.bin_count());
+    for (value, expected) in spectra.iter().zip(expected.concat()) {
+      assert!((value - expected).abs() <= 1e-4 * expected.max(1.0));
+    }
+  }
+}
@@ -0,0 +1,197 @@
+//! A drop-in replacement for the iterator API of the `apodize` crate.
+//!
+//! The functions mirror those of `apodize` 1.0, names, parameters and `f64` items included, so a
+//! project can migrate by replacing `apodize::` with `hann_rs::apodize::`. The Hann windows returned by
+//! [`hanning_iter`] are served from the lookup table of [`get_hann_window`](crate::get_hann_window),
+//! which stores `f32` values, so they match `apodize` to `f32` precision rather than bit for bit. The
+//! other windows are computed exactly as `apodize` computes them. As in `apodize`, the functions panic
+//! on window sizes they do not support.
+
+use std::{ f64::consts::PI, vec };
+
+use crate::hann_window::get_hann_window;
+
+/// An iterator over the values of a generalized cosine window.
+///
+/// Created by [`cosine_iter`], [`hanning_iter`], [`hamming_iter`], [`blackman_iter`] and
+/// [`nuttall_iter`].
+#[derive(Debug, Clone)]
+pub struct CosineWindowIter {
+  values: CosineValues,
+}
+
+// The source of the values of a cosine window
+#[derive(Debug, Clone)]
+enum CosineValues {
+  // A Hann window read from the lookup table
+  Table(vec::IntoIter<f32>),
+  // A window computed from its coefficients
+  Coefficients { a: f64, b: f64, c: f64, d: f64, index: usize, size: usize },
+}
+
+impl Iterator for CosineWindowIter {
+  type Item = f64;
+
+  fn next(&mut self) -> Option<f64> {
+    match &mut self.values {
+      CosineValues::Table(values) => values.next().map(f64::from),
+      CosineValues::Coefficients { a, b, c, d, index, size } => {
+        if *index == *size {
+          return None;
+        }
+        *index += 1;
+        Some(cosine_at(*a, *b, *c, *d, *size, *index - 1))
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = match &self.values {
+      CosineValues::Table(values) => values.len(),
+      CosineValues::Coefficients { index, size, .. } => size - index,
+    };
+    (remaining, Some(remaining))
+  }
+}
+
+impl ExactSizeIterator for CosineWindowIter {}
+
+/// The value at `index` of the generalized cosine window of `size` values,
+/// `a - b * cos(2x) + c * cos(4x) - d * cos(6x)` with `x = π * index / (size - 1)`.
+pub fn cosine_at(a: f64, b: f64, c: f64, d: f64, size: usize, index: usize) -> f64 {
+  let x = (PI * (index as f64)) / ((size - 1) as f64);
+  (a - b * (2.0 * x).cos()) + (c * (4.0 * x).cos() - d * (6.0 * x).cos())
+}
+
+/// Iterate over the generalized cosine window of `size` values with the coefficients `a` to `d`,
+/// see [`cosine_at`].
+///
+/// Panics if the size is less than 2.
+pub fn cosine_iter(a: f64, b: f64, c: f64, d: f64, size: usize) -> CosineWindowIter {
+  assert!(1 < size);
+  CosineWindowIter { values: CosineValues::Coefficients { a, b, c, d, index: 0, size } }
+}
+
+/// Iterate over the Hann window of `size` values, served from the lookup table.
+///
+/// Panics if the size is less than 2, or too large.
+pub fn hanning_iter(size: usize) -> CosineWindowIter {
+  assert!(1 < size);
+  let window = get_hann_window(size).expect("Window size is too large");
+  CosineWindowIter { values: CosineValues::Table(window.into_iter()) }
+}
+
+/// Iterate over the Hamming window of `size` values.
+///
+/// Panics if the size is less than 2.
+pub fn hamming_iter(size: usize) -> CosineWindowIter {
+  cosine_iter(0.54, 0.46, 0.0, 0.0, size)
+}
+
+/// Iterate over the window `apodize` calls Blackman, with the four coefficients of the
+/// Blackman-Harris window.
+///
+/// Panics if the size is less than 2.
+pub fn blackman_iter(size: usize) -> CosineWindowIter {
+  cosine_iter(0.35875, 0.48829, 0.14128, 0.01168, size)
+}
+
+/// Iterate over the Nuttall window of `size` values.
+///
+/// Panics if the size is less than 2.
+pub fn nuttall_iter(size: usize) -> CosineWindowIter {
+  cosine_iter(0.355_768, 0.487_396, 0.144_232, 0.012_604, size)
+}
+
+/// An iterator over the values of a triangular window.
+///
+/// Created by [`triangular_iter`].
+#[derive(Debug, Clone)]
+pub struct TriangularWindowIter {
+  index: usize,
+  size: usize,
+}
+
+impl Iterator for TriangularWindowIter {
+  type Item = f64;
+
+  fn next(&mut self) -> Option<f64> {
+    if self.index == self.size {
+      return None;
+    }
+    self.index += 1;
+    Some(triangular_at(self.size, self.size, self.index - 1))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.size - self.index;
+    (remaining, Some(remaining))
+  }
+}
+
+impl ExactSizeIterator for TriangularWindowIter {}
+
+/// The value at `index` of the triangular window of `size` values and width `l`,
+/// `1 - |(index - (size - 1) / 2) / (l / 2)|`.
+pub fn triangular_at(l: usize, size: usize, index: usize) -> f64 {
+  1.0 - (((index as f64) - ((size - 1) as f64) / 2.0) / ((l as f64) / 2.0)).abs()
+}
+
+/// Iterate over the triangular window of `size` values, which does not reach 0 at its ends.
+///
+/// Panics if the size is 0.
+pub fn triangular_iter(size: usize) -> TriangularWindowIter {
+  assert!(0 < size);
+  TriangularWindowIter { index: 0, size }
+}
+
+#[cfg(test)]
+mod test_apodize {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_hanning_iter() {
+    // The values of the example of apodize
+    let expected = [0.0, 0.24999999999999994, 0.7499999999999999, 1.0, 0.7500000000000002, 0.25, 0.0];
+
+    let window: Vec<f64> = hanning_iter(7).collect();
+
+    for (value, expected) in window.iter().zip(expected) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-7);
+    }
+    assert_eq!(hanning_iter(1024).len(), 1024);
+  }
+
+  #[test]
+  fn test_cosine_iters() {
+    let hamming: Vec<f64> = hamming_iter(5).collect();
+    let blackman: Vec<f64> = blackman_iter(5).collect();
+    let nuttall: Vec<f64> = nuttall_iter(5).collect();
+
+    for (value, expected) in hamming.iter().zip([0.08, 0.54, 1.0, 0.54, 0.08]) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-15);
+    }
+    assert_abs_diff_eq!(blackman[0], 0.35875 - 0.48829 + 0.14128 - 0.01168, epsilon = 1e-15);
+    assert_abs_diff_eq!(blackman[2], 1.0, epsilon = 1e-15);
+    assert_abs_diff_eq!(nuttall[2], 1.0, epsilon = 1e-15);
+
+    let mut iterator = cosine_iter(0.5, 0.5, 0.0, 0.0, 4);
+    iterator.next();
+    assert_eq!(iterator.len(), 3);
+  }
+
+  #[test]
+  fn test_triangular_iter() {
+    let window: Vec<f64> = triangular_iter(4).collect();
+
+    assert_eq!(window, vec![0.25, 0.75, 0.75, 0.25]);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_cosine_iter_panics_on_small_size() {
+    let _ = hanning_iter(1);
+  }
+}
@@ -0,0 +1,96 @@
+use crate::hann_window::HannWindowError;
+
+/// Multiply a signal by a window in place.
+///
+/// This function takes a mutable slice `signal` and a slice `window` of the same length and
+/// multiplies every sample of the signal by the corresponding window value. If the lengths of
+/// the two slices differ, an error is returned and the signal is left untouched.
+pub fn apply_window(signal: &mut [f32], window: &[f32]) -> Result<(), HannWindowError> {
+  // The window must cover the signal sample for sample
+  if signal.len() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  multiply_by_window(signal, window);
+
+  Ok(())
+}
+
+/// Multiply a fixed-length frame by a window of the same length in place.
+///
+/// This is the counterpart of [`apply_window`] for per-frame paths whose frame length is known at
+/// compile time. The lengths are part of the array types, so nothing is checked and no error can be
+/// returned.
+pub fn apply_window_array<const N: usize>(signal: &mut [f32; N], window: &[f32; N]) {
+  multiply_by_window(signal, window);
+}
+
+// Multiply every sample by its window value
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+pub(crate) fn multiply_by_window(signal: &mut [f32], window: &[f32]) {
+  for (sample, &value) in signal.iter_mut().zip(window.iter()) {
+    *sample *= value;
+  }
+}
+
+// Multiply every sample by its window value, four samples at a time with the wasm SIMD instructions
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub(crate) fn multiply_by_window(signal: &mut [f32], window: &[f32]) {
+  use std::arch::wasm32::{ f32x4_mul, v128, v128_load, v128_store };
+
+  let mut signal_chunks = signal.chunks_exact_mut(4);
+  let mut window_chunks = window.chunks_exact(4);
+  for (samples, values) in (&mut signal_chunks).zip(&mut window_chunks) {
+    // Both chunks hold exactly 4 values, and wasm loads and stores need no alignment
+    unsafe {
+      let samples = samples.as_mut_ptr() as *mut v128;
+      v128_store(samples, f32x4_mul(v128_load(samples), v128_load(values.as_ptr() as *const v128)));
+    }
+  }
+
+  // Multiply the last samples one by one
+  for (sample, &value) in signal_chunks.into_remainder().iter_mut().zip(window_chunks.remainder().iter()) {
+    *sample *= value;
+  }
+}
+
+#[cfg(test)]
+mod test_apply_window {
+  use crate::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_apply_window_values() {
+    let window = get_hann_window(5).unwrap();
+    let mut signal = [2.0; 5];
+
+    apply_window(&mut signal, &window).unwrap();
+
+    assert_eq!(signal, [0.0, 1.0, 2.0, 1.0, 0.0]);
+  }
+
+  #[test]
+  fn test_apply_window_length_mismatch() {
+    let window = get_hann_window(5).unwrap();
+    let mut signal = [1.0; 4];
+
+    let result = apply_window(&mut signal, &window);
+
+    assert_eq!(result.unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(signal, [1.0; 4]);
+  }
+
+  #[test]
+  fn test_apply_window_array() {
+    let window: [f32; 5] = get_hann_window(5).unwrap().as_slice().try_into().unwrap();
+    let mut signal = [2.0; 5];
+    let mut expected = [2.0; 5];
+
+    apply_window_array(&mut signal, &window);
+    apply_window(&mut expected, &window).unwrap();
+
+    assert_eq!(signal, expected);
+    assert_eq!(signal, [0.0, 1.0, 2.0, 1.0, 0.0]);
+  }
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "realfft")]
+use std::sync::Arc;
+
+#[cfg(feature = "realfft")]
+use arrow_array::FixedSizeListArray;
+use arrow_array::Float32Array;
+use arrow_buffer::ScalarBuffer;
+#[cfg(feature = "realfft")]
+use arrow_schema::{ DataType, Field };
+
+#[cfg(feature = "realfft")]
+use crate::{ frames::{ frames, PaddingMode }, real_fft::WindowedRealFft };
+use crate::hann_window::{ get_hann_window, HannWindowError };
+
+/// Move a window into an Arrow `Float32Array` without copying.
+///
+/// The vector becomes the values buffer of the array as is, so it can be handed on to DataFusion,
+/// Polars or any other Arrow consumer zero-copy. The array has no null buffer.
+pub fn window_to_arrow(window: Vec<f32>) -> Float32Array {
+  Float32Array::new(ScalarBuffer::from(window), None)
+}
+
+/// Get the Hann window of `window_length` values as an Arrow `Float32Array`.
+///
+/// The window is moved into the array without copying, see [`get_hann_window`] for the errors.
+pub fn get_hann_window_arrow(window_length: usize) -> Result<Float32Array, HannWindowError> {
+  Ok(window_to_arrow(get_hann_window(window_length)?))
+}
+
+/// Compute the power spectrogram of a signal, returned as an Arrow `FixedSizeListArray`.
+///
+/// This is the [`spectrogram`](crate::stft::spectrogram) function, available with the `realfft` feature,
+/// returning one list of `window.len() / 2 + 1` non-null `Float32` powers per frame. The spectra are
+/// written straight into a single contiguous values buffer, frame after frame, which the array takes
+/// over without copying. An error is returned if the window length is less than or equal to 1 or if
+/// the hop size is 0.
+#[cfg(feature = "realfft")]
+pub fn spectrogram_arrow(
+  signal: &[f32],
+  window: &[f32],
+  hop_size: usize,
+  padding_mode: PaddingMode
+) -> Result<FixedSizeListArray, HannWindowError> {
+  let mut fft = WindowedRealFft::new(window.len())?.with_window(window)?;
+  let mut iterator = frames(signal, window.len(), hop_size, padding_mode)?;
+
+  // Write the spectra of all the frames into one buffer
+  let bin_count = fft.bin_count();
+  let mut values = Vec::with_capacity(iterator.len() * bin_count);
+  let mut frame = vec![0.0; window.len()];
+  while iterator.next_into(&mut frame)? {
+    values.extend(fft.process(&frame)?.iter().map(|bin| bin.norm_sqr()));
+  }
+
+  Ok(values_to_list_array(values, bin_count))
+}
+
+// Wrap a buffer of rows of `row_length` values into a FixedSizeListArray
+#[cfg(feature = "realfft")]
+fn values_to_list_array(values: Vec<f32>, row_length: usize) -> FixedSizeListArray {
+  let field = Arc::new(Field::new("item", DataType::Float32, false));
+  let row_length = i32::try_from(row_length).expect("Row length fits in an i32");
+  FixedSizeListArray::try_new(field, row_length, Arc::new(window_to_arrow(values)), None)
+    .expect("Values cover every row")
+}
+
+#[cfg(test)]
+mod test_arrow_interop {
+  use arrow_array::Array;
+
+  use super::*;
+
+  #[test]
+  fn test_get_hann_window_arrow() {
+    let window = get_hann_window_arrow(5).unwrap();
+
+    assert_eq!(window.values().as_ref(), &[0.0, 0.5, 1.0, 0.5, 0.0]);
+    assert_eq!(window.null_count(), 0);
+    assert_eq!(get_hann_window_arrow(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+
+  #[test]
+  fn test_window_to_arrow_is_zero_copy() {
+    let window = get_hann_window(64).unwrap();
+    let pointer = window.as_ptr();
+
+    let array = window_to_arrow(window);
+
+    assert_eq!(array.values().as_ptr(), pointer);
+  }
+
+  #[cfg(feature = "realfft")]
+  #[test]
+  fn test_spectrogram_arrow_matches_spectrogram() {
+    let signal: Vec<f32> = (0..300).map(|n| ((n * n) % 17) as f32).collect();
+    let window = get_hann_window(32).unwrap();
+
+    let array = spectrogram_arrow(&signal, &window, 16, PaddingMode::CenterReflect).unwrap();
+    let expected = crate::stft::spectrogram(&signal, &window, 16, PaddingMode::CenterReflect).unwrap();
+
+    assert_eq!(array.len(), expected.len());
+    assert_eq!(array.value_length(), 17);
+    for (row, spectrum) in expected.iter().enumerate() {
+      let values = array.value(row);
+      let values = values.as_any().downcast_ref::<Float32Array>().unwrap();
+      assert_eq!(values.values().as_ref(), spectrum.as_slice());
+    }
+  }
+}
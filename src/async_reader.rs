@@ -0,0 +1,190 @@
+use std::{ collections::VecDeque, future::poll_fn, io, pin::Pin, task::{ Context, Poll } };
+
+use tokio::io::{ AsyncRead, ReadBuf };
+
+use crate::{
+  latency::Latency,
+  sample_format::{ decode_samples, SampleFormat },
+  streaming_windower::StreamingWindower,
+};
+
+/// A frame source reading raw PCM from any `tokio::io::AsyncRead` and yielding windowed frames.
+///
+/// The bytes are decoded according to the given [`SampleFormat`] and cut into frames by a
+/// [`StreamingWindower`]. Samples split across two reads are reassembled. Reads are at most one hop of
+/// samples long, so that frames are yielded as soon as the data for them has arrived.
+#[derive(Debug)]
+pub struct AsyncFrameReader<R> {
+  reader: R,
+  format: SampleFormat,
+  windower: StreamingWindower,
+  bytes: Vec<u8>,
+  // Number of bytes read into `bytes` that have not been decoded yet
+  buffered_bytes: usize,
+  samples: Vec<f32>,
+  pending_frames: VecDeque<Vec<f32>>,
+  finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncFrameReader<R> {
+  /// Create a frame source decoding `format` samples from `reader` and framing them with `windower`.
+  pub fn new(reader: R, format: SampleFormat, windower: StreamingWindower) -> Self {
+    let buffer_samples = windower.hop_size().min(windower.frame_length());
+    AsyncFrameReader {
+      reader,
+      format,
+      windower,
+      bytes: vec![0; buffer_samples * format.bytes_per_sample()],
+      buffered_bytes: 0,
+      samples: Vec::with_capacity(buffer_samples),
+      pending_frames: VecDeque::new(),
+      finished: false,
+    }
+  }
+
+  /// The format of the decoded samples.
+  pub fn format(&self) -> SampleFormat {
+    self.format
+  }
+
+  /// Consume the frame source and return the wrapped reader.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+
+  /// Read the next windowed frame.
+  ///
+  /// Returns `Ok(None)` once the reader reached its end and every complete frame was yielded. Samples
+  /// that do not fill a whole frame at the end of the stream are discarded. An error of kind
+  /// `UnexpectedEof` is returned if the stream ends in the middle of a sample.
+  pub async fn next_frame(&mut self) -> io::Result<Option<Vec<f32>>> {
+    poll_fn(|context| self.poll_next_frame(context)).await
+  }
+
+  /// Poll for the next windowed frame, see [`AsyncFrameReader::next_frame`].
+  pub fn poll_next_frame(&mut self, context: &mut Context<'_>) -> Poll<io::Result<Option<Vec<f32>>>> {
+    loop {
+      // Hand out the frames completed by the previous read first
+      if let Some(frame) = self.pending_frames.pop_front() {
+        return Poll::Ready(Ok(Some(frame)));
+      }
+      if self.finished {
+        return Poll::Ready(Ok(None));
+      }
+
+      // Read more bytes after the ones left over from a split sample
+      let mut read_buffer = ReadBuf::new(&mut self.bytes[self.buffered_bytes..]);
+      match Pin::new(&mut self.reader).poll_read(context, &mut read_buffer) {
+        Poll::Ready(Ok(())) => {}
+        Poll::Ready(Err(error)) => {
+          return Poll::Ready(Err(error));
+        }
+        Poll::Pending => {
+          return Poll::Pending;
+        }
+      }
+      let read_bytes = read_buffer.filled().len();
+
+      // An empty read marks the end of the stream
+      if read_bytes == 0 {
+        self.finished = true;
+        if self.buffered_bytes != 0 {
+          return Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Stream ended in the middle of a sample")));
+        }
+        continue;
+      }
+      self.buffered_bytes += read_bytes;
+
+      // Decode the whole samples and keep the bytes of a split sample for the next read
+      let bytes_per_sample = self.format.bytes_per_sample();
+      let whole_bytes = self.buffered_bytes - (self.buffered_bytes % bytes_per_sample);
+      self.samples.resize(whole_bytes / bytes_per_sample, 0.0);
+      decode_samples(&self.bytes[..whole_bytes], self.format, &mut self.samples).expect(
+        "Sample buffer matches the decoded bytes"
+      );
+      self.bytes.copy_within(whole_bytes..self.buffered_bytes, 0);
+      self.buffered_bytes -= whole_bytes;
+
+      let pending_frames = &mut self.pending_frames;
+      self.windower.process(&self.samples, |frame| pending_frames.push_back(frame.to_vec()));
+    }
+  }
+}
+
+impl<R> Latency for AsyncFrameReader<R> {
+  fn latency(&self) -> usize {
+    self.windower.latency()
+  }
+
+  fn group_delay(&self) -> f64 {
+    self.windower.group_delay()
+  }
+}
+
+#[cfg(feature = "futures")]
+impl<R: AsyncRead + Unpin> futures_core::Stream for AsyncFrameReader<R> {
+  type Item = io::Result<Vec<f32>>;
+
+  fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.poll_next_frame(context).map(Result::transpose)
+  }
+}
+
+#[cfg(test)]
+mod test_async_reader {
+  use tokio::runtime::Builder;
+
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  // Read every frame from the given bytes on a single-threaded runtime
+  fn read_frames(bytes: &[u8], format: SampleFormat, windower: StreamingWindower) -> io::Result<Vec<Vec<f32>>> {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    runtime.block_on(async {
+      let mut reader = AsyncFrameReader::new(bytes, format, windower);
+      let mut frames = Vec::new();
+      while let Some(frame) = reader.next_frame().await? {
+        frames.push(frame);
+      }
+      Ok(frames)
+    })
+  }
+
+  #[test]
+  fn test_async_frame_reader_f32() {
+    let signal: Vec<f32> = (0..50).map(|i| (i as f32) * 0.1).collect();
+    let bytes: Vec<u8> = signal.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let window = get_hann_window(8).unwrap();
+    let windower = StreamingWindower::new(8, 3).unwrap().with_window(&window).unwrap();
+
+    let emitted = read_frames(&bytes, SampleFormat::F32Le, windower).unwrap();
+    let expected: Vec<Vec<f32>> = frames(&signal, 8, 3, PaddingMode::None)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(emitted, expected);
+  }
+
+  #[test]
+  fn test_async_frame_reader_i16() {
+    let bytes: Vec<u8> = [16384i16; 8].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let windower = StreamingWindower::new(4, 4).unwrap();
+
+    let emitted = read_frames(&bytes, SampleFormat::I16Le, windower).unwrap();
+
+    assert_eq!(emitted, vec![vec![0.5; 4], vec![0.5; 4]]);
+  }
+
+  #[test]
+  fn test_async_frame_reader_split_sample() {
+    let windower = StreamingWindower::new(2, 2).unwrap();
+
+    let error = read_frames(&[0, 0, 0, 0, 0], SampleFormat::I16Le, windower).unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+  }
+}
@@ -0,0 +1,195 @@
+use std::{ error::Error, fs::File, io::{ BufReader, Read }, path::PathBuf };
+
+use clap::{ Args, ValueEnum };
+use hann_rs::{ io::{ Colormap, SpectrogramPlot, WavFrameReader }, stft::{ MelFilterbank, WindowedRealFft }, streaming::{ Downmix, StreamingWindower } };
+
+use crate::WindowArgs;
+
+/// The colormaps of the spectrogram image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColormapName {
+  /// From dark blue through green to yellow.
+  Viridis,
+  /// From black through purple and orange to light yellow.
+  Magma,
+  /// From black to white.
+  Grayscale,
+  /// From black through copper to light orange.
+  Copper,
+}
+
+/// The arguments of the analyze subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct AnalyzeArgs {
+  /// The WAV file to analyze, downmixed to one channel by averaging.
+  pub input: PathBuf,
+  #[command(flatten)]
+  pub window: WindowArgs,
+  /// The number of samples of every frame, which is also the FFT length.
+  #[arg(long = "frame-len", default_value_t = 1024)]
+  pub frame_length: usize,
+  /// The number of samples between the starts of consecutive frames.
+  #[arg(long = "hop", default_value_t = 256)]
+  pub hop_size: usize,
+  /// Reduce the spectra to this number of mel bands.
+  #[arg(long = "mel")]
+  pub mel_bands: Option<usize>,
+  /// The colormap of the levels.
+  #[arg(long, value_enum, default_value = "viridis")]
+  pub colormap: ColormapName,
+  /// The range of levels shown below the loudest one, in decibels.
+  #[arg(long = "range", default_value_t = 80.0)]
+  pub range_db: f32,
+  /// Use a logarithmic frequency axis from this frequency, in Hz.
+  #[arg(long = "log-freq")]
+  pub min_log_frequency: Option<f32>,
+  /// The width and height of the image, in pixels.
+  #[arg(long, num_args = 2, value_names = ["WIDTH", "HEIGHT"], default_values_t = [1024, 512])]
+  pub size: Vec<u32>,
+  /// The image file, an SVG file if its extension is "svg" and a PNG file otherwise.
+  #[arg(short, long)]
+  pub output: PathBuf,
+}
+
+/// The power spectra of the frames of a WAV file and its sample rate.
+#[derive(Debug, Clone, PartialEq)]
+struct FileSpectrogram {
+  power: Vec<Vec<f32>>,
+  sample_rate: u32,
+  // The center frequencies of the mel bands, if the spectra are reduced to them
+  mel_frequencies: Option<Vec<f32>>,
+}
+
+// Read the WAV file and render its spectrogram to the image file
+pub fn run(args: &AnalyzeArgs) -> Result<(), Box<dyn Error>> {
+  let spectrogram = analyze(BufReader::new(File::open(&args.input)?), args)?;
+  spectrogram_plot(&spectrogram, args).plot(&spectrogram.power, &args.output)?;
+  Ok(())
+}
+
+// Frame, transform and optionally reduce the samples of a WAV file to mel bands
+fn analyze<R: Read>(reader: R, args: &AnalyzeArgs) -> Result<FileSpectrogram, Box<dyn Error>> {
+  let window = args.window.spec().generate(args.frame_length)?;
+  let mut fft = WindowedRealFft::new(args.frame_length)?.with_window(&window)?;
+  let windower = StreamingWindower::new(args.frame_length, args.hop_size)?;
+  let frames = WavFrameReader::new(reader, windower, Downmix::Average)?;
+  let sample_rate = frames.spec().sample_rate;
+  let filterbank = match args.mel_bands {
+    Some(band_count) => Some(MelFilterbank::new(sample_rate, args.frame_length, band_count, 0.0, (sample_rate as f32) / 2.0)?),
+    None => None,
+  };
+
+  // The power of every bin, or of every mel band
+  let mut power = Vec::new();
+  for frame in frames {
+    let bins: Vec<f32> = fft.process(&frame?)?.iter().map(|bin| bin.norm_sqr()).collect();
+    power.push(match &filterbank {
+      Some(filterbank) => filterbank.apply(&bins)?,
+      None => bins,
+    });
+  }
+
+  Ok(FileSpectrogram {
+    power,
+    sample_rate,
+    mel_frequencies: filterbank.map(|filterbank| filterbank.center_frequencies().to_vec()),
+  })
+}
+
+// The renderer of the spectrogram configured by the arguments
+fn spectrogram_plot(spectrogram: &FileSpectrogram, args: &AnalyzeArgs) -> SpectrogramPlot {
+  let colormap = match args.colormap {
+    ColormapName::Viridis => Colormap::Viridis,
+    ColormapName::Magma => Colormap::Magma,
+    ColormapName::Grayscale => Colormap::Grayscale,
+    ColormapName::Copper => Colormap::Copper,
+  };
+  let mut plot = SpectrogramPlot::new(spectrogram.sample_rate, args.hop_size)
+    .with_colormap(colormap)
+    .with_dynamic_range(args.range_db)
+    .with_title(&format!("Spectrogram of {}", args.input.display()))
+    .with_size(args.size[0], args.size[1]);
+  if let Some(frequencies) = &spectrogram.mel_frequencies {
+    plot = plot.with_frequencies(frequencies.clone());
+  }
+  if let Some(min_frequency) = args.min_log_frequency {
+    plot = plot.with_log_frequency(min_frequency);
+  }
+  plot
+}
+
+#[cfg(test)]
+mod test_analyze {
+  use std::{ f32::consts::PI, io::Cursor };
+
+  use clap::Parser;
+  use hound::{ WavSpec, WavWriter };
+  use plotters::prelude::*;
+
+  use crate::{ Cli, Command };
+
+  use super::*;
+
+  // Parse the arguments of the analyze subcommand
+  fn parse(arguments: &[&str]) -> AnalyzeArgs {
+    let Command::Analyze(args) = Cli::parse_from(["hann-rs", "analyze"].iter().chain(arguments)).command else {
+      panic!("Expected the analyze subcommand")
+    };
+    args
+  }
+
+  // A mono 16-bit WAV file of a tone at 1 kHz, sampled at 8 kHz
+  fn tone_wav() -> Vec<u8> {
+    let spec = WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let mut bytes = Vec::new();
+    let mut writer = WavWriter::new(Cursor::new(&mut bytes), spec).unwrap();
+    for n in 0..8000 {
+      writer.write_sample((16384.0 * (2.0 * PI * 1000.0 * (n as f32) / 8000.0).sin()) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    bytes
+  }
+
+  #[test]
+  fn test_analyze_tone() {
+    let args = parse(&["tone.wav", "--frame-len", "256", "--hop", "128", "-o", "tone.png"]);
+
+    let spectrogram = analyze(Cursor::new(tone_wav()), &args).unwrap();
+
+    // The loudest bin of every frame is 1 kHz, bin 32 of 256 at 8 kHz
+    assert_eq!(spectrogram.power.len(), (8000 - 256) / 128 + 1);
+    assert_eq!(spectrogram.sample_rate, 8000);
+    for row in &spectrogram.power {
+      assert_eq!(row.len(), 129);
+      assert_eq!(row.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0, 32);
+    }
+  }
+
+  #[test]
+  fn test_analyze_mel() {
+    let args = parse(&["tone.wav", "--frame-len", "256", "--mel", "40", "-o", "tone.png"]);
+
+    let spectrogram = analyze(Cursor::new(tone_wav()), &args).unwrap();
+
+    assert_eq!(spectrogram.mel_frequencies.as_ref().map(Vec::len), Some(40));
+    assert!(spectrogram.power.iter().all(|row| row.len() == 40));
+  }
+
+  #[test]
+  fn test_spectrogram_plot_svg() {
+    let args = parse(&["tone.wav", "--frame-len", "256", "--mel", "40", "--colormap", "grayscale", "--log-freq", "20", "-o", "tone.svg"]);
+    let spectrogram = analyze(Cursor::new(tone_wav()), &args).unwrap();
+    let mut svg = String::new();
+
+    {
+      let area = SVGBackend::with_string(&mut svg, (640, 320)).into_drawing_area();
+      spectrogram_plot(&spectrogram, &args).draw(&area, &spectrogram.power).unwrap();
+      area.present().unwrap();
+    }
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("Spectrogram of tone.wav"));
+    assert!(svg.contains("Time (s)"));
+    assert!(svg.contains("Frequency (Hz)"));
+  }
+}
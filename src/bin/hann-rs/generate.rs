@@ -0,0 +1,134 @@
+use std::{ error::Error, io::Write, path::PathBuf };
+
+use clap::{ Args, ValueEnum };
+use hann_rs::io::{ export_window, write_npy, write_rust_const, CArrayGenerator, ExportFormat, NpyArray };
+
+use crate::{ open_output, WindowArgs };
+
+/// The output formats of the coefficients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GenFormat {
+  /// An index,value CSV table.
+  Csv,
+  /// A JSON array.
+  Json,
+  /// A NumPy .npy file.
+  Npy,
+  /// Raw 32-bit little-endian floats.
+  F32le,
+  /// A Rust constant array.
+  Rust,
+  /// A C constant array.
+  C,
+}
+
+/// The arguments of the gen subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct GenArgs {
+  #[command(flatten)]
+  pub window: WindowArgs,
+  /// The number of coefficients.
+  #[arg(long = "len")]
+  pub length: usize,
+  /// The output format.
+  #[arg(long, value_enum, default_value = "csv")]
+  pub format: GenFormat,
+  /// The number of digits after the decimal point, the shortest exact representation by default.
+  #[arg(long)]
+  pub precision: Option<usize>,
+  /// The name of the generated Rust or C array, used as given. By default the window name and the
+  /// length, e.g. `HANN_1024` for Rust and `hann_1024` for C.
+  #[arg(long)]
+  pub name: Option<String>,
+  /// The output file, the standard output by default.
+  #[arg(short, long)]
+  pub output: Option<PathBuf>,
+}
+
+// Generate the window and write it to the output
+pub fn run(args: &GenArgs) -> Result<(), Box<dyn Error>> {
+  let window = args.window.spec().generate(args.length)?;
+  let mut output = open_output(args.output.as_ref())?;
+  write_window(&window, args, &mut output)?;
+  output.flush()?;
+  Ok(())
+}
+
+// Write the coefficients in the format of the arguments
+fn write_window<W: Write>(window: &[f32], args: &GenArgs, output: &mut W) -> Result<(), Box<dyn Error>> {
+  // An explicit name is used as given, only the default name follows the case of the language
+  let default_name = format!("{}_{}", args.window.window.replace('-', "_"), window.len());
+  match args.format {
+    GenFormat::Csv => export_window(window, ExportFormat::Csv { precision: args.precision }, output)?,
+    GenFormat::Json => export_window(window, ExportFormat::Json { precision: args.precision }, output)?,
+    GenFormat::Npy => write_npy(output, &NpyArray::from_window(window.to_vec()))?,
+    GenFormat::F32le => {
+      let bytes: Vec<u8> = window.iter().flat_map(|value| value.to_le_bytes()).collect();
+      output.write_all(&bytes)?;
+    }
+    GenFormat::Rust => {
+      let name = args.name.clone().unwrap_or_else(|| default_name.to_uppercase());
+      write_rust_const(&name, window, output)?;
+    }
+    GenFormat::C => {
+      let name = args.name.clone().unwrap_or_else(|| default_name.to_lowercase());
+      let mut generator = CArrayGenerator::new(&name)?;
+      if let Some(precision) = args.precision {
+        generator = generator.with_precision(precision);
+      }
+      generator.write(window, output)?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_generate {
+  use clap::Parser;
+
+  use crate::{ Cli, Command };
+
+  use super::*;
+
+  // Run the gen subcommand with `arguments` into a buffer
+  fn generate(arguments: &[&str]) -> Vec<u8> {
+    let Command::Gen(args) = Cli::parse_from(["hann-rs", "gen"].iter().chain(arguments)).command else {
+      panic!("Expected the gen subcommand")
+    };
+    let window = args.window.spec().generate(args.length).unwrap();
+    let mut output = Vec::new();
+    write_window(&window, &args, &mut output).unwrap();
+    output
+  }
+
+  #[test]
+  fn test_gen_formats() {
+    assert_eq!(generate(&["--len", "3", "--format", "json"]), b"[0, 1, 0]\n");
+    assert_eq!(generate(&["--len", "3", "--format", "f32le"]), [0.0f32, 1.0, 0.0].iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>());
+    assert_eq!(generate(&["--len", "3", "--format", "rust"]), b"pub const HANN_3: [f32; 3] = [\n  0.0, 1.0, 0.0,\n];\n");
+    assert_eq!(generate(&["--len", "3", "--format", "c", "--precision", "1", "--name", "w"]), b"const float w[3] = {\n  0.0f, 1.0f, 0.0f,\n};\n");
+  }
+
+  #[test]
+  fn test_gen_names() {
+    // An explicit name keeps its case, the default name takes the case of the language
+    assert!(generate(&["--len", "3", "--format", "rust", "--name", "myWindow"]).starts_with(b"pub const myWindow: [f32; 3]"));
+    assert!(generate(&["--len", "3", "--format", "c", "--name", "myWindow"]).starts_with(b"const float myWindow[3]"));
+    assert!(generate(&["--len", "3", "--format", "c", "--window", "power-of-cosine"]).starts_with(b"const float power_of_cosine_3[3]"));
+  }
+
+  #[test]
+  fn test_gen_invalid_names() {
+    for format in ["rust", "c"] {
+      let Command::Gen(args) = Cli::parse_from(["hann-rs", "gen", "--len", "3", "--format", format, "--name", "my-window"]).command else {
+        panic!("Expected the gen subcommand")
+      };
+      let mut output = Vec::new();
+
+      let error = write_window(&[0.0, 1.0, 0.0], &args, &mut output).unwrap_err();
+
+      assert!(error.to_string().contains("Array name must be an identifier"));
+      assert!(output.is_empty());
+    }
+  }
+}
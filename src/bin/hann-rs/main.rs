@@ -0,0 +1,131 @@
+//! The `hann-rs` command line tool, built with the `cli` feature.
+
+use std::{ error::Error, fs::File, io::{ self, BufWriter, Write }, path::PathBuf, process::ExitCode };
+
+use clap::{ builder::PossibleValuesParser, Args, Parser, Subcommand };
+use hann_rs::windows::{ WINDOW_NAMES, WindowSpec };
+
+mod analyze;
+mod generate;
+mod metrics;
+mod plot;
+
+/// Generate and analyze window functions.
+#[derive(Debug, Parser)]
+#[command(name = "hann-rs", version)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Render the spectrogram of a WAV file.
+  Analyze(analyze::AnalyzeArgs),
+  /// Write the coefficients of a window.
+  Gen(generate::GenArgs),
+  /// Print the figures of merit of windows.
+  Metrics(metrics::MetricsArgs),
+  /// Plot the shapes and the magnitude responses of windows.
+  Plot(plot::PlotArgs),
+}
+
+/// The selection of a window and its shape parameters.
+#[derive(Debug, Clone, Args)]
+struct WindowArgs {
+  /// The window function.
+  #[arg(long, value_parser = PossibleValuesParser::new(WINDOW_NAMES), default_value = "hann")]
+  window: String,
+  /// The shape parameter of the Kaiser window.
+  #[arg(long, default_value_t = 8.6)]
+  beta: f32,
+  /// The shape parameter of the Gaussian window, relative to half the window length.
+  #[arg(long, default_value_t = 0.4)]
+  sigma: f32,
+  /// The fraction of the Tukey window that is tapered.
+  #[arg(long, default_value_t = 0.5)]
+  alpha: f32,
+  /// The fraction of the Planck-taper window tapered at either end.
+  #[arg(long, default_value_t = 0.1)]
+  epsilon: f32,
+  /// The exponent of the power-of-cosine window.
+  #[arg(long, default_value_t = 2.0)]
+  exponent: f32,
+}
+
+impl WindowArgs {
+  // The window spec selected by the arguments, with the shape parameter of its own option
+  fn spec(&self) -> WindowSpec {
+    match WindowSpec::from_name(&self.window, None).expect("The window is one of WINDOW_NAMES") {
+      WindowSpec::Kaiser { .. } => WindowSpec::Kaiser { beta: self.beta },
+      WindowSpec::Gaussian { .. } => WindowSpec::Gaussian { sigma: self.sigma },
+      WindowSpec::Tukey { .. } => WindowSpec::Tukey { alpha: self.alpha },
+      WindowSpec::PlanckTaper { .. } => WindowSpec::PlanckTaper { epsilon: self.epsilon },
+      WindowSpec::PowerOfCosine { .. } => WindowSpec::PowerOfCosine { alpha: self.exponent },
+      spec => spec,
+    }
+  }
+}
+
+// Parse a window of the command line, "name" or "name:parameter", keeping the text as its label
+fn parse_window(value: &str) -> Result<(String, WindowSpec), String> {
+  let spec = value.parse::<WindowSpec>().map_err(|error| format!("invalid window {}: {}", value, error))?;
+  Ok((value.to_string(), spec))
+}
+
+// Open the output file, or the standard output if there is none
+fn open_output(output: Option<&PathBuf>) -> io::Result<Box<dyn Write>> {
+  Ok(match output {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+    None => Box::new(BufWriter::new(io::stdout().lock())),
+  })
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let result: Result<(), Box<dyn Error>> = match &cli.command {
+    Command::Analyze(args) => analyze::run(args),
+    Command::Gen(args) => generate::run(args),
+    Command::Metrics(args) => metrics::run(args),
+    Command::Plot(args) => plot::run(args),
+  };
+
+  // Report errors on the standard error
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("error: {}", error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_cli {
+  use clap::CommandFactory;
+
+  use super::*;
+
+  #[test]
+  fn test_cli_definition() {
+    Cli::command().debug_assert();
+  }
+
+  #[test]
+  fn test_window_args() {
+    let cli = Cli::parse_from(["hann-rs", "gen", "--window", "kaiser", "--beta", "5", "--len", "16"]);
+
+    let Command::Gen(args) = cli.command else { panic!("Expected the gen subcommand") };
+    assert_eq!(args.window.spec(), WindowSpec::Kaiser { beta: 5.0 });
+  }
+
+  #[test]
+  fn test_parse_window() {
+    assert_eq!(parse_window("kaiser:5").unwrap(), (String::from("kaiser:5"), WindowSpec::Kaiser { beta: 5.0 }));
+    assert_eq!(parse_window("hamming").unwrap().1, WindowSpec::Hamming);
+    assert_eq!(parse_window("planck-taper:0.2").unwrap().1, WindowSpec::PlanckTaper { epsilon: 0.2 });
+    assert_eq!(parse_window("power-of-cosine:1.5").unwrap().1, WindowSpec::PowerOfCosine { alpha: 1.5 });
+    assert!(parse_window("kaiser:x").is_err());
+    assert!(parse_window("hanning").is_err());
+  }
+}
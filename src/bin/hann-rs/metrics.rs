@@ -0,0 +1,164 @@
+use std::{ error::Error, io::Write, path::PathBuf };
+
+use clap::{ Args, ValueEnum };
+use hann_rs::{ metrics::{ window_metrics, WindowMetrics }, windows::{ WINDOW_NAMES, WindowSpec } };
+
+use crate::{ open_output, parse_window };
+
+/// The output formats of the metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MetricsFormat {
+  /// An aligned text table.
+  Table,
+  /// A CSV table.
+  Csv,
+  /// A JSON array of objects.
+  Json,
+}
+
+/// The arguments of the metrics subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct MetricsArgs {
+  /// A window to measure, "name" or "name:parameter", e.g. "kaiser:8.6". May be repeated, and every
+  /// window with its default parameter by default.
+  #[arg(long = "window", value_parser = parse_window, default_values = WINDOW_NAMES)]
+  pub windows: Vec<(String, WindowSpec)>,
+  /// The number of coefficients of every window.
+  #[arg(long = "len", default_value_t = 1024)]
+  pub length: usize,
+  /// The output format.
+  #[arg(long, value_enum, default_value = "table")]
+  pub format: MetricsFormat,
+  /// The output file, the standard output by default.
+  #[arg(short, long)]
+  pub output: Option<PathBuf>,
+}
+
+// The columns of the metrics, with their units
+const COLUMNS: [&str; 6] = [
+  "window",
+  "coherent_gain",
+  "enbw_bins",
+  "scalloping_loss_db",
+  "sidelobe_level_db",
+  "recommended_overlap",
+];
+
+// Measure the windows and write their metrics to the output
+pub fn run(args: &MetricsArgs) -> Result<(), Box<dyn Error>> {
+  let mut rows = Vec::with_capacity(args.windows.len());
+  for (label, spec) in &args.windows {
+    rows.push((label.clone(), window_metrics(&spec.generate(args.length)?)?));
+  }
+
+  let mut output = open_output(args.output.as_ref())?;
+  write_metrics(&rows, args.format, &mut output)?;
+  output.flush()?;
+  Ok(())
+}
+
+// Write the metrics of every window in the format
+fn write_metrics<W: Write>(rows: &[(String, WindowMetrics)], format: MetricsFormat, output: &mut W) -> Result<(), Box<dyn Error>> {
+  let cells: Vec<[String; 6]> = rows.iter().map(|(label, metrics)| format_row(label, metrics)).collect();
+  match format {
+    MetricsFormat::Table => {
+      // Pad every column to its widest cell, the labels to the left and the numbers to the right
+      let widths: Vec<usize> = (0..COLUMNS.len())
+        .map(|column| cells.iter().map(|row| row[column].len()).chain([COLUMNS[column].len()]).max().unwrap_or(0))
+        .collect();
+      let header: Vec<String> = COLUMNS.iter().zip(&widths).map(|(name, &width)| format!("{:<width$}", name)).collect();
+      writeln!(output, "{}", header.join("  ").trim_end())?;
+      for row in &cells {
+        let line: Vec<String> = row
+          .iter()
+          .zip(&widths)
+          .enumerate()
+          .map(|(column, (cell, &width))| if column == 0 { format!("{:<width$}", cell) } else { format!("{:>width$}", cell) })
+          .collect();
+        writeln!(output, "{}", line.join("  ").trim_end())?;
+      }
+    }
+    MetricsFormat::Csv => {
+      writeln!(output, "{}", COLUMNS.join(","))?;
+      for row in &cells {
+        writeln!(output, "{}", row.join(","))?;
+      }
+    }
+    MetricsFormat::Json => {
+      // Non-finite levels, e.g. of windows without sidelobes, have no JSON representation
+      let objects: Vec<String> = cells
+        .iter()
+        .map(|row| {
+          let fields: Vec<String> = COLUMNS
+            .iter()
+            .zip(row)
+            .enumerate()
+            .map(|(column, (name, cell))| match column {
+              0 => format!("\"{}\": \"{}\"", name, cell),
+              _ if cell.parse::<f32>().is_ok_and(f32::is_finite) => format!("\"{}\": {}", name, cell),
+              _ => format!("\"{}\": null", name),
+            })
+            .collect();
+          format!("{{{}}}", fields.join(", "))
+        })
+        .collect();
+      writeln!(output, "[{}]", objects.join(", "))?;
+    }
+  }
+  Ok(())
+}
+
+// Format the label and the metrics of a window with a fixed number of decimals
+fn format_row(label: &str, metrics: &WindowMetrics) -> [String; 6] {
+  [
+    label.to_string(),
+    format!("{:.4}", metrics.coherent_gain),
+    format!("{:.4}", metrics.enbw),
+    format!("{:.2}", metrics.scalloping_loss),
+    format!("{:.1}", metrics.sidelobe_level),
+    format!("{:.3}", metrics.recommended_overlap),
+  ]
+}
+
+#[cfg(test)]
+mod test_metrics {
+  use super::*;
+
+  // The metrics of the Hann and the rectangular windows
+  fn rows() -> Vec<(String, WindowMetrics)> {
+    ["hann", "rectangular"]
+      .iter()
+      .map(|name| (name.to_string(), window_metrics(&parse_window(name).unwrap().1.generate(1024).unwrap()).unwrap()))
+      .collect()
+  }
+
+  // Write the rows in a format to a string
+  fn write(format: MetricsFormat) -> String {
+    let mut output = Vec::new();
+    write_metrics(&rows(), format, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+  }
+
+  #[test]
+  fn test_metrics_csv() {
+    assert_eq!(
+      write(MetricsFormat::Csv),
+      "window,coherent_gain,enbw_bins,scalloping_loss_db,sidelobe_level_db,recommended_overlap\n\
+       hann,0.4995,1.5015,1.42,-31.5,0.500\n\
+       rectangular,1.0000,1.0000,3.92,-13.3,0.000\n"
+    );
+  }
+
+  #[test]
+  fn test_metrics_table_and_json() {
+    let table = write(MetricsFormat::Table);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].split_whitespace().collect::<Vec<&str>>(), ["hann", "0.4995", "1.5015", "1.42", "-31.5", "0.500"]);
+    assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+
+    let json = write(MetricsFormat::Json);
+    assert!(json.starts_with("[{\"window\": \"hann\", \"coherent_gain\": 0.4995, "));
+    assert!(json.ends_with("\"recommended_overlap\": 0.000}]\n"));
+  }
+}
@@ -0,0 +1,78 @@
+use std::{ error::Error, path::PathBuf };
+
+use clap::Args;
+use hann_rs::{ io::{ draw_response_chart, draw_window_chart }, windows::WindowSpec };
+use plotters::{ coord::Shift, prelude::* };
+
+use crate::parse_window;
+
+/// The arguments of the plot subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct PlotArgs {
+  /// A window to plot, "name" or "name:parameter", e.g. "kaiser:8.6". May be repeated.
+  #[arg(long = "window", value_parser = parse_window, default_value = "hann")]
+  pub windows: Vec<(String, WindowSpec)>,
+  /// The number of coefficients of every window.
+  #[arg(long = "len", default_value_t = 64)]
+  pub length: usize,
+  /// The FFT length of the response, 16 times the window length by default.
+  #[arg(long = "fft-len")]
+  pub fft_length: Option<usize>,
+  /// The width and height of the image, in pixels.
+  #[arg(long, num_args = 2, value_names = ["WIDTH", "HEIGHT"], default_values_t = [1024, 768])]
+  pub size: Vec<u32>,
+  /// The image file, an SVG file if its extension is "svg" and a PNG file otherwise.
+  #[arg(short, long)]
+  pub output: PathBuf,
+}
+
+// Generate the windows and render their shapes and responses to the image file
+pub fn run(args: &PlotArgs) -> Result<(), Box<dyn Error>> {
+  let fft_length = args.fft_length.unwrap_or(16 * args.length);
+  let mut curves = Vec::with_capacity(args.windows.len());
+  for (label, spec) in &args.windows {
+    curves.push((label.clone(), spec.generate(args.length)?));
+  }
+
+  let size = (args.size[0], args.size[1]);
+  if args.output.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) {
+    draw(SVGBackend::new(&args.output, size).into_drawing_area(), &curves, fft_length)
+  } else {
+    draw(BitMapBackend::new(&args.output, size).into_drawing_area(), &curves, fft_length)
+  }
+}
+
+// Draw the shapes of the windows above their magnitude responses
+fn draw<DB>(root: DrawingArea<DB, Shift>, curves: &[(String, Vec<f32>)], fft_length: usize) -> Result<(), Box<dyn Error>>
+where
+  DB: DrawingBackend,
+  DB::ErrorType: 'static,
+{
+  root.fill(&WHITE)?;
+  let (upper, lower) = root.split_vertically(root.dim_in_pixel().1 / 2);
+  let windows: Vec<(&str, &[f32])> = curves.iter().map(|(label, window)| (label.as_str(), window.as_slice())).collect();
+
+  draw_window_chart(&upper, &windows)?;
+  draw_response_chart(&lower, &windows, fft_length)?;
+
+  root.present()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_plot {
+  use super::*;
+
+  #[test]
+  fn test_draw_svg() {
+    let hann = WindowSpec::Hann.generate(32).unwrap();
+    let curves = vec![(String::from("hann"), hann)];
+    let mut svg = String::new();
+
+    draw(SVGBackend::with_string(&mut svg, (640, 480)).into_drawing_area(), &curves, 512).unwrap();
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("Magnitude response"));
+    assert!(svg.contains("hann"));
+  }
+}
@@ -0,0 +1,256 @@
+use std::{ error::Error, fmt, sync::{ atomic::{ AtomicUsize, Ordering }, Arc } };
+
+use cpal::{
+  traits::{ DeviceTrait, HostTrait, StreamTrait },
+  Device,
+  FromSample,
+  Sample,
+  SizedSample,
+  Stream,
+  StreamConfig,
+};
+
+use crate::{
+  downmix::{ downmix, Downmix },
+  frame_queue::{ frame_queue, FrameConsumer, FrameProducer },
+  hann_window::HannWindowError,
+  instrument::trace_event,
+  streaming_windower::StreamingWindower,
+};
+
+// The number of sample frames converted at once in the capture callback
+const CAPTURE_CHUNK_FRAMES: usize = 1024;
+
+/// Error type for the live capture front-end.
+#[derive(Debug)]
+pub enum CaptureError {
+  NoInputDevice,
+  DefaultStreamConfig(cpal::DefaultStreamConfigError),
+  UnsupportedSampleFormat(cpal::SampleFormat),
+  BuildStream(cpal::BuildStreamError),
+  PlayStream(cpal::PlayStreamError),
+  Windower(HannWindowError),
+}
+
+// Implement the Error trait for the CaptureError enum
+impl Error for CaptureError {}
+
+// Implement the Display trait for the CaptureError enum
+impl fmt::Display for CaptureError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      CaptureError::NoInputDevice => {
+        write!(f, "CaptureError: No input device is available.")
+      }
+      CaptureError::DefaultStreamConfig(error) => {
+        write!(f, "CaptureError: Failed to get the input configuration: {}", error)
+      }
+      CaptureError::UnsupportedSampleFormat(format) => {
+        write!(f, "CaptureError: Sample format {} is not supported.", format)
+      }
+      CaptureError::BuildStream(error) => {
+        write!(f, "CaptureError: Failed to build the input stream: {}", error)
+      }
+      CaptureError::PlayStream(error) => {
+        write!(f, "CaptureError: Failed to start the input stream: {}", error)
+      }
+      CaptureError::Windower(error) => {
+        write!(f, "CaptureError: {}", error)
+      }
+    }
+  }
+}
+
+/// Live windowed frames from an audio input device.
+///
+/// The capture callback converts every device sample format to `f32`, reduces the channels to one as
+/// described by a [`Downmix`], cuts the samples into frames with a [`StreamingWindower`] and pushes the
+/// frames onto a [`frame_queue`]. The callback never allocates or blocks: frames are dropped when the
+/// queue is full, see [`FrameConsumer::dropped_frames`]. The device keeps capturing until the
+/// `LiveCapture` is dropped.
+pub struct LiveCapture {
+  // The stream stops when dropped
+  _stream: Stream,
+  frames: FrameConsumer,
+  sample_rate: u32,
+  channel_count: u16,
+  stream_errors: Arc<AtomicUsize>,
+}
+
+impl LiveCapture {
+  /// Start capturing from the default input device of the default host.
+  pub fn start_default(
+    windower: StreamingWindower,
+    downmix: Downmix,
+    queue_capacity: usize
+  ) -> Result<Self, CaptureError> {
+    let device = cpal::default_host().default_input_device().ok_or(CaptureError::NoInputDevice)?;
+    LiveCapture::start(&device, windower, downmix, queue_capacity)
+  }
+
+  /// Start capturing from `device` with its default input configuration.
+  ///
+  /// Frames are framed by `windower` and buffered in a queue of `queue_capacity` frames. An error is
+  /// returned if the device has no usable input configuration, if the selected downmix channel does
+  /// not exist, or if the queue capacity is 0.
+  pub fn start(
+    device: &Device,
+    windower: StreamingWindower,
+    downmix: Downmix,
+    queue_capacity: usize
+  ) -> Result<Self, CaptureError> {
+    let supported_config = device.default_input_config().map_err(CaptureError::DefaultStreamConfig)?;
+    let sample_format = supported_config.sample_format();
+    let config: StreamConfig = supported_config.into();
+    let channel_count = usize::from(config.channels);
+
+    // The selected channel must exist
+    if let Downmix::Channel(channel) = downmix {
+      if channel >= channel_count {
+        return Err(CaptureError::Windower(HannWindowError::InvalidChannelCount));
+      }
+    }
+
+    let (producer, consumer) = frame_queue(queue_capacity, windower.frame_length()).map_err(
+      CaptureError::Windower
+    )?;
+    // Allocate the conversion buffers up front, the callback must not allocate
+    let state = CaptureState {
+      windower,
+      downmix,
+      channel_count,
+      interleaved: vec![0.0; CAPTURE_CHUNK_FRAMES * channel_count],
+      mono: vec![0.0; CAPTURE_CHUNK_FRAMES],
+      producer,
+    };
+    let stream_errors = Arc::new(AtomicUsize::new(0));
+
+    // Build the stream for the native sample format of the device
+    let stream = match sample_format {
+      cpal::SampleFormat::I8 => build_stream::<i8>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::I16 => build_stream::<i16>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::I32 => build_stream::<i32>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::I64 => build_stream::<i64>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::U8 => build_stream::<u8>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::U16 => build_stream::<u16>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::U32 => build_stream::<u32>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::U64 => build_stream::<u64>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::F32 => build_stream::<f32>(device, &config, state, stream_errors.clone()),
+      cpal::SampleFormat::F64 => build_stream::<f64>(device, &config, state, stream_errors.clone()),
+      format => {
+        return Err(CaptureError::UnsupportedSampleFormat(format));
+      }
+    }.map_err(CaptureError::BuildStream)?;
+    stream.play().map_err(CaptureError::PlayStream)?;
+
+    Ok(LiveCapture {
+      _stream: stream,
+      frames: consumer,
+      sample_rate: config.sample_rate.0,
+      channel_count: config.channels,
+      stream_errors,
+    })
+  }
+
+  /// The queue of captured windowed frames.
+  pub fn frames(&mut self) -> &mut FrameConsumer {
+    &mut self.frames
+  }
+
+  /// The sample rate of the device in Hz.
+  pub fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  /// The number of channels captured from the device, before the downmix.
+  pub fn channel_count(&self) -> u16 {
+    self.channel_count
+  }
+
+  /// The number of errors reported by the device since the capture started.
+  pub fn stream_errors(&self) -> usize {
+    self.stream_errors.load(Ordering::Relaxed)
+  }
+}
+
+// Build an input stream delivering samples of type `T` to the capture state
+fn build_stream<T>(
+  device: &Device,
+  config: &StreamConfig,
+  mut state: CaptureState,
+  stream_errors: Arc<AtomicUsize>
+) -> Result<Stream, cpal::BuildStreamError>
+  where T: SizedSample, f32: FromSample<T>
+{
+  device.build_input_stream(
+    config,
+    move |data: &[T], _: &cpal::InputCallbackInfo| state.process(data),
+    move |_error| {
+      trace_event!(WARN, error = %_error, "input stream error");
+      stream_errors.fetch_add(1, Ordering::Relaxed);
+    },
+    None
+  )
+}
+
+// The state moved into the capture callback
+struct CaptureState {
+  windower: StreamingWindower,
+  downmix: Downmix,
+  channel_count: usize,
+  interleaved: Vec<f32>,
+  mono: Vec<f32>,
+  producer: FrameProducer,
+}
+
+impl CaptureState {
+  // Convert, downmix and frame one callback buffer of interleaved device samples
+  fn process<T>(&mut self, data: &[T]) where T: Sample, f32: FromSample<T> {
+    for chunk in data.chunks(self.interleaved.len()) {
+      // Convert the device samples to `f32`, ignoring an incomplete trailing sample frame
+      let sample_frames = chunk.len() / self.channel_count;
+      let interleaved = &mut self.interleaved[..sample_frames * self.channel_count];
+      for (sample, device_sample) in interleaved.iter_mut().zip(chunk) {
+        *sample = f32::from_sample(*device_sample);
+      }
+
+      let mono = &mut self.mono[..sample_frames];
+      downmix(interleaved, self.channel_count, self.downmix, mono).expect("Buffers hold whole sample frames");
+
+      // Frames are dropped by the queue when the consumer falls behind
+      let producer = &mut self.producer;
+      self.windower.process(mono, |frame| {
+        producer.push(frame).expect("Queue slots match the frame length");
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_capture {
+  use super::*;
+
+  #[test]
+  fn test_capture_state_converts_and_downmixes() {
+    let (producer, mut consumer) = frame_queue(4, 2).unwrap();
+    // Chunks of two stereo sample frames, to exercise the chunked conversion
+    let mut state = CaptureState {
+      windower: StreamingWindower::new(2, 2).unwrap(),
+      downmix: Downmix::Average,
+      channel_count: 2,
+      interleaved: vec![0.0; 4],
+      mono: vec![0.0; 2],
+      producer,
+    };
+
+    state.process(&[16384i16, 0, -16384, -16384, 0, 16384, 8192, 8192]);
+
+    let mut frame = [0.0; 2];
+    assert!(consumer.pop(&mut frame).unwrap());
+    assert_eq!(frame, [0.25, -0.5]);
+    assert!(consumer.pop(&mut frame).unwrap());
+    assert_eq!(frame, [0.25, 0.25]);
+    assert!(consumer.is_empty());
+  }
+}
@@ -0,0 +1,328 @@
+use std::io::{ self, Write };
+
+use crate::hann_window::HannWindowError;
+
+// The keywords of C11 and C23, which cannot name an array
+const C_KEYWORDS: [&str; 59] = [
+  "alignas", "alignof", "auto", "bool", "break", "case", "char", "const", "constexpr", "continue", "default", "do",
+  "double", "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long", "nullptr",
+  "register", "restrict", "return", "short", "signed", "sizeof", "static", "static_assert", "struct", "switch",
+  "thread_local", "true", "typedef", "typeof", "typeof_unqual", "union", "unsigned", "void", "volatile", "while",
+  "_Alignas", "_Alignof", "_Atomic", "_BitInt", "_Bool", "_Complex", "_Decimal128", "_Decimal32", "_Decimal64",
+  "_Generic", "_Imaginary", "_Noreturn", "_Static_assert", "_Thread_local",
+];
+
+// The strict and reserved keywords of Rust up to the 2024 edition, and the lone underscore, which
+// cannot name a constant
+const RUST_KEYWORDS: [&str; 53] = [
+  "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn",
+  "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+  "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self", "static", "struct",
+  "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// The element type of a generated C array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CElementType {
+  /// `float` values.
+  Float,
+  /// `q15_t` values, the signed 16-bit fixed point format of CMSIS-DSP: the coefficients are scaled by
+  /// 32768, rounded and saturated to [-32768, 32767].
+  Q15,
+}
+
+/// A generator of C source code defining window coefficients as a constant array.
+///
+/// The array is defined as `<qualifiers> const <type> <name>[<length>] <attributes> = { ... };`, for
+/// firmware that cannot compute windows at runtime. The qualifiers, e.g. `static`, and the GCC/Clang
+/// section and alignment attributes are optional. With [`CElementType::Q15`] the code expects `q15_t`
+/// to be defined, e.g. by `arm_math.h` or `typedef int16_t q15_t;`. Coefficients that are not finite
+/// are written as the `NAN` and `INFINITY` macros of `math.h` in `float` arrays, and saturated in
+/// `q15_t` arrays, NaN becoming 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CArrayGenerator {
+  name: String,
+  element_type: CElementType,
+  precision: Option<usize>,
+  qualifiers: Option<String>,
+  section: Option<String>,
+  alignment: Option<usize>,
+  values_per_line: usize,
+}
+
+impl CArrayGenerator {
+  /// Create a generator of a `float` array called `name`, with the shortest exact representation of
+  /// every coefficient and 8 coefficients per line.
+  ///
+  /// An error is returned if the name is not a C identifier, letters, digits and underscores not
+  /// starting with a digit, or if it is a keyword.
+  pub fn new(name: &str) -> Result<Self, HannWindowError> {
+    check_identifier(name, &C_KEYWORDS)?;
+
+    Ok(CArrayGenerator {
+      name: name.to_string(),
+      element_type: CElementType::Float,
+      precision: None,
+      qualifiers: None,
+      section: None,
+      alignment: None,
+      values_per_line: 8,
+    })
+  }
+
+  /// Use `element_type` for the values of the array.
+  pub fn with_element_type(mut self, element_type: CElementType) -> Self {
+    self.element_type = element_type;
+    self
+  }
+
+  /// Write `float` coefficients with `precision` digits after the decimal point.
+  pub fn with_precision(mut self, precision: usize) -> Self {
+    self.precision = Some(precision);
+    self
+  }
+
+  /// Prefix the definition with `qualifiers`, e.g. `static`.
+  pub fn with_qualifiers(mut self, qualifiers: &str) -> Self {
+    self.qualifiers = Some(qualifiers.to_string());
+    self
+  }
+
+  /// Place the array in the linker section `section`, e.g. `.rodata.windows`.
+  pub fn with_section(mut self, section: &str) -> Self {
+    self.section = Some(section.to_string());
+    self
+  }
+
+  /// Align the array to `alignment` bytes.
+  pub fn with_alignment(mut self, alignment: usize) -> Self {
+    self.alignment = Some(alignment);
+    self
+  }
+
+  /// Write `values_per_line` coefficients per line, at least 1.
+  pub fn with_values_per_line(mut self, values_per_line: usize) -> Self {
+    self.values_per_line = values_per_line.max(1);
+    self
+  }
+
+  /// Write the definition of the array holding `window` to `writer`.
+  pub fn write<W: Write>(&self, window: &[f32], mut writer: W) -> io::Result<()> {
+    // The declarator, with the qualifiers and the attributes
+    let element_type = match self.element_type {
+      CElementType::Float => "float",
+      CElementType::Q15 => "q15_t",
+    };
+    if let Some(qualifiers) = &self.qualifiers {
+      write!(writer, "{} ", qualifiers)?;
+    }
+    write!(writer, "const {} {}[{}]", element_type, self.name, window.len())?;
+    if let Some(section) = &self.section {
+      write!(writer, " __attribute__((section(\"{}\")))", section)?;
+    }
+    if let Some(alignment) = self.alignment {
+      write!(writer, " __attribute__((aligned({})))", alignment)?;
+    }
+    writeln!(writer, " = {{")?;
+
+    // The coefficients, a fixed number per line
+    for line in window.chunks(self.values_per_line) {
+      let values: Vec<String> = line.iter().map(|&value| self.format_value(value)).collect();
+      writeln!(writer, "  {},", values.join(", "))?;
+    }
+    writeln!(writer, "}};")?;
+
+    writer.flush()
+  }
+
+  /// Return the definition of the array holding `window`.
+  pub fn generate(&self, window: &[f32]) -> String {
+    let mut source = Vec::new();
+    self.write(window, &mut source).expect("Writing to a Vec does not fail");
+    String::from_utf8(source).expect("The source is valid UTF-8")
+  }
+
+  // Format a coefficient as a C literal of the element type
+  fn format_value(&self, value: f32) -> String {
+    match (self.element_type, self.precision) {
+      (CElementType::Q15, _) => to_q15(value).to_string(),
+      // The literals cannot express the values that are not finite, the macros of `math.h` do
+      (CElementType::Float, _) if value.is_nan() => "NAN".to_string(),
+      (CElementType::Float, _) if value.is_infinite() => (if value > 0.0 { "INFINITY" } else { "-INFINITY" }).to_string(),
+      // A literal without a decimal point needs one before the suffix
+      (CElementType::Float, Some(0)) => format!("{:.0}.f", value),
+      (CElementType::Float, Some(precision)) => format!("{:.*}f", precision, value),
+      (CElementType::Float, None) => format!("{:?}f", value),
+    }
+  }
+}
+
+/// Write Rust source defining window coefficients as a constant array to `writer`.
+///
+/// The array is defined as `pub const <name>: [f32; <length>] = [ ... ];`, so that embedded projects can
+/// include windows as `const` data, e.g. with `include!`, without depending on this crate. Every
+/// coefficient is written with the shortest literal that reads back to the same `f32`, 8 per line,
+/// and the coefficients that are not finite as `f32::NAN`, `f32::INFINITY` and `f32::NEG_INFINITY`.
+/// An error of the kind [`io::ErrorKind::InvalidInput`] wrapping
+/// [`HannWindowError::InvalidIdentifier`] is returned, and nothing is written, if the name is not a
+/// Rust identifier, letters, digits and underscores not starting with a digit, or if it is a keyword.
+pub fn write_rust_const<W: Write>(name: &str, window: &[f32], mut writer: W) -> io::Result<()> {
+  check_identifier(name, &RUST_KEYWORDS).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+  writeln!(writer, "pub const {}: [f32; {}] = [", name, window.len())?;
+  for line in window.chunks(8) {
+    let values: Vec<String> = line.iter().map(|&value| rust_literal(value)).collect();
+    writeln!(writer, "  {},", values.join(", "))?;
+  }
+  writeln!(writer, "];")?;
+
+  writer.flush()
+}
+
+/// Return Rust source defining window coefficients as a constant array, see [`write_rust_const`].
+///
+/// An error is returned if the name is not a Rust identifier or if it is a keyword.
+pub fn rust_const(name: &str, window: &[f32]) -> Result<String, HannWindowError> {
+  check_identifier(name, &RUST_KEYWORDS)?;
+  let mut source = Vec::new();
+  write_rust_const(name, window, &mut source).expect("Writing a checked name to a Vec does not fail");
+  Ok(String::from_utf8(source).expect("The source is valid UTF-8"))
+}
+
+// Check that a name is an ASCII identifier, letters, digits and underscores not starting with a digit,
+// other than one of the keywords
+fn check_identifier(name: &str, keywords: &[&str]) -> Result<(), HannWindowError> {
+  let mut characters = name.chars();
+  let is_identifier = characters.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+    && characters.all(|character| character.is_ascii_alphanumeric() || character == '_');
+  if !is_identifier || keywords.contains(&name) {
+    return Err(HannWindowError::InvalidIdentifier);
+  }
+  Ok(())
+}
+
+// Format a coefficient as a Rust `f32` expression, the literals cannot express the values that are
+// not finite, the constants of `f32` do
+fn rust_literal(value: f32) -> String {
+  if value.is_nan() {
+    "f32::NAN".to_string()
+  } else if value.is_infinite() {
+    (if value > 0.0 { "f32::INFINITY" } else { "f32::NEG_INFINITY" }).to_string()
+  } else {
+    format!("{:?}", value)
+  }
+}
+
+// Convert a coefficient to Q15, saturating values outside of [-1, 1)
+fn to_q15(value: f32) -> i16 {
+  (value * 32768.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+#[cfg(test)]
+mod test_codegen {
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_c_array_float() {
+    let generator = CArrayGenerator::new("hann_5").unwrap().with_precision(3).with_values_per_line(3);
+
+    let source = generator.generate(&get_hann_window(5).unwrap());
+
+    assert_eq!(source, "const float hann_5[5] = {\n  0.000f, 0.500f, 1.000f,\n  0.500f, 0.000f,\n};\n");
+  }
+
+  #[test]
+  fn test_c_array_q15_attributes() {
+    let generator = CArrayGenerator::new("hann_q15")
+      .unwrap()
+      .with_element_type(CElementType::Q15)
+      .with_qualifiers("static")
+      .with_section(".rodata.windows")
+      .with_alignment(4);
+
+    let source = generator.generate(&[-1.5, -1.0, 0.0, 0.5, 1.0]);
+
+    assert_eq!(
+      source,
+      "static const q15_t hann_q15[5] __attribute__((section(\".rodata.windows\"))) __attribute__((aligned(4))) = {\n  -32768, -32768, 0, 16384, 32767,\n};\n"
+    );
+  }
+
+  #[test]
+  fn test_c_array_float_literals() {
+    let generator = CArrayGenerator::new("values").unwrap();
+
+    // The shortest exact literals, and literals without decimals
+    assert_eq!(generator.generate(&[0.0, 1e-7, 0.1]), "const float values[3] = {\n  0.0f, 1e-7f, 0.1f,\n};\n");
+    assert_eq!(generator.with_precision(0).generate(&[1.0]), "const float values[1] = {\n  1.f,\n};\n");
+  }
+
+  #[test]
+  fn test_c_array_not_finite() {
+    let window = [f32::NAN, f32::INFINITY, f32::NEG_INFINITY];
+
+    assert_eq!(
+      CArrayGenerator::new("values").unwrap().with_precision(3).generate(&window),
+      "const float values[3] = {\n  NAN, INFINITY, -INFINITY,\n};\n"
+    );
+    assert_eq!(
+      CArrayGenerator::new("values").unwrap().with_element_type(CElementType::Q15).generate(&window),
+      "const q15_t values[3] = {\n  0, 32767, -32768,\n};\n"
+    );
+  }
+
+  #[test]
+  fn test_c_array_invalid_names() {
+    assert!(CArrayGenerator::new("_hann_1024").is_ok());
+    for name in ["", "1024_hann", "hann-1024", "hann 1024", "hann[1024]", "hänn", "float", "static"] {
+      assert_eq!(CArrayGenerator::new(name).unwrap_err(), HannWindowError::InvalidIdentifier);
+    }
+  }
+
+  #[test]
+  fn test_rust_const() {
+    let source = rust_const("HANN_5", &get_hann_window(5).unwrap()).unwrap();
+
+    assert_eq!(source, "pub const HANN_5: [f32; 5] = [\n  0.0, 0.5, 1.0, 0.5, 0.0,\n];\n");
+  }
+
+  #[test]
+  fn test_rust_const_not_finite() {
+    let source = rust_const("VALUES", &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -0.0]).unwrap();
+
+    assert_eq!(source, "pub const VALUES: [f32; 4] = [\n  f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -0.0,\n];\n");
+  }
+
+  #[test]
+  fn test_rust_const_round_trip() {
+    let window = get_hann_window(1024).unwrap();
+
+    let source = rust_const("HANN_1024", &window).unwrap();
+
+    // Every literal reads back to the same coefficient
+    let values: Vec<f32> = source
+      .lines()
+      .skip(1)
+      .take_while(|line| *line != "];")
+      .flat_map(|line| line.split(',').map(str::trim).filter(|value| !value.is_empty()))
+      .map(|value| value.parse().unwrap())
+      .collect();
+    assert_eq!(values, window);
+  }
+
+  #[test]
+  fn test_rust_const_invalid_names() {
+    let mut output = Vec::new();
+
+    assert!(rust_const("_HANN_1024", &[1.0]).is_ok());
+    for name in ["", "1X", "my-window", "my window", "HANN[4]", "fn", "Self", "_", "r#fn"] {
+      assert_eq!(rust_const(name, &[1.0]).unwrap_err(), HannWindowError::InvalidIdentifier);
+      let error = write_rust_const(name, &[1.0], &mut output).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+      assert_eq!(error.get_ref().unwrap().downcast_ref(), Some(&HannWindowError::InvalidIdentifier));
+    }
+    // Nothing is written for a rejected name
+    assert!(output.is_empty());
+  }
+}
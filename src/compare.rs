@@ -0,0 +1,175 @@
+use std::{ error::Error, fmt };
+
+/// The largest difference accepted between two values by [`windows_approx_eq`] and
+/// [`spectrograms_approx_eq`].
+///
+/// Equal values, including infinities of the same sign, are always accepted, and NaN is never accepted.
+/// A plain `f32` converts into an absolute tolerance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+  /// The largest absolute difference, `|a - b| <= tolerance`.
+  Absolute(f32),
+  /// The largest difference relative to the larger magnitude, `|a - b| <= tolerance * max(|a|, |b|)`.
+  /// Note that only 0 is accepted as equal to 0.
+  Relative(f32),
+  /// The largest number of representable `f32` values between the two values, where 0 and -0 are
+  /// equal.
+  Ulps(u32),
+}
+
+// Convert an f32 into an absolute Tolerance, so that windows_approx_eq(a, b, 1e-6) reads naturally
+impl From<f32> for Tolerance {
+  fn from(tolerance: f32) -> Self {
+    Tolerance::Absolute(tolerance)
+  }
+}
+
+impl Tolerance {
+  /// Check whether two values are equal to within the tolerance.
+  pub fn accepts(self, a: f32, b: f32) -> bool {
+    if a == b {
+      return true;
+    }
+    // The differences of infinities and NaN are NaN, and of no use
+    if !a.is_finite() || !b.is_finite() {
+      return false;
+    }
+    match self {
+      Tolerance::Absolute(tolerance) => (a - b).abs() <= tolerance,
+      Tolerance::Relative(tolerance) => (a - b).abs() <= tolerance * a.abs().max(b.abs()),
+      Tolerance::Ulps(ulps) => ordered_bits(a).abs_diff(ordered_bits(b)) <= u64::from(ulps),
+    }
+  }
+}
+
+// Map the bits of an f32 onto integers in the order of the values, so that neighbours differ by 1
+fn ordered_bits(value: f32) -> i64 {
+  let bits = i64::from(value.to_bits() as i32);
+  // Negative values count down from -0, which falls on 0 like 0
+  if bits < 0 { i64::from(i32::MIN) - bits } else { bits }
+}
+
+/// The first pair of values found to differ by more than the tolerance.
+///
+/// The `index` is a position in an array, or a `(row, column)` position in a matrix. A value is `None`
+/// when the arrays, or the rows of the matrices, differ in length and the other array has no value there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mismatch<I = usize> {
+  /// The position of the values.
+  pub index: I,
+  /// The value of the first array, or matrix.
+  pub left: Option<f32>,
+  /// The value of the second array, or matrix.
+  pub right: Option<f32>,
+}
+
+// Implement the Error trait for the Mismatch struct, so that comparisons can be propagated with ?
+impl<I: fmt::Debug> Error for Mismatch<I> {}
+
+// Implement the Display trait for the Mismatch struct
+impl<I: fmt::Debug> fmt::Display for Mismatch<I> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match (self.left, self.right) {
+      (Some(left), Some(right)) => {
+        write!(f, "Mismatch: The values at index {:?} differ, {:?} and {:?}", self.index, left, right)
+      }
+      _ => {
+        write!(f, "Mismatch: The lengths differ, from index {:?}", self.index)
+      }
+    }
+  }
+}
+
+/// Compare two windows, or any two arrays of coefficients, value by value.
+///
+/// This function takes the arrays `a` and `b` and the `tolerance`, an `f32` for an absolute tolerance
+/// or a [`Tolerance`], and returns the first [`Mismatch`] in index order, if any. Arrays of different
+/// lengths mismatch at the length of the shorter one.
+pub fn windows_approx_eq(a: &[f32], b: &[f32], tolerance: impl Into<Tolerance>) -> Result<(), Mismatch> {
+  let tolerance = tolerance.into();
+  if let Some(index) = a.iter().zip(b).position(|(&left, &right)| !tolerance.accepts(left, right)) {
+    return Err(Mismatch { index, left: Some(a[index]), right: Some(b[index]) });
+  }
+  if a.len() != b.len() {
+    let index = a.len().min(b.len());
+    return Err(Mismatch { index, left: a.get(index).copied(), right: b.get(index).copied() });
+  }
+  Ok(())
+}
+
+/// Compare two spectrograms, or any two matrices stored as rows, value by value.
+///
+/// This function takes the matrices `a` and `b` and the `tolerance`, and returns the first
+/// [`Mismatch`] in row-major order, with its `(row, column)` index, if any. Matrices with different
+/// numbers of rows mismatch at the first column of the first missing row.
+pub fn spectrograms_approx_eq<R: AsRef<[f32]>>(a: &[R], b: &[R], tolerance: impl Into<Tolerance>) -> Result<(), Mismatch<(usize, usize)>> {
+  let tolerance = tolerance.into();
+  for (row, (left, right)) in a.iter().zip(b).enumerate() {
+    windows_approx_eq(left.as_ref(), right.as_ref(), tolerance).map_err(|mismatch| Mismatch {
+      index: (row, mismatch.index),
+      left: mismatch.left,
+      right: mismatch.right,
+    })?;
+  }
+  if a.len() != b.len() {
+    let row = a.len().min(b.len());
+    let first = |matrix: &[R]| matrix.get(row).and_then(|values| values.as_ref().first().copied());
+    return Err(Mismatch { index: (row, 0), left: first(a), right: first(b) });
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_compare {
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_tolerance() {
+    assert!(Tolerance::Absolute(0.1).accepts(1.0, 1.05));
+    assert!(!Tolerance::Absolute(0.01).accepts(1.0, 1.05));
+    assert!(Tolerance::Relative(0.01).accepts(1000.0, 1005.0));
+    assert!(!Tolerance::Relative(0.01).accepts(0.0, 1e-30));
+    assert!(Tolerance::Ulps(0).accepts(f32::INFINITY, f32::INFINITY));
+    assert!(!Tolerance::Absolute(f32::MAX).accepts(f32::NAN, f32::NAN));
+  }
+
+  #[test]
+  fn test_tolerance_ulps() {
+    let next = f32::from_bits(1.0f32.to_bits() + 1);
+    assert!(Tolerance::Ulps(1).accepts(1.0, next));
+    assert!(!Tolerance::Ulps(0).accepts(1.0, next));
+    // The smallest positive and negative values are 2 steps apart, across 0
+    assert!(Tolerance::Ulps(2).accepts(f32::from_bits(1), -f32::from_bits(1)));
+    assert!(!Tolerance::Ulps(1).accepts(f32::from_bits(1), -f32::from_bits(1)));
+    assert!(Tolerance::Ulps(0).accepts(0.0, -0.0));
+  }
+
+  #[test]
+  fn test_windows_approx_eq() {
+    let window = get_hann_window(16).unwrap();
+    let mut perturbed = window.clone();
+    perturbed[5] += 1e-4;
+    perturbed[9] += 1e-4;
+
+    assert_eq!(windows_approx_eq(&window, &perturbed, 1e-3), Ok(()));
+    assert_eq!(windows_approx_eq(&window, &perturbed, 1e-5), Err(Mismatch { index: 5, left: Some(window[5]), right: Some(perturbed[5]) }));
+    assert_eq!(windows_approx_eq(&window, &window[..12], 1e-3), Err(Mismatch { index: 12, left: Some(window[12]), right: None }));
+  }
+
+  #[test]
+  fn test_spectrograms_approx_eq() {
+    let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+    let b = vec![vec![1.0, 2.0], vec![3.0, 4.5]];
+
+    assert_eq!(spectrograms_approx_eq(&a, &a, Tolerance::Ulps(0)), Ok(()));
+    assert_eq!(spectrograms_approx_eq(&a, &b, Tolerance::Relative(0.01)).unwrap_err().index, (1, 1));
+    assert_eq!(spectrograms_approx_eq(&a, &a[..1], 0.0).unwrap_err(), Mismatch { index: (1, 0), left: Some(3.0), right: None });
+    assert_eq!(
+      spectrograms_approx_eq(&a, &b, 0.0).unwrap_err().to_string(),
+      "Mismatch: The values at index (1, 1) differ, 4.0 and 4.5"
+    );
+  }
+}
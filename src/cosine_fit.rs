@@ -0,0 +1,198 @@
+use std::f64::consts::PI;
+
+use crate::{ hann_window::HannWindowError, window_spec::{ WindowSpec, MAX_COSINE_SUM_TERMS } };
+
+// The span of the sidelobe region fitted beyond the mainlobe, in bins, and the number of frequencies
+// sampled per bin
+const SIDELOBE_SPAN: f64 = 64.0;
+const SAMPLES_PER_BIN: usize = 16;
+
+/// The target of a cosine-sum window design, see [`fit_cosine_sum`].
+///
+/// Frequencies are given in bins, that is in cycles per window length, and responses as amplitudes
+/// relative to the window length, so that a window peaking at 1 has the response `a0`, its coherent
+/// gain, at 0 bins.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CosineSumTarget {
+  /// Samples `(frequency, amplitude)` of the desired response, signed like the real response of a
+  /// symmetric window, whose mainlobe is positive and whose sidelobes alternate in sign.
+  Response(Vec<(f32, f32)>),
+  /// A template of the shape of the sidelobes beyond `mainlobe_half_width` bins, falling by
+  /// `rolloff_db_per_octave`. The energy of the response over 64 bins beyond the mainlobe is
+  /// minimized, weighted by the inverse of the template, so that the far sidelobes count as much more
+  /// as the template is lower there. The level the sidelobes reach is not part of the template: with
+  /// the peak of the window fixed at 1, it is set by the term count and the mainlobe width alone, and
+  /// can be read from [`crate::metrics::window_metrics`] of the designed window.
+  Sidelobes { mainlobe_half_width: f32, rolloff_db_per_octave: f32 },
+}
+
+/// Design a cosine-sum window of `term_count` coefficients by least squares.
+///
+/// The response of the window `a0 - a1 * cos(2π * n / (N - 1)) + ...` is, for long windows, the sum
+/// of sinc kernels `a0 * sinc(f) + sum(a_k * (sinc(f - k) + sinc(f + k)) / 2)`, which is linear in
+/// the coefficients. The coefficients minimizing the squared error to the target are found under the
+/// constraint that their sum, the peak of the window, is 1, and are returned as a
+/// [`WindowSpec::CosineSum`] that generates the window at any length. An error is returned if the
+/// term count is 0 or greater than [`MAX_COSINE_SUM_TERMS`], if a frequency, amplitude or rolloff of
+/// the target is not finite, if the mainlobe half width is not greater than 0, or if the target has
+/// too few samples to determine the coefficients.
+pub fn fit_cosine_sum(target: &CosineSumTarget, term_count: usize) -> Result<WindowSpec, HannWindowError> {
+  if term_count == 0 || term_count > MAX_COSINE_SUM_TERMS {
+    return Err(HannWindowError::InvalidTermCount);
+  }
+
+  // The weighted samples (weight, frequency, amplitude) of the target
+  let samples: Vec<(f64, f64, f64)> = match target {
+    CosineSumTarget::Response(points) => {
+      if points.iter().any(|(frequency, amplitude)| !(frequency.is_finite() && amplitude.is_finite())) {
+        return Err(HannWindowError::InvalidShapeParameter);
+      }
+      points.iter().map(|&(frequency, amplitude)| (1.0, f64::from(frequency), f64::from(amplitude))).collect()
+    }
+    &CosineSumTarget::Sidelobes { mainlobe_half_width, rolloff_db_per_octave } => {
+      if !(mainlobe_half_width > 0.0 && mainlobe_half_width.is_finite() && rolloff_db_per_octave.is_finite()) {
+        return Err(HannWindowError::InvalidShapeParameter);
+      }
+      let edge = f64::from(mainlobe_half_width);
+      (0..=((SIDELOBE_SPAN as usize) * SAMPLES_PER_BIN))
+        .map(|i| {
+          let frequency = edge + (i as f64) / (SAMPLES_PER_BIN as f64);
+          // The template relative to its level at the edge of the mainlobe, which does not change the fit
+          let level = -f64::from(rolloff_db_per_octave) * (frequency / edge).log2();
+          (10.0f64.powf(-level / 20.0), frequency, 0.0)
+        })
+        .collect()
+    }
+  };
+
+  // The normal equations of the least squares, bordered by the constraint on the sum of the
+  // coefficients, with the Lagrange multiplier as the last unknown
+  let size = term_count + 1;
+  let mut system = vec![vec![0.0f64; size + 1]; size];
+  for &(weight, frequency, amplitude) in &samples {
+    let basis: Vec<f64> = (0..term_count).map(|k| weight * kernel(k, frequency)).collect();
+    for i in 0..term_count {
+      for j in 0..term_count {
+        system[i][j] += basis[i] * basis[j];
+      }
+      system[i][size] += basis[i] * weight * amplitude;
+    }
+  }
+  for row in system.iter_mut().take(term_count) {
+    row[term_count] = 1.0;
+  }
+  system[term_count][..term_count].fill(1.0);
+  system[term_count][size] = 1.0;
+
+  let solution = solve(system).ok_or(HannWindowError::InvalidShapeParameter)?;
+  let mut coefficients = [0.0; MAX_COSINE_SUM_TERMS];
+  for (coefficient, value) in coefficients.iter_mut().zip(&solution[..term_count]) {
+    *coefficient = *value as f32;
+  }
+  Ok(WindowSpec::CosineSum { coefficients })
+}
+
+// The response at `frequency` bins of the cosine term `k` of a window peaking at its center
+fn kernel(k: usize, frequency: f64) -> f64 {
+  let sinc = |x: f64| if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+  if k == 0 {
+    sinc(frequency)
+  } else {
+    (sinc(frequency - (k as f64)) + sinc(frequency + (k as f64))) / 2.0
+  }
+}
+
+// Solve a linear system given as rows of coefficients followed by the right-hand side, by Gaussian
+// elimination with partial pivoting, or return `None` if it is singular
+fn solve(mut system: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+  let size = system.len();
+  let scale = system.iter().flat_map(|row| row[..size].iter()).fold(0.0, |max: f64, value| max.max(value.abs()));
+  for column in 0..size {
+    let pivot = (column..size).max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))?;
+    if system[pivot][column].abs() <= scale * 1e-12 {
+      return None;
+    }
+    system.swap(column, pivot);
+    let (upper, lower) = system.split_at_mut(column + 1);
+    let pivot_row = &upper[column];
+    for row in lower {
+      let factor = row[column] / pivot_row[column];
+      for (value, pivot_value) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+        *value -= factor * pivot_value;
+      }
+    }
+  }
+
+  // Substitute back from the last unknown
+  let mut solution = vec![0.0; size];
+  for row in (0..size).rev() {
+    let sum: f64 = ((row + 1)..size).map(|k| system[row][k] * solution[k]).sum();
+    solution[row] = (system[row][size] - sum) / system[row][row];
+  }
+  Some(solution)
+}
+
+#[cfg(test)]
+mod test_cosine_fit {
+  use approx::assert_abs_diff_eq;
+
+  use crate::metrics::window_metrics;
+
+  use super::*;
+
+  // The response of a cosine-sum window at the given frequencies
+  fn response(coefficients: &[f64], frequencies: impl Iterator<Item = f64>) -> Vec<(f32, f32)> {
+    frequencies
+      .map(|frequency| {
+        let amplitude: f64 = coefficients.iter().enumerate().map(|(k, a)| a * kernel(k, frequency)).sum();
+        (frequency as f32, amplitude as f32)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_fit_cosine_sum_response() {
+    // The response of the Blackman window determines its coefficients
+    let target = CosineSumTarget::Response(response(&[0.42, 0.5, 0.08], (0..40).map(|i| (i as f64) * 0.25)));
+
+    let WindowSpec::CosineSum { coefficients } = fit_cosine_sum(&target, 3).unwrap() else { panic!("Expected a cosine-sum window") };
+
+    assert_abs_diff_eq!(coefficients[..3], [0.42, 0.5, 0.08][..], epsilon = 1e-5);
+    assert_eq!(coefficients[3..], [0.0; 5]);
+    let window = WindowSpec::CosineSum { coefficients }.generate(101).unwrap();
+    for (value, expected) in window.iter().zip(WindowSpec::Blackman.generate(101).unwrap()) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_fit_cosine_sum_sidelobes() {
+    let target = CosineSumTarget::Sidelobes { mainlobe_half_width: 3.0, rolloff_db_per_octave: 0.0 };
+
+    let spec = fit_cosine_sum(&target, 3).unwrap();
+    let WindowSpec::CosineSum { coefficients } = spec else { panic!("Expected a cosine-sum window") };
+    let metrics = window_metrics(&spec.generate(512).unwrap()).unwrap();
+
+    // The window peaks at 1, and its sidelobes are far below those of the Hann window
+    assert_abs_diff_eq!(coefficients.iter().sum::<f32>(), 1.0, epsilon = 1e-6);
+    assert!(metrics.sidelobe_level < -50.0);
+
+    // A falling template weights the far sidelobes more, and changes the design
+    let falling = CosineSumTarget::Sidelobes { mainlobe_half_width: 3.0, rolloff_db_per_octave: 18.0 };
+    assert_ne!(fit_cosine_sum(&falling, 3).unwrap(), spec);
+  }
+
+  #[test]
+  fn test_fit_cosine_sum_invalid_targets() {
+    let sidelobes = CosineSumTarget::Sidelobes { mainlobe_half_width: 0.0, rolloff_db_per_octave: 6.0 };
+
+    assert_eq!(fit_cosine_sum(&CosineSumTarget::Response(vec![(0.0, 0.5)]), 0).unwrap_err(), HannWindowError::InvalidTermCount);
+    assert_eq!(fit_cosine_sum(&CosineSumTarget::Response(vec![(0.0, 0.5)]), 9).unwrap_err(), HannWindowError::InvalidTermCount);
+    assert_eq!(fit_cosine_sum(&sidelobes, 3).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!(fit_cosine_sum(&CosineSumTarget::Response(vec![(f32::NAN, 0.5)]), 2).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    // A single sample cannot determine three coefficients
+    assert_eq!(fit_cosine_sum(&CosineSumTarget::Response(vec![(0.0, 0.5)]), 3).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+}
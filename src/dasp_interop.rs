@@ -0,0 +1,114 @@
+use dasp::{ sample::{ FromSample, Sample }, Frame, Signal };
+
+use crate::hann_window::HannWindowError;
+
+/// A `dasp` signal multiplied by a window.
+///
+/// Created by [`WindowSignalExt::windowed`]. Every channel of frame `n` of the signal is scaled by
+/// `window[n]`. The signal is exhausted after `window.len()` frames, or earlier if the underlying
+/// signal is, and yields equilibrium frames from then on.
+#[derive(Debug, Clone)]
+pub struct WindowedSignal<'a, S> {
+  signal: S,
+  window: &'a [f32],
+  position: usize,
+}
+
+impl<S> Signal for WindowedSignal<'_, S>
+where
+  S: Signal,
+  <<S::Frame as Frame>::Sample as Sample>::Float: FromSample<f32>,
+{
+  type Frame = S::Frame;
+
+  fn next(&mut self) -> Self::Frame {
+    // Past the end of the window the signal is silent
+    match self.window.get(self.position) {
+      Some(&value) => {
+        self.position += 1;
+        self.signal.next().scale_amp(FromSample::from_sample_(value))
+      }
+      None => Self::Frame::EQUILIBRIUM,
+    }
+  }
+
+  fn is_exhausted(&self) -> bool {
+    self.position >= self.window.len() || self.signal.is_exhausted()
+  }
+}
+
+/// An extension trait multiplying `dasp` signals by windows.
+pub trait WindowSignalExt: Signal + Sized {
+  /// Multiply the next `window.len()` frames of the signal by `window`, e.g. one returned by
+  /// `get_hann_window`.
+  fn windowed(self, window: &[f32]) -> WindowedSignal<'_, Self> {
+    WindowedSignal { signal: self, window, position: 0 }
+  }
+}
+
+impl<S: Signal> WindowSignalExt for S {}
+
+/// Multiply a buffer of `dasp` frames by a window in place.
+///
+/// Every channel of frame `n` is scaled by `window[n]`, for frames with any number of channels and any
+/// sample type. If the lengths of the buffer and the window differ, an error is returned and the frames
+/// are left untouched.
+pub fn apply_window_to_frames<F>(frames: &mut [F], window: &[f32]) -> Result<(), HannWindowError>
+where
+  F: Frame,
+  <F::Sample as Sample>::Float: FromSample<f32>,
+{
+  // The window must cover the buffer frame for frame
+  if frames.len() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Scale every frame by its window value
+  for (frame, &value) in frames.iter_mut().zip(window.iter()) {
+    *frame = frame.scale_amp(FromSample::from_sample_(value));
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_dasp_interop {
+  use dasp::signal;
+
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_windowed_signal() {
+    let window = get_hann_window(5).unwrap();
+    let mut windowed = signal::from_iter([[2.0f32]; 8]).windowed(&window);
+
+    let frames: Vec<[f32; 1]> = (0..5).map(|_| windowed.next()).collect();
+
+    assert_eq!(frames, vec![[0.0], [1.0], [2.0], [1.0], [0.0]]);
+    assert!(windowed.is_exhausted());
+    assert_eq!(windowed.next(), [0.0]);
+  }
+
+  #[test]
+  fn test_windowed_signal_short_signal() {
+    let window = get_hann_window(5).unwrap();
+    let mut windowed = signal::from_iter([[4.0f64, -4.0]; 2]).windowed(&window);
+
+    assert_eq!(windowed.next(), [0.0, 0.0]);
+    assert_eq!(windowed.next(), [2.0, -2.0]);
+    assert!(windowed.is_exhausted());
+  }
+
+  #[test]
+  fn test_apply_window_to_frames() {
+    let window = get_hann_window(3).unwrap();
+    let mut stereo = [[1000i16, -1000]; 3];
+
+    apply_window_to_frames(&mut stereo, &window).unwrap();
+
+    assert_eq!(stereo, [[0, 0], [1000, -1000], [0, 0]]);
+    assert_eq!(apply_window_to_frames(&mut stereo[..2], &window).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
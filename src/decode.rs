@@ -0,0 +1,379 @@
+use std::{ collections::VecDeque, error::Error, fmt, fs::File, io, path::Path };
+
+use symphonia::core::{
+  audio::SampleBuffer,
+  codecs::{ Decoder, DecoderOptions, CODEC_TYPE_NULL },
+  errors::Error as SymphoniaError,
+  formats::{ FormatOptions, FormatReader },
+  io::{ MediaSource, MediaSourceStream },
+  meta::MetadataOptions,
+  probe::Hint,
+};
+
+use crate::{
+  downmix::{ downmix, Downmix },
+  hann_window::HannWindowError,
+  instrument::{ trace_event, trace_span },
+  resampler::Resampler,
+  streaming_windower::StreamingWindower,
+};
+
+/// Error type for the decode-and-analyze pipeline.
+#[derive(Debug)]
+pub enum DecodeError {
+  Decode(SymphoniaError),
+  NoAudioTrack,
+  Windower(HannWindowError),
+}
+
+// Implement the Error trait for the DecodeError enum
+impl Error for DecodeError {}
+
+// Implement the Display trait for the DecodeError enum
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      DecodeError::Decode(error) => {
+        write!(f, "DecodeError: Failed to decode the audio: {}", error)
+      }
+      DecodeError::NoAudioTrack => {
+        write!(f, "DecodeError: No decodable audio track with a known sample rate was found.")
+      }
+      DecodeError::Windower(error) => {
+        write!(f, "DecodeError: {}", error)
+      }
+    }
+  }
+}
+
+/// A frame source decoding compressed or uncompressed audio and yielding windowed frames.
+///
+/// Any container and codec supported by `symphonia` with the enabled features can be read, including
+/// MP3, FLAC, AAC, Vorbis and WAV. The first audio track is decoded, its channels are reduced to one as
+/// described by a [`Downmix`], optionally resampled with [`DecodedFrameReader::with_sample_rate`] and
+/// cut into frames by a [`StreamingWindower`]. Packets that fail to decode are skipped. Samples that do
+/// not fill a whole frame at the end of the stream are discarded.
+pub struct DecodedFrameReader {
+  format: Box<dyn FormatReader>,
+  decoder: Box<dyn Decoder>,
+  track_id: u32,
+  source_sample_rate: u32,
+  windower: StreamingWindower,
+  downmix: Downmix,
+  resampler: Option<Resampler>,
+  sample_buffer: Option<SampleBuffer<f32>>,
+  mono: Vec<f32>,
+  resampled: Vec<f32>,
+  pending_frames: VecDeque<Vec<f32>>,
+  finished: bool,
+}
+
+impl DecodedFrameReader {
+  /// Open the audio file at `path` and frame its samples with `windower`.
+  ///
+  /// The file extension is used as a hint to detect the container format.
+  pub fn open<P: AsRef<Path>>(path: P, windower: StreamingWindower, downmix: Downmix) -> Result<Self, DecodeError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|error| DecodeError::Decode(SymphoniaError::IoError(error)))?;
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    DecodedFrameReader::new(Box::new(file), extension, windower, downmix)
+  }
+
+  /// Decode the audio read from `source` and frame its samples with `windower`.
+  ///
+  /// The container format is detected from the content of the source, helped by the optional file
+  /// `extension`. An error is returned if the format is not supported, if the source has no audio
+  /// track, or if the selected downmix channel does not exist.
+  pub fn new(
+    source: Box<dyn MediaSource>,
+    extension: Option<&str>,
+    windower: StreamingWindower,
+    downmix: Downmix
+  ) -> Result<Self, DecodeError> {
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+      hint.with_extension(extension);
+    }
+    let stream = MediaSourceStream::new(source, Default::default());
+    let probed = symphonia::default
+      ::get_probe()
+      .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+      .map_err(DecodeError::Decode)?;
+
+    // Decode the first audio track with a known sample rate
+    let format = probed.format;
+    let track = format
+      .tracks()
+      .iter()
+      .find(|track| track.codec_params.codec != CODEC_TYPE_NULL && track.codec_params.sample_rate.is_some())
+      .ok_or(DecodeError::NoAudioTrack)?;
+    let track_id = track.id;
+    let source_sample_rate = track.codec_params.sample_rate.ok_or(DecodeError::NoAudioTrack)?;
+
+    // The selected channel must exist, if the channels are known before decoding
+    if let (Downmix::Channel(channel), Some(channels)) = (downmix, track.codec_params.channels) {
+      if channel >= channels.count() {
+        return Err(DecodeError::Windower(HannWindowError::InvalidChannelCount));
+      }
+    }
+
+    let decoder = symphonia::default
+      ::get_codecs()
+      .make(&track.codec_params, &DecoderOptions::default())
+      .map_err(DecodeError::Decode)?;
+
+    Ok(DecodedFrameReader {
+      format,
+      decoder,
+      track_id,
+      source_sample_rate,
+      windower,
+      downmix,
+      resampler: None,
+      sample_buffer: None,
+      mono: Vec::new(),
+      resampled: Vec::new(),
+      pending_frames: VecDeque::new(),
+      finished: false,
+    })
+  }
+
+  /// Resample the decoded audio to `sample_rate` before framing it.
+  ///
+  /// The audio is converted by a [`Resampler`] with its default kernel, which is flushed at the end of
+  /// the stream. An error is returned if the sample rate is 0.
+  pub fn with_sample_rate(mut self, sample_rate: u32) -> Result<Self, DecodeError> {
+    // Resampling to the source sample rate is not needed
+    self.resampler = if sample_rate == self.source_sample_rate {
+      None
+    } else {
+      Some(Resampler::new(self.source_sample_rate, sample_rate).map_err(DecodeError::Windower)?)
+    };
+
+    Ok(self)
+  }
+
+  /// The sample rate of the framed samples in Hz.
+  pub fn sample_rate(&self) -> u32 {
+    self.resampler.as_ref().map_or(self.source_sample_rate, |resampler| resampler.output_sample_rate())
+  }
+
+  /// The sample rate of the decoded audio track in Hz.
+  pub fn source_sample_rate(&self) -> u32 {
+    self.source_sample_rate
+  }
+
+  // Decode the next packet of the track and queue the frames it completes
+  fn read_packet(&mut self) -> Result<(), DecodeError> {
+    trace_span!(DEBUG, "decode_packet");
+    let packet = match self.format.next_packet() {
+      Ok(packet) => packet,
+      // The end of the stream is reported as an unexpected end of file
+      Err(SymphoniaError::IoError(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+        self.finished = true;
+        // Frame the samples the resampler still holds
+        if let Some(resampler) = &mut self.resampler {
+          let pending_frames = &mut self.pending_frames;
+          self.resampled.resize(resampler.max_flush_length(), 0.0);
+          let count = resampler.flush(&mut self.resampled).map_err(DecodeError::Windower)?;
+          self.windower.process(&self.resampled[..count], |frame| pending_frames.push_back(frame.to_vec()));
+        }
+        return Ok(());
+      }
+      Err(error) => {
+        return Err(DecodeError::Decode(error));
+      }
+    };
+    if packet.track_id() != self.track_id {
+      return Ok(());
+    }
+
+    let decoded = match self.decoder.decode(&packet) {
+      Ok(decoded) => decoded,
+      // The decoder recovers at the next packet after a corrupt one
+      Err(SymphoniaError::DecodeError(_error)) => {
+        trace_event!(WARN, error = _error, "skipping a packet that failed to decode");
+        return Ok(());
+      }
+      Err(error) => {
+        return Err(DecodeError::Decode(error));
+      }
+    };
+
+    // Convert the decoded samples to interleaved `f32` samples, growing the buffer when needed
+    let spec = *decoded.spec();
+    let channel_count = spec.channels.count();
+    let sample_frames = decoded.frames();
+    let required_capacity = decoded.capacity() * channel_count;
+    if self.sample_buffer.as_ref().is_none_or(|buffer| buffer.capacity() < required_capacity) {
+      self.sample_buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+    }
+    let sample_buffer = self.sample_buffer.as_mut().expect("Sample buffer was allocated");
+    sample_buffer.copy_interleaved_ref(decoded);
+
+    self.mono.resize(sample_frames, 0.0);
+    downmix(sample_buffer.samples(), channel_count, self.downmix, &mut self.mono).map_err(DecodeError::Windower)?;
+
+    let pending_frames = &mut self.pending_frames;
+    match &mut self.resampler {
+      Some(resampler) => {
+        self.resampled.resize(resampler.max_output_length(self.mono.len()), 0.0);
+        let count = resampler.process(&self.mono, &mut self.resampled).map_err(DecodeError::Windower)?;
+        self.windower.process(&self.resampled[..count], |frame| pending_frames.push_back(frame.to_vec()));
+      }
+      None => {
+        self.windower.process(&self.mono, |frame| pending_frames.push_back(frame.to_vec()));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl Iterator for DecodedFrameReader {
+  type Item = Result<Vec<f32>, DecodeError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      // Hand out the frames completed by the previous packet first
+      if let Some(frame) = self.pending_frames.pop_front() {
+        return Some(Ok(frame));
+      }
+      if self.finished {
+        return None;
+      }
+      // Stop reading after the first unrecoverable error
+      if let Err(error) = self.read_packet() {
+        self.finished = true;
+        return Some(Err(error));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_decode {
+  use std::io::Cursor;
+
+  use approx::assert_abs_diff_eq;
+
+  use crate::frames::{ frames, PaddingMode };
+
+  use super::*;
+
+  // Encode interleaved 16-bit samples as an in-memory WAV file
+  fn encode_wav(samples: &[i16], channels: u16, sample_rate: u32) -> Vec<u8> {
+    let data_length = (samples.len() * 2) as u32;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_length).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * u32::from(channels) * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_length.to_le_bytes());
+    bytes.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+    bytes
+  }
+
+  fn open(bytes: Vec<u8>, windower: StreamingWindower, downmix: Downmix) -> Result<DecodedFrameReader, DecodeError> {
+    DecodedFrameReader::new(Box::new(Cursor::new(bytes)), Some("wav"), windower, downmix)
+  }
+
+  #[test]
+  fn test_decoded_frame_reader_matches_frames() {
+    let samples: Vec<i16> = (0..5000).map(|i| (((i as f32) * 0.05).sin() * 10000.0) as i16).collect();
+    let signal: Vec<f32> = samples.iter().map(|sample| (*sample as f32) / 32768.0).collect();
+
+    let reader = open(encode_wav(&samples, 1, 8000), StreamingWindower::new(256, 64).unwrap(), Downmix::Average).unwrap();
+    assert_eq!(reader.sample_rate(), 8000);
+    let emitted: Vec<Vec<f32>> = reader.map(Result::unwrap).collect();
+    let expected: Vec<Vec<f32>> = frames(&signal, 256, 64, PaddingMode::None)
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(emitted.len(), expected.len());
+    for (emitted_frame, expected_frame) in emitted.iter().zip(expected.iter()) {
+      for (emitted_sample, expected_sample) in emitted_frame.iter().zip(expected_frame.iter()) {
+        assert_abs_diff_eq!(emitted_sample, expected_sample, epsilon = 1e-6);
+      }
+    }
+  }
+
+  #[test]
+  fn test_decoded_frame_reader_stereo_downmix() {
+    let bytes = encode_wav(&[16384, 0, -16384, -16384, 8192, 8192, 0, 16384], 2, 8000);
+
+    let emitted: Vec<Vec<f32>> = open(bytes, StreamingWindower::new(4, 4).unwrap(), Downmix::Channel(1))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect();
+
+    assert_eq!(emitted, vec![vec![0.0, -0.5, 0.25, 0.5]]);
+  }
+
+  #[test]
+  fn test_decoded_frame_reader_resample() {
+    let tone = |n: usize, sample_rate: f64| (2.0 * std::f64::consts::PI * 500.0 * (n as f64) / sample_rate).sin();
+    let samples: Vec<i16> = (0..2000).map(|n| (tone(n, 8000.0) * 16384.0).round() as i16).collect();
+
+    let reader = open(encode_wav(&samples, 1, 8000), StreamingWindower::new(100, 100).unwrap(), Downmix::Average)
+      .unwrap()
+      .with_sample_rate(16000)
+      .unwrap();
+    assert_eq!(reader.sample_rate(), 16000);
+    assert_eq!(reader.source_sample_rate(), 8000);
+    let resampled: Vec<f32> = reader.flat_map(Result::unwrap).collect();
+
+    // The tone is sampled at the new rate, the resampler being flushed at the end of the stream
+    assert_eq!(resampled.len(), 4000);
+    for (n, sample) in resampled.iter().enumerate().take(3800).skip(200) {
+      assert_abs_diff_eq!(*sample, (tone(n, 16000.0) / 2.0) as f32, epsilon = 1e-4);
+    }
+  }
+
+  #[test]
+  fn test_decoded_frame_reader_downsample_without_aliasing() {
+    // A tone above the Nyquist frequency of the new sample rate, which would fold back to 500 Hz
+    let tone = |n: usize| (2.0 * std::f64::consts::PI * 3500.0 * (n as f64) / 8000.0).sin();
+    let samples: Vec<i16> = (0..8000).map(|n| (tone(n) * 16384.0).round() as i16).collect();
+
+    let reader = open(encode_wav(&samples, 1, 8000), StreamingWindower::new(100, 100).unwrap(), Downmix::Average)
+      .unwrap()
+      .with_sample_rate(4000)
+      .unwrap();
+    let resampled: Vec<f32> = reader.flat_map(Result::unwrap).collect();
+
+    // The tone is filtered out rather than aliased, away from the transients at either end
+    assert_eq!(resampled.len(), 4000);
+    let steady = &resampled[200..3800];
+    let rms = (steady.iter().map(|sample| sample * sample).sum::<f32>() / (steady.len() as f32)).sqrt();
+    assert!(rms < 1e-4);
+  }
+
+  #[test]
+  fn test_decoded_frame_reader_invalid_input() {
+    let bytes = encode_wav(&[0; 4], 2, 8000);
+
+    assert!(matches!(open(vec![0; 64], StreamingWindower::new(2, 2).unwrap(), Downmix::Average), Err(DecodeError::Decode(_))));
+    assert!(
+      matches!(
+        open(bytes.clone(), StreamingWindower::new(2, 2).unwrap(), Downmix::Channel(2)),
+        Err(DecodeError::Windower(HannWindowError::InvalidChannelCount))
+      )
+    );
+    assert!(
+      matches!(
+        open(bytes, StreamingWindower::new(2, 2).unwrap(), Downmix::Average).unwrap().with_sample_rate(0),
+        Err(DecodeError::Windower(HannWindowError::InvalidSampleRate))
+      )
+    );
+  }
+}
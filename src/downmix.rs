@@ -0,0 +1,82 @@
+use crate::hann_window::HannWindowError;
+
+/// How interleaved multichannel samples are reduced to a single channel before framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Downmix {
+  /// Average all channels.
+  Average,
+  /// Keep only the channel with the given index and discard the others.
+  Channel(usize),
+}
+
+/// Reduce interleaved multichannel samples to a single channel.
+///
+/// This function takes a slice `interleaved` holding whole sample frames of `channel_count` channels
+/// and writes one sample per sample frame into `output`, combined as described by `downmix`. An error
+/// is returned if `channel_count` is 0 or smaller than the selected channel, or if `output` does not
+/// hold exactly one sample per sample frame.
+pub fn downmix(
+  interleaved: &[f32],
+  channel_count: usize,
+  downmix: Downmix,
+  output: &mut [f32]
+) -> Result<(), HannWindowError> {
+  // There must be at least one channel, and the selected channel must exist
+  if channel_count == 0 {
+    return Err(HannWindowError::InvalidChannelCount);
+  }
+  if let Downmix::Channel(channel) = downmix {
+    if channel >= channel_count {
+      return Err(HannWindowError::InvalidChannelCount);
+    }
+  }
+  // The input must contain whole sample frames and the output must hold one sample for each of them
+  if !interleaved.len().is_multiple_of(channel_count) || interleaved.len() / channel_count != output.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Combine the channels of every sample frame
+  for (sample, sample_frame) in output.iter_mut().zip(interleaved.chunks_exact(channel_count)) {
+    *sample = match downmix {
+      Downmix::Average => sample_frame.iter().sum::<f32>() / (channel_count as f32),
+      Downmix::Channel(channel) => sample_frame[channel],
+    };
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_downmix {
+  use super::*;
+
+  #[test]
+  fn test_downmix_average() {
+    let mut output = [0.0; 2];
+
+    downmix(&[1.0, 0.0, -0.5, 0.5], 2, Downmix::Average, &mut output).unwrap();
+
+    assert_eq!(output, [0.5, 0.0]);
+  }
+
+  #[test]
+  fn test_downmix_channel() {
+    let mut output = [0.0; 2];
+
+    downmix(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3, Downmix::Channel(2), &mut output).unwrap();
+
+    assert_eq!(output, [3.0, 6.0]);
+  }
+
+  #[test]
+  fn test_downmix_invalid_input() {
+    let mut output = [0.0; 2];
+
+    assert_eq!(downmix(&[0.0; 4], 0, Downmix::Average, &mut output).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(downmix(&[0.0; 4], 2, Downmix::Channel(2), &mut output).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(downmix(&[0.0; 5], 2, Downmix::Average, &mut output).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(downmix(&[0.0; 6], 2, Downmix::Average, &mut output).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+// The number of nanoseconds in a second
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// The rounding of durations that do not span a whole number of samples, see [`duration_to_samples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DurationRounding {
+  /// The closest number of samples, halfway durations rounded up.
+  #[default]
+  Nearest,
+  /// The largest number of samples not longer than the duration.
+  Down,
+  /// The smallest number of samples not shorter than the duration.
+  Up,
+}
+
+/// Convert a duration into a number of samples at `sample_rate` Hz.
+///
+/// The conversion is exact, in integers down to the nanosecond, so that durations spanning a whole
+/// number of samples, like 25 ms at 48 kHz, never land one sample off through floating-point rounding.
+/// Other durations are rounded by `rounding`. Counts beyond a `usize` saturate.
+pub fn duration_to_samples(duration: Duration, sample_rate: u32, rounding: DurationRounding) -> usize {
+  let scaled = duration.as_nanos() * u128::from(sample_rate);
+  let samples = match rounding {
+    DurationRounding::Nearest => (scaled + NANOS_PER_SECOND / 2) / NANOS_PER_SECOND,
+    DurationRounding::Down => scaled / NANOS_PER_SECOND,
+    DurationRounding::Up => scaled.div_ceil(NANOS_PER_SECOND),
+  };
+  usize::try_from(samples).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod test_duration {
+  use super::*;
+
+  #[test]
+  fn test_duration_to_samples() {
+    assert_eq!(duration_to_samples(Duration::from_millis(25), 48_000, DurationRounding::Nearest), 1200);
+    assert_eq!(duration_to_samples(Duration::from_millis(25), 48_000, DurationRounding::Up), 1200);
+    assert_eq!(duration_to_samples(Duration::from_millis(10), 44_100, DurationRounding::Down), 441);
+    assert_eq!(duration_to_samples(Duration::ZERO, 48_000, DurationRounding::Up), 0);
+  }
+
+  #[test]
+  fn test_duration_rounding() {
+    // 25 ms at 22.05 kHz is 551.25 samples, and 1 / 32000 s at 16 kHz is half a sample
+    let duration = Duration::from_millis(25);
+    let half_sample = Duration::from_nanos(31_250);
+
+    assert_eq!(duration_to_samples(duration, 22_050, DurationRounding::Nearest), 551);
+    assert_eq!(duration_to_samples(duration, 22_050, DurationRounding::Down), 551);
+    assert_eq!(duration_to_samples(duration, 22_050, DurationRounding::Up), 552);
+    assert_eq!(duration_to_samples(half_sample, 16_000, DurationRounding::Nearest), 1);
+    assert_eq!(duration_to_samples(half_sample, 16_000, DurationRounding::Down), 0);
+  }
+}
@@ -0,0 +1,297 @@
+use std::f64::consts::PI;
+
+use crate::{
+  hann_window::{ check_window_length, get_hann_window, HannWindowError },
+  instrument::trace_allocation,
+  latency::Latency,
+};
+
+/// The level measured by an [`EnvelopeFollower`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EnvelopeMode {
+  /// The Hann-weighted root mean square `sqrt(sum(w * x^2) / sum(w))`.
+  Rms,
+  /// The Hann-weighted peak `max(w * |x|)`.
+  Peak,
+}
+
+/// A level meter measuring the Hann-weighted RMS or peak of the last `window_length` samples of a
+/// stream every `hop_size` samples.
+///
+/// In the RMS mode, the sums of the squares weighted by the Hann window are updated incrementally,
+/// with a sliding transform of the constant and the first cosine term of the window, so that every
+/// sample costs a constant number of operations whatever the window length. The sums are recomputed
+/// exactly every `window_length` samples, which bounds the rounding errors of the recursion. In the
+/// peak mode, the last `window_length` magnitudes are scanned at every hop. The first level is
+/// measured once `window_length` samples have been received, like the frames of a
+/// [`crate::streaming::StreamingWindower`]. All buffers are allocated on construction, processing samples does
+/// not allocate.
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+  mode: EnvelopeMode,
+  hop_size: usize,
+  window: Vec<f32>,
+  // The phase factors e^(i * 2π * k / (N - 1)) of the cosine term of the window
+  phases: Vec<(f64, f64)>,
+  weight_sum: f64,
+  // The squares, or magnitudes, of the last samples, the oldest at the write position
+  ring_buffer: Vec<f64>,
+  write_position: usize,
+  // The plain sum and the phase-weighted sum of the squares in the ring buffer
+  sum: f64,
+  phase_sum: (f64, f64),
+  // Number of samples left before the next level, and before the next exact recomputation of the sums
+  until_next_level: usize,
+  until_resync: usize,
+}
+
+impl EnvelopeFollower {
+  /// Create an envelope follower measuring the level of `window_length` samples every `hop_size`
+  /// samples.
+  ///
+  /// An error is returned if the window length is less than or equal to 2, since the Hann window of 2
+  /// values is all zeros and weights no sample, or too large, or if the hop size is 0.
+  pub fn new(window_length: usize, hop_size: usize, mode: EnvelopeMode) -> Result<Self, HannWindowError> {
+    check_window_length(window_length)?;
+    // The levels must advance by at least one sample
+    if hop_size == 0 {
+      return Err(HannWindowError::InvalidHopSize);
+    }
+    let window = get_hann_window(window_length)?;
+    trace_allocation("EnvelopeFollower", window_length * (std::mem::size_of::<f32>() + 3 * std::mem::size_of::<f64>()));
+
+    // The Hann window is 0.5 - 0.5 * cos(θk), with θ = 2π / (N - 1)
+    let step = (2.0 * PI) / ((window_length - 1) as f64);
+    let phases: Vec<(f64, f64)> = (0..window_length).map(|k| (((k as f64) * step).cos(), ((k as f64) * step).sin())).collect();
+    let weight_sum = phases.iter().map(|&(cos, _)| 0.5 - 0.5 * cos).sum();
+    // A window without weight would measure every level as 0 / 0
+    if weight_sum <= 0.0 {
+      return Err(HannWindowError::WindowLengthTooSmall);
+    }
+
+    Ok(EnvelopeFollower {
+      mode,
+      hop_size,
+      window,
+      phases,
+      weight_sum,
+      ring_buffer: vec![0.0; window_length],
+      write_position: 0,
+      sum: 0.0,
+      phase_sum: (0.0, 0.0),
+      until_next_level: window_length,
+      until_resync: window_length,
+    })
+  }
+
+  /// The number of samples every level is measured over.
+  pub fn window_length(&self) -> usize {
+    self.window.len()
+  }
+
+  /// The number of samples between two levels.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// The measured level.
+  pub fn mode(&self) -> EnvelopeMode {
+    self.mode
+  }
+
+  /// Feed a chunk of samples and call `on_level` with every level completed by it.
+  ///
+  /// The chunk may have any length, including 0. Returns the number of measured levels.
+  pub fn process<F: FnMut(f32)>(&mut self, samples: &[f32], mut on_level: F) -> usize {
+    let mut level_count = 0;
+    for &sample in samples {
+      self.push(sample);
+      self.until_next_level -= 1;
+      if self.until_next_level == 0 {
+        on_level(self.level());
+        self.until_next_level = self.hop_size;
+        level_count += 1;
+      }
+    }
+    level_count
+  }
+
+  /// Discard every buffered sample, the next level is measured after `window_length` new samples.
+  pub fn reset(&mut self) {
+    self.ring_buffer.fill(0.0);
+    self.write_position = 0;
+    self.sum = 0.0;
+    self.phase_sum = (0.0, 0.0);
+    self.until_next_level = self.window.len();
+    self.until_resync = self.window.len();
+  }
+
+  // Replace the oldest sample of the ring buffer by a new one, and slide the sums of the RMS mode
+  fn push(&mut self, sample: f32) {
+    let oldest = self.ring_buffer[self.write_position];
+    let value = match self.mode {
+      EnvelopeMode::Rms => f64::from(sample) * f64::from(sample),
+      EnvelopeMode::Peak => f64::from(sample.abs()),
+    };
+    self.ring_buffer[self.write_position] = value;
+    self.write_position = (self.write_position + 1) % self.ring_buffer.len();
+    if self.mode == EnvelopeMode::Peak {
+      return;
+    }
+
+    // Every sample moves one step towards the oldest end, which turns its phase back by θ, and the
+    // newest sample has the phase θ(N - 1) = 2π
+    let (cos, sin) = self.phases[1];
+    let (real, imaginary) = (self.phase_sum.0 - oldest, self.phase_sum.1);
+    self.phase_sum = (real * cos + imaginary * sin + value, imaginary * cos - real * sin);
+    self.sum += value - oldest;
+
+    self.until_resync -= 1;
+    if self.until_resync == 0 {
+      self.resync();
+      self.until_resync = self.ring_buffer.len();
+    }
+  }
+
+  // Recompute the sums of the RMS mode from the ring buffer
+  fn resync(&mut self) {
+    let length = self.ring_buffer.len();
+    self.sum = 0.0;
+    self.phase_sum = (0.0, 0.0);
+    for k in 0..length {
+      let value = self.ring_buffer[(self.write_position + k) % length];
+      let (cos, sin) = self.phases[k];
+      self.sum += value;
+      self.phase_sum.0 += value * cos;
+      self.phase_sum.1 += value * sin;
+    }
+  }
+
+  // Measure the level of the samples in the ring buffer
+  fn level(&self) -> f32 {
+    match self.mode {
+      EnvelopeMode::Rms => {
+        // The rounding of the sliding sums may push the sum of a silent stream slightly below 0
+        let weighted = 0.5 * self.sum - 0.5 * self.phase_sum.0;
+        (weighted.max(0.0) / self.weight_sum).sqrt() as f32
+      }
+      EnvelopeMode::Peak => {
+        let length = self.ring_buffer.len();
+        (0..length)
+          .map(|k| f64::from(self.window[k]) * self.ring_buffer[(self.write_position + k) % length])
+          .fold(0.0, f64::max) as f32
+      }
+    }
+  }
+}
+
+/// A level is measured `window_length - 1` samples after the first sample it covers, and
+/// `(window_length - 1) / 2` samples after the peak of its window.
+impl Latency for EnvelopeFollower {
+  fn latency(&self) -> usize {
+    self.window.len() - 1
+  }
+
+  fn group_delay(&self) -> f64 {
+    ((self.window.len() - 1) as f64) / 2.0
+  }
+}
+
+#[cfg(test)]
+mod test_envelope {
+  use approx::assert_abs_diff_eq;
+
+  use crate::frames::{ frames, PaddingMode };
+
+  use super::*;
+
+  // A tone with a rising amplitude and a pseudorandom noise floor
+  fn signal() -> Vec<f32> {
+    let mut state = 7u32;
+    (0..5000)
+      .map(|n| {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        let noise = ((state >> 8) as f32) / ((1 << 24) as f32) - 0.5;
+        (n as f32) / 5000.0 * (0.05 * (n as f32)).sin() + 0.01 * noise
+      })
+      .collect()
+  }
+
+  // Feed the signal in chunks of varying lengths and collect the levels
+  fn follow(follower: &mut EnvelopeFollower, signal: &[f32]) -> Vec<f32> {
+    let mut levels = Vec::new();
+    let mut remaining = signal;
+    let mut chunk_length = 1;
+    while !remaining.is_empty() {
+      let count = chunk_length.min(remaining.len());
+      follower.process(&remaining[..count], |level| levels.push(level));
+      remaining = &remaining[count..];
+      chunk_length = (chunk_length * 3) % 97 + 1;
+    }
+    levels
+  }
+
+  #[test]
+  fn test_envelope_rms() {
+    let signal = signal();
+    let window = get_hann_window(256).unwrap();
+    let mut follower = EnvelopeFollower::new(256, 64, EnvelopeMode::Rms).unwrap();
+
+    let levels = follow(&mut follower, &signal);
+
+    // The levels match the weighted RMS of every frame, computed directly
+    let frames: Vec<Vec<f32>> = frames(&signal, 256, 64, PaddingMode::None).unwrap().map(|frame| frame.into_owned()).collect();
+    assert_eq!(levels.len(), frames.len());
+    let weight_sum: f32 = window.iter().sum();
+    for (level, frame) in levels.iter().zip(&frames) {
+      let weighted: f32 = frame.iter().zip(&window).map(|(sample, weight)| weight * sample * sample).sum();
+      assert_abs_diff_eq!(*level, (weighted / weight_sum).sqrt(), epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_envelope_peak() {
+    let signal = signal();
+    let window = get_hann_window(100).unwrap();
+    let mut follower = EnvelopeFollower::new(100, 30, EnvelopeMode::Peak).unwrap();
+
+    let levels = follow(&mut follower, &signal);
+
+    let frames: Vec<Vec<f32>> = frames(&signal, 100, 30, PaddingMode::None).unwrap().map(|frame| frame.into_owned()).collect();
+    assert_eq!(levels.len(), frames.len());
+    for (level, frame) in levels.iter().zip(&frames) {
+      assert_eq!(*level, frame.iter().zip(&window).map(|(sample, weight)| weight * sample.abs()).fold(0.0, f32::max));
+    }
+  }
+
+  #[test]
+  fn test_envelope_constant_and_reset() {
+    let mut follower = EnvelopeFollower::new(481, 480, EnvelopeMode::Rms).unwrap();
+    let mut levels = Vec::new();
+
+    // The RMS of a constant is its magnitude, and silence after it falls to 0 without drifting
+    for _ in 0..100 {
+      follower.process(&[-0.5; 480], |level| levels.push(level));
+    }
+    for _ in 0..10 {
+      follower.process(&[0.0; 480], |level| levels.push(level));
+    }
+    assert_abs_diff_eq!(levels[98], 0.5, epsilon = 1e-6);
+    assert_eq!(*levels.last().unwrap(), 0.0);
+
+    follower.reset();
+    assert_eq!(follower.process(&[1.0; 480], |_| ()), 0);
+    assert_eq!(follower.latency(), 480);
+  }
+
+  #[test]
+  fn test_envelope_invalid_parameters() {
+    assert_eq!(EnvelopeFollower::new(1, 1, EnvelopeMode::Rms).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(EnvelopeFollower::new(2, 1, EnvelopeMode::Rms).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(EnvelopeFollower::new(2, 1, EnvelopeMode::Peak).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert!(EnvelopeFollower::new(3, 1, EnvelopeMode::Rms).is_ok());
+    assert_eq!(EnvelopeFollower::new(64, 0, EnvelopeMode::Peak).unwrap_err(), HannWindowError::InvalidHopSize);
+  }
+}
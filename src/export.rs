@@ -0,0 +1,91 @@
+use std::io::{ self, Write };
+
+/// The text format of exported window coefficients.
+///
+/// The `precision` is the number of digits written after the decimal point, or `None` for the shortest
+/// representation that reads back to the same `f32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ExportFormat {
+  /// A CSV table with an `index,value` header and one coefficient per row.
+  Csv { precision: Option<usize> },
+  /// A JSON array of the coefficients, with non-finite values written as `null`.
+  Json { precision: Option<usize> },
+}
+
+/// Write window coefficients to `writer` as text.
+///
+/// This function takes the `window`, the `format` of the text, see [`ExportFormat`], and the `writer`,
+/// e.g. a file or a `Vec<u8>`, and writes every coefficient in order, ending with a newline. Errors of
+/// the writer are returned as is.
+pub fn export_window<W: Write>(window: &[f32], format: ExportFormat, mut writer: W) -> io::Result<()> {
+  match format {
+    ExportFormat::Csv { precision } => {
+      writeln!(writer, "index,value")?;
+      for (index, &value) in window.iter().enumerate() {
+        writeln!(writer, "{},{}", index, format_value(value, precision))?;
+      }
+    }
+    ExportFormat::Json { precision } => {
+      // JSON has no representation of NaN and infinities
+      let values: Vec<String> = window
+        .iter()
+        .map(|&value| if value.is_finite() { format_value(value, precision) } else { String::from("null") })
+        .collect();
+      writeln!(writer, "[{}]", values.join(", "))?;
+    }
+  }
+
+  writer.flush()
+}
+
+// Format a coefficient with a fixed number of decimals, or with the shortest exact representation
+pub(crate) fn format_value(value: f32, precision: Option<usize>) -> String {
+  match precision {
+    Some(precision) => format!("{:.*}", precision, value),
+    None => value.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod test_export {
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_export_window_csv() {
+    let mut output = Vec::new();
+
+    export_window(&get_hann_window(5).unwrap(), ExportFormat::Csv { precision: Some(3) }, &mut output).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "index,value\n0,0.000\n1,0.500\n2,1.000\n3,0.500\n4,0.000\n");
+  }
+
+  #[test]
+  fn test_export_window_json() {
+    let mut output = Vec::new();
+
+    export_window(&[0.0, 0.25, 1.0, f32::NAN], ExportFormat::Json { precision: None }, &mut output).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "[0, 0.25, 1, null]\n");
+  }
+
+  #[test]
+  fn test_export_window_shortest_round_trip() {
+    let window = get_hann_window(64).unwrap();
+    let mut output = Vec::new();
+
+    export_window(&window, ExportFormat::Csv { precision: None }, &mut output).unwrap();
+
+    // Every coefficient reads back exactly
+    let parsed: Vec<f32> = String::from_utf8(output)
+      .unwrap()
+      .lines()
+      .skip(1)
+      .map(|line| line.split(',').nth(1).unwrap().parse().unwrap())
+      .collect();
+    assert_eq!(parsed, window);
+  }
+}
@@ -0,0 +1,135 @@
+use std::{ fmt, sync::Arc };
+
+use rustfft::{ num_complex::Complex, Fft, FftPlanner };
+
+use crate::{ hann_window::{ get_hann_window, HannWindowError }, instrument::trace_allocation };
+
+/// A windowed FFT plan owning the window, the `rustfft` plan and every buffer of the transform.
+///
+/// The window and the FFT always have the same length, so a frame can never be transformed with a
+/// window meant for another FFT length. Every buffer is allocated by [`WindowedFft::new`], and
+/// [`WindowedFft::process`] does not allocate.
+#[derive(Clone)]
+pub struct WindowedFft {
+  window: Vec<f32>,
+  fft: Arc<dyn Fft<f32>>,
+  spectrum: Vec<Complex<f32>>,
+  scratch: Vec<Complex<f32>>,
+}
+
+impl WindowedFft {
+  /// Create a plan for frames of `fft_length` samples, windowed by the Hann window of the same length.
+  ///
+  /// An error is returned if the FFT length is less than or equal to 1, or too large.
+  pub fn new(fft_length: usize) -> Result<Self, HannWindowError> {
+    let window = get_hann_window(fft_length)?;
+    let fft = FftPlanner::new().plan_fft_forward(fft_length);
+    trace_allocation("WindowedFft", (fft_length + fft.get_inplace_scratch_len()) * std::mem::size_of::<Complex<f32>>());
+
+    Ok(WindowedFft {
+      window,
+      spectrum: vec![Complex::default(); fft_length],
+      scratch: vec![Complex::default(); fft.get_inplace_scratch_len()],
+      fft,
+    })
+  }
+
+  /// Use `window` instead of the Hann window.
+  ///
+  /// An error is returned if the window length differs from the FFT length.
+  pub fn with_window(mut self, window: &[f32]) -> Result<Self, HannWindowError> {
+    // The window must cover the transform sample for sample
+    if window.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.window.copy_from_slice(window);
+    Ok(self)
+  }
+
+  /// The length of the frames and of the spectra.
+  pub fn fft_length(&self) -> usize {
+    self.window.len()
+  }
+
+  /// The window applied to every frame.
+  pub fn window(&self) -> &[f32] {
+    &self.window
+  }
+
+  /// Window a frame and return its spectrum.
+  ///
+  /// The spectrum has `fft_length` bins, bin `k` being the frequency `k / fft_length` cycles per sample.
+  /// It is stored in the plan and overwritten by the next call. An error is returned if the frame
+  /// length differs from the FFT length.
+  pub fn process(&mut self, frame: &[f32]) -> Result<&[Complex<f32>], HannWindowError> {
+    // The frame must match the window sample for sample
+    if frame.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    // Window the frame into the transform buffer and transform it in place
+    for ((bin, &sample), &value) in self.spectrum.iter_mut().zip(frame.iter()).zip(self.window.iter()) {
+      *bin = Complex::new(sample * value, 0.0);
+    }
+    self.fft.process_with_scratch(&mut self.spectrum, &mut self.scratch);
+
+    Ok(&self.spectrum)
+  }
+}
+
+// Implement the Debug trait for the WindowedFft struct, the plan itself is not Debug
+impl fmt::Debug for WindowedFft {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("WindowedFft").field("fft_length", &self.fft_length()).finish_non_exhaustive()
+  }
+}
+
+#[cfg(test)]
+mod test_fft {
+  use std::f32::consts::PI;
+
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_windowed_fft_tone() {
+    let mut fft = WindowedFft::new(64).unwrap();
+    let frame: Vec<f32> = (0..64).map(|n| ((2.0 * PI * 8.0 * (n as f32)) / 64.0).cos()).collect();
+
+    let spectrum = fft.process(&frame).unwrap();
+
+    // The windowed tone peaks at its bin and its mirror image, and the bins far away are empty
+    let peak = spectrum[8].norm();
+    assert_abs_diff_eq!(spectrum[56].norm(), peak, epsilon = 1e-4);
+    assert!(peak > 10.0);
+    assert!(spectrum[20].norm() < 1e-3 * peak);
+  }
+
+  #[test]
+  fn test_windowed_fft_matches_direct_transform() {
+    let window = [1.0, 0.5, 0.25, 0.5];
+    let frame = [1.0, 2.0, -1.0, 0.5];
+    let mut fft = WindowedFft::new(4).unwrap().with_window(&window).unwrap();
+
+    let spectrum = fft.process(&frame).unwrap().to_vec();
+
+    for (k, bin) in spectrum.iter().enumerate() {
+      let expected: Complex<f32> = (0..4)
+        .map(|n| Complex::from_polar(frame[n] * window[n], (-2.0 * PI * ((k * n) as f32)) / 4.0))
+        .sum();
+      assert_abs_diff_eq!(bin.re, expected.re, epsilon = 1e-5);
+      assert_abs_diff_eq!(bin.im, expected.im, epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_windowed_fft_length_mismatch() {
+    let mut fft = WindowedFft::new(16).unwrap();
+
+    assert_eq!(fft.fft_length(), 16);
+    assert_eq!(fft.process(&[0.0; 15]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(WindowedFft::new(16).unwrap().with_window(&[1.0; 8]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(WindowedFft::new(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
@@ -0,0 +1,139 @@
+// The largest power of two of a usize
+const MAX_POWER_OF_TWO: usize = 1 << (usize::BITS - 1);
+
+/// The power of two closest to `length`.
+///
+/// Lengths halfway between two powers of two, like 3 or 6, are rounded up, which keeps the frequency
+/// resolution. Lengths of 0 and 1 give 1, and lengths above the largest power of two of a `usize` give
+/// that power of two.
+pub fn nearest_power_of_two(length: usize) -> usize {
+  if length <= 1 {
+    return 1;
+  }
+  if length >= MAX_POWER_OF_TWO {
+    return MAX_POWER_OF_TWO;
+  }
+  let upper = length.next_power_of_two();
+  let lower = upper / 2;
+  if upper - length <= length - lower { upper } else { lower }
+}
+
+/// The smallest length of at least `length` of the form `2^a * 3^b * 5^c`.
+///
+/// FFTs of these lengths are decomposed into radix-2, 3 and 5 butterflies, which is nearly as fast as
+/// a power of two, while landing much closer to lengths derived from durations and sample rates, e.g.
+/// 480 for 10 ms at 48 kHz. A length of 0 gives 1, and lengths above the largest power of two of a
+/// `usize` are returned unchanged.
+pub fn next_fast_length(length: usize) -> usize {
+  if length <= 1 {
+    return 1;
+  }
+  if length > MAX_POWER_OF_TWO {
+    return length;
+  }
+
+  // Complete every product of powers of 5 and 3 below the length with the smallest power of two
+  let mut best = length.next_power_of_two();
+  let mut fives = 1usize;
+  while fives < best {
+    let mut product = fives;
+    while product < best {
+      if let Some(candidate) = product.checked_mul(length.div_ceil(product).next_power_of_two()) {
+        best = best.min(candidate);
+      }
+      product = product.saturating_mul(3);
+    }
+    fives = fives.saturating_mul(5);
+  }
+  best
+}
+
+/// The policy rounding the frame lengths of an analysis to efficient FFT lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LengthRounding {
+  /// The length is used as given.
+  #[default]
+  Exact,
+  /// The length is rounded to the closest power of two, see [`nearest_power_of_two`].
+  NearestPowerOfTwo,
+  /// The length is rounded up to a power of two.
+  NextPowerOfTwo,
+  /// The length is rounded up to a product of powers of 2, 3 and 5, see [`next_fast_length`].
+  NextFastLength,
+}
+
+impl LengthRounding {
+  /// Round `length` by the policy.
+  pub fn round(self, length: usize) -> usize {
+    match self {
+      LengthRounding::Exact => length,
+      LengthRounding::NearestPowerOfTwo => nearest_power_of_two(length),
+      LengthRounding::NextPowerOfTwo => length.checked_next_power_of_two().unwrap_or(length),
+      LengthRounding::NextFastLength => next_fast_length(length),
+    }
+  }
+
+  /// Round `length` by the policy and report the adjustment, `None` if the length is unchanged.
+  pub fn adjust(self, length: usize) -> Option<LengthAdjustment> {
+    let adjusted = self.round(length);
+    (adjusted != length).then_some(LengthAdjustment { requested: length, adjusted })
+  }
+}
+
+/// A length changed by a [`LengthRounding`] policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthAdjustment {
+  /// The length asked for.
+  pub requested: usize,
+  /// The length used instead.
+  pub adjusted: usize,
+}
+
+#[cfg(test)]
+mod test_fft_length {
+  use super::*;
+
+  #[test]
+  fn test_nearest_power_of_two() {
+    let lengths = [0, 1, 2, 3, 5, 6, 7, 441, 800, 1000, 1536, 1537];
+    let expected = [1, 1, 2, 4, 4, 8, 8, 512, 1024, 1024, 2048, 2048];
+
+    assert_eq!(lengths.map(nearest_power_of_two), expected);
+    assert_eq!(nearest_power_of_two(1500), 1024);
+    assert_eq!(nearest_power_of_two(usize::MAX), MAX_POWER_OF_TWO);
+  }
+
+  #[test]
+  fn test_next_fast_length() {
+    let lengths = [0, 1, 7, 11, 13, 17, 97, 441, 480, 1000, 1025, 1323];
+    let expected = [1, 1, 8, 12, 15, 18, 100, 450, 480, 1000, 1080, 1350];
+
+    assert_eq!(lengths.map(next_fast_length), expected);
+
+    // Every length up to 2000 rounds to the smallest 5-smooth length not below it
+    let is_fast = |mut length: usize| {
+      for factor in [2, 3, 5] {
+        while length.is_multiple_of(factor) {
+          length /= factor;
+        }
+      }
+      length == 1
+    };
+    for length in 1..2000 {
+      let fast = next_fast_length(length);
+      assert!(fast >= length && is_fast(fast));
+      assert!((length..fast).all(|smaller| !is_fast(smaller)));
+    }
+  }
+
+  #[test]
+  fn test_length_rounding() {
+    assert_eq!(LengthRounding::Exact.adjust(441), None);
+    assert_eq!(LengthRounding::NearestPowerOfTwo.adjust(441), Some(LengthAdjustment { requested: 441, adjusted: 512 }));
+    assert_eq!(LengthRounding::NextPowerOfTwo.round(1025), 2048);
+    assert_eq!(LengthRounding::NextFastLength.adjust(441), Some(LengthAdjustment { requested: 441, adjusted: 450 }));
+    assert_eq!(LengthRounding::NextFastLength.adjust(480), None);
+  }
+}
@@ -0,0 +1,249 @@
+use std::f64::consts::PI;
+
+use crate::{ hann_window::HannWindowError, window_spec::WindowSpec };
+
+/// Design a linear-phase lowpass FIR filter by the window method.
+///
+/// This function takes the number of taps `num_taps`, the `cutoff` frequency in cycles per sample (the
+/// cutoff frequency divided by the sample rate) and the `window` tapering the ideal sinc impulse
+/// response. The taps are normalized to unit gain at 0 Hz. An error is returned if the cutoff is not
+/// in (0, 0.5) or if the number of taps is less than 2.
+pub fn fir_lowpass(num_taps: usize, cutoff: f32, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  check_band(&[cutoff])?;
+  let mut taps = windowed_sinc(num_taps, f64::from(cutoff), window)?;
+  normalize_gain(&mut taps, 0.0);
+
+  Ok(taps)
+}
+
+/// Design a linear-phase highpass FIR filter by the window method.
+///
+/// The filter is the spectral inversion of the lowpass filter with the same cutoff, normalized to unit
+/// gain at the Nyquist frequency. The number of taps must be odd, since an even-length symmetric
+/// filter always has a zero at the Nyquist frequency. See [`fir_lowpass`] for the parameters.
+pub fn fir_highpass(num_taps: usize, cutoff: f32, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  // Only odd-length symmetric filters can pass the Nyquist frequency
+  if num_taps.is_multiple_of(2) {
+    return Err(HannWindowError::InvalidTapCount);
+  }
+  let mut taps = fir_lowpass(num_taps, cutoff, window)?;
+  spectral_inversion(&mut taps);
+  normalize_gain(&mut taps, 0.5);
+
+  Ok(taps)
+}
+
+/// Design a linear-phase bandpass FIR filter by the window method.
+///
+/// The filter is a lowpass filter of half the bandwidth shifted to the center of the band, between the
+/// `low` and `high` frequencies in cycles per sample, normalized to unit gain at the center of the
+/// band. An error is returned if the frequencies are not increasing within (0, 0.5).
+pub fn fir_bandpass(num_taps: usize, low: f32, high: f32, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  check_band(&[low, high])?;
+  let center = (f64::from(low) + f64::from(high)) / 2.0;
+  let mut taps = windowed_sinc(num_taps, (f64::from(high) - f64::from(low)) / 2.0, window)?;
+
+  // Shift the lowpass response from 0 Hz to the center of the band
+  let middle = ((num_taps - 1) as f64) / 2.0;
+  for (n, tap) in taps.iter_mut().enumerate() {
+    *tap *= (2.0 * (2.0 * PI * center * ((n as f64) - middle)).cos()) as f32;
+  }
+  normalize_gain(&mut taps, center);
+
+  Ok(taps)
+}
+
+/// Design a linear-phase bandstop FIR filter by the window method.
+///
+/// The filter is the spectral inversion of the bandpass filter for the same band, normalized to unit
+/// gain at 0 Hz. The number of taps must be odd. See [`fir_bandpass`] for the parameters.
+pub fn fir_bandstop(num_taps: usize, low: f32, high: f32, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  // Only odd-length symmetric filters can pass the Nyquist frequency
+  if num_taps.is_multiple_of(2) {
+    return Err(HannWindowError::InvalidTapCount);
+  }
+  let mut taps = fir_bandpass(num_taps, low, high, window)?;
+  spectral_inversion(&mut taps);
+  normalize_gain(&mut taps, 0.0);
+
+  Ok(taps)
+}
+
+/// Design a linear-phase FIR Hilbert transformer by the window method.
+///
+/// The ideal impulse response `(1 - cos(π * t)) / (π * t)`, with `t` the distance of each tap from the
+/// center, is tapered by the `window`. An odd number of taps gives a type III filter, whose every other
+/// tap is zero and whose response vanishes at 0 Hz and at the Nyquist frequency. An even number of
+/// taps gives a type IV filter, which only vanishes at 0 Hz but delays the output by half a sample.
+/// Convolving a signal with the taps shifts every component by -90 degrees. An error is returned if the
+/// number of taps is less than 2.
+pub fn fir_hilbert(num_taps: usize, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  let window = window.generate(num_taps)?;
+  let middle = ((num_taps - 1) as f64) / 2.0;
+
+  let taps = window
+    .iter()
+    .enumerate()
+    .map(|(n, &value)| {
+      let t = (n as f64) - middle;
+      let ideal = if t == 0.0 { 0.0 } else { (1.0 - (PI * t).cos()) / (PI * t) };
+      (ideal * f64::from(value)) as f32
+    })
+    .collect();
+
+  Ok(taps)
+}
+
+// Check that the band edges are increasing and lie strictly between 0 and the Nyquist frequency
+fn check_band(frequencies: &[f32]) -> Result<(), HannWindowError> {
+  let in_range = frequencies.iter().all(|frequency| *frequency > 0.0 && *frequency < 0.5);
+  let increasing = frequencies.windows(2).all(|pair| pair[0] < pair[1]);
+  if !in_range || !increasing {
+    return Err(HannWindowError::InvalidCutoff);
+  }
+  Ok(())
+}
+
+// The ideal lowpass impulse response `2 * cutoff * sinc(2 * cutoff * (n - middle))`, tapered by the window
+fn windowed_sinc(num_taps: usize, cutoff: f64, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  let window = window.generate(num_taps)?;
+  let middle = ((num_taps - 1) as f64) / 2.0;
+
+  let taps = window
+    .iter()
+    .enumerate()
+    .map(|(n, &value)| {
+      let t = (n as f64) - middle;
+      let sinc = if t == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * t).sin() / (PI * t) };
+      (sinc * f64::from(value)) as f32
+    })
+    .collect();
+
+  Ok(taps)
+}
+
+// Turn a lowpass or bandpass response into its complement by subtracting it from a centered impulse
+fn spectral_inversion(taps: &mut [f32]) {
+  taps.iter_mut().for_each(|tap| {
+    *tap = -*tap;
+  });
+  taps[taps.len() / 2] += 1.0;
+}
+
+// Scale the taps to unit gain at `frequency`, in cycles per sample
+fn normalize_gain(taps: &mut [f32], frequency: f64) {
+  // The response of a symmetric filter at `frequency` is real once its linear phase is removed
+  let middle = ((taps.len() - 1) as f64) / 2.0;
+  let gain: f64 = taps
+    .iter()
+    .enumerate()
+    .map(|(n, &tap)| f64::from(tap) * (2.0 * PI * frequency * ((n as f64) - middle)).cos())
+    .sum();
+  taps.iter_mut().for_each(|tap| {
+    *tap = ((f64::from(*tap)) / gain) as f32;
+  });
+}
+
+#[cfg(test)]
+mod test_fir {
+  use approx::assert_abs_diff_eq;
+
+  use crate::window_spec::{ kaiser_beta, kaiser_num_taps };
+
+  use super::*;
+
+  // The magnitude of the frequency response at `frequency`, in cycles per sample
+  fn gain(taps: &[f32], frequency: f64) -> f64 {
+    let (real, imaginary) = taps.iter().enumerate().fold((0.0, 0.0), |(real, imaginary), (n, &tap)| {
+      let phase = 2.0 * PI * frequency * (n as f64);
+      (real + f64::from(tap) * phase.cos(), imaginary - f64::from(tap) * phase.sin())
+    });
+    (real * real + imaginary * imaginary).sqrt()
+  }
+
+  #[test]
+  fn test_fir_lowpass_kaiser_design() {
+    let num_taps = kaiser_num_taps(60.0, 0.05).unwrap();
+    let taps = fir_lowpass(num_taps, 0.2, WindowSpec::Kaiser { beta: kaiser_beta(60.0) }).unwrap();
+
+    // The filter is symmetric with unit gain at 0 Hz and reaches the attenuation past the transition band
+    for i in 0..num_taps / 2 {
+      assert_abs_diff_eq!(taps[i], taps[num_taps - 1 - i], epsilon = 1e-7);
+    }
+    assert_abs_diff_eq!(gain(&taps, 0.0), 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(gain(&taps, 0.2), 0.5, epsilon = 0.01);
+    for frequency in [0.23, 0.3, 0.4, 0.5] {
+      assert!(gain(&taps, frequency) < 1e-3);
+    }
+  }
+
+  #[test]
+  fn test_fir_highpass() {
+    let taps = fir_highpass(101, 0.1, WindowSpec::Hamming).unwrap();
+
+    assert_abs_diff_eq!(gain(&taps, 0.5), 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(gain(&taps, 0.3), 1.0, epsilon = 0.01);
+    assert!(gain(&taps, 0.0) < 1e-3);
+  }
+
+  #[test]
+  fn test_fir_bandpass_and_bandstop() {
+    let bandpass = fir_bandpass(128, 0.1, 0.2, WindowSpec::Blackman).unwrap();
+    let bandstop = fir_bandstop(129, 0.1, 0.2, WindowSpec::Blackman).unwrap();
+
+    assert_abs_diff_eq!(gain(&bandpass, 0.15), 1.0, epsilon = 1e-6);
+    assert!(gain(&bandpass, 0.0) < 1e-3);
+    assert!(gain(&bandpass, 0.35) < 1e-3);
+    assert_abs_diff_eq!(gain(&bandstop, 0.0), 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(gain(&bandstop, 0.5), 1.0, epsilon = 0.01);
+    assert!(gain(&bandstop, 0.15) < 1e-3);
+  }
+
+  #[test]
+  fn test_fir_hilbert() {
+    let type_three = fir_hilbert(101, WindowSpec::Kaiser { beta: 8.0 }).unwrap();
+    let type_four = fir_hilbert(100, WindowSpec::Kaiser { beta: 8.0 }).unwrap();
+
+    // The taps are antisymmetric, and every other tap of a type III filter is zero
+    for i in 0..50 {
+      assert_abs_diff_eq!(type_three[i], -type_three[100 - i], epsilon = 1e-7);
+      assert_abs_diff_eq!(type_four[i], -type_four[99 - i], epsilon = 1e-7);
+    }
+    assert!(type_three.iter().step_by(2).all(|tap| tap.abs() < 1e-7));
+
+    // Both pass the middle of the band with unit gain, only the type IV filter passes the Nyquist frequency
+    for frequency in [0.1, 0.25, 0.4] {
+      assert_abs_diff_eq!(gain(&type_three, frequency), 1.0, epsilon = 0.01);
+      assert_abs_diff_eq!(gain(&type_four, frequency), 1.0, epsilon = 0.01);
+    }
+    assert!(gain(&type_three, 0.0) < 1e-6);
+    assert!(gain(&type_three, 0.5) < 1e-6);
+    assert!(gain(&type_four, 0.0) < 1e-6);
+    assert!(gain(&type_four, 0.5) > 0.5);
+  }
+
+  #[test]
+  fn test_fir_hilbert_quadrature() {
+    // A cosine at a quarter of the sample rate turns into a sine
+    let taps = fir_hilbert(63, WindowSpec::Blackman).unwrap();
+    let signal: Vec<f32> = (0..200).map(|n| ((n as f64) * PI * 0.5).cos() as f32).collect();
+
+    for n in 100..110 {
+      let output: f32 = taps.iter().enumerate().map(|(k, tap)| tap * signal[n - k]).sum();
+      // The filter delays the output by its center tap
+      let expected = (((n - 31) as f64) * PI * 0.5).sin() as f32;
+      assert_abs_diff_eq!(output, expected, epsilon = 0.01);
+    }
+  }
+
+  #[test]
+  fn test_fir_invalid_parameters() {
+    assert_eq!(fir_lowpass(31, 0.5, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(fir_lowpass(31, 0.0, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(fir_lowpass(1, 0.1, WindowSpec::Hann).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(fir_highpass(30, 0.1, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidTapCount);
+    assert_eq!(fir_bandpass(31, 0.2, 0.1, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(fir_bandstop(30, 0.1, 0.2, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidTapCount);
+    assert_eq!(fir_hilbert(1, WindowSpec::Hann).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
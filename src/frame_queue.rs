@@ -0,0 +1,269 @@
+use std::{ cell::UnsafeCell, sync::{ atomic::{ AtomicUsize, Ordering }, Arc } };
+
+use crate::{ hann_window::HannWindowError, instrument::{ record_metric, trace_allocation, trace_event } };
+
+// The state shared by the two ends of a frame queue
+struct SharedQueue {
+  // `capacity` slots of `frame_length` samples, stored back to back
+  slots: Box<[UnsafeCell<f32>]>,
+  capacity: usize,
+  frame_length: usize,
+  // Number of frames popped so far, only written by the consumer
+  head: AtomicUsize,
+  // Number of frames pushed so far, only written by the producer
+  tail: AtomicUsize,
+  // Number of frames rejected because the queue was full
+  dropped_frames: AtomicUsize,
+}
+
+// A slot is only ever accessed by one end of the queue at a time: the producer writes slots between
+// `tail` and `head + capacity`, and the consumer reads slots between `head` and `tail`. Publishing a
+// slot by moving `tail` (or releasing it by moving `head`) uses release/acquire ordering.
+unsafe impl Sync for SharedQueue {}
+
+impl SharedQueue {
+  // Get a pointer to the first sample of the slot used by the frame with the given sequence number
+  fn slot_pointer(&self, sequence: usize) -> *mut f32 {
+    let offset = (sequence % self.capacity) * self.frame_length;
+    UnsafeCell::raw_get(self.slots[offset..].as_ptr())
+  }
+}
+
+/// The real-time end of a frame queue, pushing frames without allocating, locking or blocking.
+pub struct FrameProducer {
+  shared: Arc<SharedQueue>,
+}
+
+/// The worker end of a frame queue, popping the frames in the order they were pushed.
+///
+/// With the `tracing` feature, the frames dropped by the producer since the previous call are reported
+/// by a `WARN` event on the next call to [`FrameConsumer::pop`] or [`FrameConsumer::pop_with`], on the
+/// thread of the consumer.
+pub struct FrameConsumer {
+  shared: Arc<SharedQueue>,
+  // The number of dropped frames already reported
+  reported_dropped_frames: usize,
+}
+
+/// Create a bounded single-producer, single-consumer queue of `capacity` frames of `frame_length` samples.
+///
+/// This function allocates every slot up front and returns the two ends of the queue, which can be
+/// moved to different threads. Pushing and popping copy frames in and out of the fixed-size slots and
+/// never allocate, lock or block: pushing onto a full queue drops the frame and counts it instead. An
+/// error is returned if the capacity or the frame length is 0.
+pub fn frame_queue(
+  capacity: usize,
+  frame_length: usize
+) -> Result<(FrameProducer, FrameConsumer), HannWindowError> {
+  // A frame must contain at least one sample
+  if frame_length == 0 {
+    return Err(HannWindowError::InvalidFrameLength);
+  }
+  // The queue must hold at least one frame
+  if capacity == 0 {
+    return Err(HannWindowError::InvalidCapacity);
+  }
+  // Check that the slots can be allocated
+  let sample_count = capacity.checked_mul(frame_length).ok_or(HannWindowError::MemoryAllocationError)?;
+  trace_allocation("frame_queue", sample_count * std::mem::size_of::<f32>());
+
+  let shared = Arc::new(SharedQueue {
+    slots: (0..sample_count).map(|_| UnsafeCell::new(0.0)).collect(),
+    capacity,
+    frame_length,
+    head: AtomicUsize::new(0),
+    tail: AtomicUsize::new(0),
+    dropped_frames: AtomicUsize::new(0),
+  });
+
+  Ok((FrameProducer { shared: shared.clone() }, FrameConsumer { shared, reported_dropped_frames: 0 }))
+}
+
+impl FrameProducer {
+  /// Copy a frame into the queue.
+  ///
+  /// Returns `Ok(true)` if the frame was queued, `Ok(false)` if the queue was full and the frame was
+  /// dropped, and an error if the length of the frame is different from the frame length.
+  pub fn push(&mut self, frame: &[f32]) -> Result<bool, HannWindowError> {
+    // The frame must fit the slot exactly
+    if frame.len() != self.shared.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    Ok(self.push_with(|slot| slot.copy_from_slice(frame)))
+  }
+
+  /// Fill the next free slot in place with `fill`, avoiding an intermediate copy.
+  ///
+  /// Returns `false` without calling `fill` if the queue is full, in which case the frame is counted
+  /// as dropped.
+  pub fn push_with<F: FnOnce(&mut [f32])>(&mut self, fill: F) -> bool {
+    let shared = &*self.shared;
+    let tail = shared.tail.load(Ordering::Relaxed);
+    let head = shared.head.load(Ordering::Acquire);
+
+    // Drop the frame if the consumer has not freed a slot yet
+    if tail.wrapping_sub(head) == shared.capacity {
+      // Drops are only counted here, the consumer reports them when it pops, off the real-time thread
+      shared.dropped_frames.fetch_add(1, Ordering::Relaxed);
+      record_metric!(record_dropped_frame);
+      return false;
+    }
+
+    // The slot at `tail` is not visible to the consumer until `tail` is advanced
+    let slot = unsafe { std::slice::from_raw_parts_mut(shared.slot_pointer(tail), shared.frame_length) };
+    fill(slot);
+    shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+    true
+  }
+
+  /// The length of every frame in the queue.
+  pub fn frame_length(&self) -> usize {
+    self.shared.frame_length
+  }
+
+  /// The number of frames dropped because the queue was full.
+  pub fn dropped_frames(&self) -> usize {
+    self.shared.dropped_frames.load(Ordering::Relaxed)
+  }
+}
+
+impl FrameConsumer {
+  /// Copy the oldest queued frame into `frame`.
+  ///
+  /// Returns `Ok(true)` if a frame was popped, `Ok(false)` if the queue was empty, and an error if the
+  /// length of the buffer is different from the frame length.
+  pub fn pop(&mut self, frame: &mut [f32]) -> Result<bool, HannWindowError> {
+    // The buffer must fit the slot exactly
+    if frame.len() != self.shared.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    Ok(self.pop_with(|slot| frame.copy_from_slice(slot)).is_some())
+  }
+
+  /// Read the oldest queued frame in place with `read` and release its slot.
+  ///
+  /// Returns `None` without calling `read` if the queue is empty.
+  pub fn pop_with<R, F: FnOnce(&[f32]) -> R>(&mut self, read: F) -> Option<R> {
+    let shared = &*self.shared;
+    let head = shared.head.load(Ordering::Relaxed);
+    let tail = shared.tail.load(Ordering::Acquire);
+
+    // Report the frames the producer dropped since the last report, off the real-time thread
+    let dropped_frames = shared.dropped_frames.load(Ordering::Relaxed);
+    if dropped_frames != self.reported_dropped_frames {
+      let newly_dropped = dropped_frames.wrapping_sub(self.reported_dropped_frames);
+      trace_event!(WARN, newly_dropped, dropped_frames, "frames dropped by a full frame queue");
+      #[cfg(not(feature = "tracing"))]
+      let _ = newly_dropped;
+      self.reported_dropped_frames = dropped_frames;
+    }
+
+    // Nothing to pop until the producer publishes a frame
+    if head == tail {
+      return None;
+    }
+
+    // The slot at `head` is not reused by the producer until `head` is advanced
+    let slot = unsafe { std::slice::from_raw_parts(shared.slot_pointer(head), shared.frame_length) };
+    let result = read(slot);
+    shared.head.store(head.wrapping_add(1), Ordering::Release);
+
+    Some(result)
+  }
+
+  /// The number of frames waiting to be popped.
+  pub fn len(&self) -> usize {
+    let tail = self.shared.tail.load(Ordering::Acquire);
+    let head = self.shared.head.load(Ordering::Relaxed);
+    tail.wrapping_sub(head)
+  }
+
+  /// Whether no frame is waiting to be popped.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// The length of every frame in the queue.
+  pub fn frame_length(&self) -> usize {
+    self.shared.frame_length
+  }
+
+  /// The number of frames dropped because the queue was full.
+  pub fn dropped_frames(&self) -> usize {
+    self.shared.dropped_frames.load(Ordering::Relaxed)
+  }
+}
+
+#[cfg(test)]
+mod test_frame_queue {
+  use std::thread;
+
+  use super::*;
+
+  #[test]
+  fn test_frame_queue_push_pop() {
+    let (mut producer, mut consumer) = frame_queue(2, 3).unwrap();
+    let mut frame = [0.0; 3];
+
+    assert!(!consumer.pop(&mut frame).unwrap());
+    assert!(producer.push(&[1.0, 2.0, 3.0]).unwrap());
+    assert!(producer.push_with(|slot| slot.fill(4.0)));
+    assert_eq!(consumer.len(), 2);
+    assert!(consumer.pop(&mut frame).unwrap());
+    assert_eq!(frame, [1.0, 2.0, 3.0]);
+    assert_eq!(consumer.pop_with(|slot| slot.to_vec()), Some(vec![4.0; 3]));
+    assert!(consumer.is_empty());
+  }
+
+  #[test]
+  fn test_frame_queue_drops_when_full() {
+    let (mut producer, mut consumer) = frame_queue(1, 2).unwrap();
+
+    assert!(producer.push(&[1.0, 1.0]).unwrap());
+    assert!(!producer.push(&[2.0, 2.0]).unwrap());
+    assert_eq!(consumer.dropped_frames(), 1);
+    assert_eq!(consumer.pop_with(|slot| slot[0]), Some(1.0));
+    assert!(producer.push(&[3.0, 3.0]).unwrap());
+    assert_eq!(consumer.pop_with(|slot| slot[0]), Some(3.0));
+  }
+
+  #[test]
+  fn test_frame_queue_across_threads() {
+    const FRAME_COUNT: usize = 10_000;
+    let (mut producer, mut consumer) = frame_queue(4, 16).unwrap();
+
+    // Push numbered frames, retrying whenever the queue is full
+    let producer_thread = thread::spawn(move || {
+      for i in 0..FRAME_COUNT {
+        while !producer.push_with(|slot| slot.fill(i as f32)) {
+          thread::yield_now();
+        }
+      }
+    });
+
+    // Every frame must arrive intact and in order
+    let mut expected = 0;
+    while expected < FRAME_COUNT {
+      match consumer.pop_with(|slot| slot.iter().all(|&sample| sample == (expected as f32))) {
+        Some(intact) => {
+          assert!(intact);
+          expected += 1;
+        }
+        None => thread::yield_now(),
+      }
+    }
+    producer_thread.join().unwrap();
+  }
+
+  #[test]
+  fn test_frame_queue_invalid_parameters() {
+    assert_eq!(frame_queue(0, 4).err(), Some(HannWindowError::InvalidCapacity));
+    assert_eq!(frame_queue(4, 0).err(), Some(HannWindowError::InvalidFrameLength));
+    assert_eq!(frame_queue(usize::MAX, 2).err(), Some(HannWindowError::MemoryAllocationError));
+
+    let (mut producer, mut consumer) = frame_queue(1, 2).unwrap();
+    assert_eq!(producer.push(&[1.0]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(consumer.pop(&mut [0.0; 3]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+
+use crate::{ apply_window::apply_window, hann_window::HannWindowError };
+
+/// Padding applied to the edges of a signal when it is split into frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PaddingMode {
+  /// No padding: only frames lying entirely inside the signal are yielded.
+  None,
+  /// The end of the signal is zero-padded so that every sample falls into at least one frame.
+  Zero,
+  /// `frame_length / 2` zeros are added to both ends, so that frame `k` is centered on sample `k * hop_size`.
+  CenterZero,
+  /// Like `CenterZero`, but the signal is mirrored around its edges (without repeating the edge sample).
+  CenterReflect,
+}
+
+/// An iterator over the (optionally windowed) frames of a signal.
+///
+/// Created by the [`frames`] function. Frames are yielded as `Cow` slices: a frame that lies entirely
+/// inside the signal and is not windowed is borrowed straight from the signal, every other frame is
+/// copied into a new buffer. Use [`Frames::next_into`] to fill a reusable buffer instead.
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+  signal: &'a [f32],
+  window: Option<&'a [f32]>,
+  frame_length: usize,
+  hop_size: usize,
+  padding_mode: PaddingMode,
+  // Position of the first sample of the first frame, negative when the signal is center padded
+  offset: isize,
+  next_frame: usize,
+  frame_count: usize,
+}
+
+/// Split a signal into frames of `frame_length` samples, advancing by `hop_size` samples per frame.
+///
+/// This function takes a slice `signal`, the desired `frame_length` and `hop_size`, and a `padding_mode`
+/// describing how the edges of the signal are handled, and returns a [`Frames`] iterator. An error is
+/// returned if the frame length or the hop size is 0. Call [`Frames::with_window`] to have every frame
+/// multiplied by a window, e.g. one returned by `get_hann_window(frame_length)`.
+pub fn frames(
+  signal: &[f32],
+  frame_length: usize,
+  hop_size: usize,
+  padding_mode: PaddingMode
+) -> Result<Frames<'_>, HannWindowError> {
+  // A frame must contain at least one sample
+  if frame_length == 0 {
+    return Err(HannWindowError::InvalidFrameLength);
+  }
+  // The iterator must advance on every frame
+  if hop_size == 0 {
+    return Err(HannWindowError::InvalidHopSize);
+  }
+
+  // Centered frames start half a frame before the beginning of the signal
+  let offset = match padding_mode {
+    PaddingMode::CenterZero | PaddingMode::CenterReflect => -((frame_length / 2) as isize),
+    PaddingMode::None | PaddingMode::Zero => 0,
+  };
+
+  Ok(Frames {
+    signal,
+    window: None,
+    frame_length,
+    hop_size,
+    padding_mode,
+    offset,
+    next_frame: 0,
+    frame_count: get_frame_count(signal.len(), frame_length, hop_size, padding_mode),
+  })
+}
+
+/// Compute the number of frames produced for a signal of `signal_length` samples.
+///
+/// This function returns the number of frames the [`frames`] iterator yields for the given parameters.
+/// An empty signal never produces a frame.
+pub fn get_frame_count(
+  signal_length: usize,
+  frame_length: usize,
+  hop_size: usize,
+  padding_mode: PaddingMode
+) -> usize {
+  // Invalid parameters and empty signals do not produce any frames
+  if signal_length == 0 || frame_length == 0 || hop_size == 0 {
+    return 0;
+  }
+
+  match padding_mode {
+    // Only full frames are counted
+    PaddingMode::None if signal_length < frame_length => 0,
+    PaddingMode::None => 1 + (signal_length - frame_length) / hop_size,
+    // The last frame may be partially filled with zeros
+    PaddingMode::Zero if signal_length <= frame_length => 1,
+    PaddingMode::Zero => 1 + (signal_length - frame_length).div_ceil(hop_size),
+    // Half a frame is added to both ends, which always leaves room for at least one frame
+    PaddingMode::CenterZero | PaddingMode::CenterReflect => {
+      let padded_length = signal_length + 2 * (frame_length / 2);
+      1 + (padded_length - frame_length) / hop_size
+    }
+  }
+}
+
+impl<'a> Frames<'a> {
+  /// Multiply every yielded frame by `window`.
+  ///
+  /// An error is returned if the length of the window is different from the frame length.
+  pub fn with_window(mut self, window: &'a [f32]) -> Result<Self, HannWindowError> {
+    // The window must cover the frame sample for sample
+    if window.len() != self.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.window = Some(window);
+    Ok(self)
+  }
+
+  /// The length of every yielded frame.
+  pub fn frame_length(&self) -> usize {
+    self.frame_length
+  }
+
+  /// The number of samples between the starts of two consecutive frames.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// The total number of frames, including those already yielded.
+  pub fn frame_count(&self) -> usize {
+    self.frame_count
+  }
+
+  /// Copy the next frame into `buffer`.
+  ///
+  /// Returns `Ok(true)` if a frame was written, `Ok(false)` once the iterator is exhausted, and an error
+  /// if the length of the buffer is different from the frame length. This does not allocate.
+  pub fn next_into(&mut self, buffer: &mut [f32]) -> Result<bool, HannWindowError> {
+    // The buffer must hold exactly one frame
+    if buffer.len() != self.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    // Stop once every frame has been yielded
+    if self.next_frame >= self.frame_count {
+      return Ok(false);
+    }
+
+    let start = self.frame_start(self.next_frame);
+    self.fill_frame(start, buffer);
+    self.next_frame += 1;
+
+    Ok(true)
+  }
+
+  // Compute the signal position of the first sample of the frame with the given index
+  fn frame_start(&self, frame_index: usize) -> isize {
+    self.offset + ((frame_index * self.hop_size) as isize)
+  }
+
+  // Copy the frame starting at `start` into `buffer`, applying the padding and the window
+  fn fill_frame(&self, start: isize, buffer: &mut [f32]) {
+    let signal_length = self.signal.len() as isize;
+    let end = start + (self.frame_length as isize);
+
+    // Copy the part of the frame that overlaps the signal in one go
+    let first = start.clamp(0, signal_length);
+    let last = end.clamp(0, signal_length);
+    let inside = (first - start) as usize..(last - start) as usize;
+    buffer[inside.clone()].copy_from_slice(&self.signal[first as usize..last as usize]);
+
+    // Pad the samples before and after the signal
+    for (i, sample) in buffer.iter_mut().enumerate() {
+      if inside.contains(&i) {
+        continue;
+      }
+      *sample = match self.padding_mode {
+        PaddingMode::CenterReflect => self.signal[reflect_index(start + (i as isize), self.signal.len())],
+        PaddingMode::None | PaddingMode::Zero | PaddingMode::CenterZero => 0.0,
+      };
+    }
+
+    // Multiply the frame by the window, the lengths were checked on construction
+    if let Some(window) = self.window {
+      apply_window(buffer, window).expect("Window length was checked on construction");
+    }
+  }
+}
+
+impl<'a> Iterator for Frames<'a> {
+  type Item = Cow<'a, [f32]>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    // Stop once every frame has been yielded
+    if self.next_frame >= self.frame_count {
+      return None;
+    }
+
+    let start = self.frame_start(self.next_frame);
+    self.next_frame += 1;
+
+    // Frames that lie inside the signal and are not windowed are borrowed without copying
+    let end = start + (self.frame_length as isize);
+    if self.window.is_none() && start >= 0 && end <= (self.signal.len() as isize) {
+      return Some(Cow::Borrowed(&self.signal[start as usize..end as usize]));
+    }
+
+    // Every other frame is copied into a new buffer
+    let mut buffer = vec![0.0; self.frame_length];
+    self.fill_frame(start, &mut buffer);
+    Some(Cow::Owned(buffer))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.frame_count - self.next_frame;
+    (remaining, Some(remaining))
+  }
+}
+
+impl ExactSizeIterator for Frames<'_> {}
+
+// Map a position outside of the signal onto the signal by mirroring it around the edges
+pub(crate) fn reflect_index(index: isize, signal_length: usize) -> usize {
+  // A single sample is its own reflection
+  if signal_length == 1 {
+    return 0;
+  }
+  // Reflection is periodic with a period of 2 * (N - 1), which also handles very short signals
+  let period = 2 * ((signal_length as isize) - 1);
+  let position = index.rem_euclid(period);
+  if position < (signal_length as isize) {
+    position as usize
+  } else {
+    (period - position) as usize
+  }
+}
+
+#[cfg(test)]
+mod test_frames {
+  use crate::get_hann_window;
+
+  use super::*;
+
+  const SIGNAL: [f32; 10] = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+
+  #[test]
+  fn test_frames_without_padding() {
+    let frames: Vec<_> = frames(&SIGNAL, 4, 3, PaddingMode::None).unwrap().collect();
+
+    assert_eq!(frames.len(), 3);
+    assert_eq!(&*frames[0], &[1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(&*frames[2], &[7.0, 8.0, 9.0, 10.0]);
+    assert!(frames.iter().all(|frame| matches!(frame, Cow::Borrowed(_))));
+  }
+
+  #[test]
+  fn test_frames_zero_padding() {
+    let frames: Vec<_> = frames(&SIGNAL, 4, 4, PaddingMode::Zero).unwrap().collect();
+
+    assert_eq!(frames.len(), 3);
+    assert_eq!(&*frames[2], &[9.0, 10.0, 0.0, 0.0]);
+    assert!(matches!(frames[2], Cow::Owned(_)));
+  }
+
+  #[test]
+  fn test_frames_center_padding() {
+    let zero: Vec<_> = frames(&SIGNAL, 4, 4, PaddingMode::CenterZero).unwrap().collect();
+    let reflect: Vec<_> = frames(&SIGNAL, 4, 4, PaddingMode::CenterReflect).unwrap().collect();
+
+    assert_eq!(zero.len(), 3);
+    assert_eq!(&*zero[0], &[0.0, 0.0, 1.0, 2.0]);
+    assert_eq!(&*reflect[0], &[3.0, 2.0, 1.0, 2.0]);
+    assert_eq!(&*reflect[2], &[7.0, 8.0, 9.0, 10.0]);
+  }
+
+  #[test]
+  fn test_frames_reflect_short_signal() {
+    let signal = [1.0, 2.0];
+    let frames: Vec<_> = frames(&signal, 8, 8, PaddingMode::CenterReflect).unwrap().collect();
+
+    assert_eq!(frames.len(), 1);
+    assert_eq!(&*frames[0], &[1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 1.0, 2.0]);
+  }
+
+  #[test]
+  fn test_frames_with_window() {
+    let window = get_hann_window(5).unwrap();
+    let signal = [2.0; 7];
+    let mut frames = frames(&signal, 5, 2, PaddingMode::None).unwrap().with_window(&window).unwrap();
+    let mut buffer = [0.0; 5];
+
+    assert_eq!(frames.len(), 2);
+    assert!(frames.next_into(&mut buffer).unwrap());
+    assert_eq!(buffer, [0.0, 1.0, 2.0, 1.0, 0.0]);
+    assert_eq!(&*frames.next().unwrap(), &[0.0, 1.0, 2.0, 1.0, 0.0]);
+    assert!(!frames.next_into(&mut buffer).unwrap());
+  }
+
+  #[test]
+  fn test_frames_frame_count() {
+    for padding_mode in [PaddingMode::None, PaddingMode::Zero, PaddingMode::CenterZero, PaddingMode::CenterReflect] {
+      for signal_length in 0..SIGNAL.len() {
+        let frames = frames(&SIGNAL[..signal_length], 4, 3, padding_mode).unwrap();
+
+        assert_eq!(frames.frame_count(), frames.count());
+      }
+    }
+    assert_eq!(get_frame_count(0, 4, 3, PaddingMode::Zero), 0);
+    assert_eq!(get_frame_count(3, 4, 3, PaddingMode::None), 0);
+  }
+
+  #[test]
+  fn test_frames_invalid_parameters() {
+    let window = get_hann_window(3).unwrap();
+
+    assert_eq!(frames(&SIGNAL, 0, 1, PaddingMode::None).unwrap_err(), HannWindowError::InvalidFrameLength);
+    assert_eq!(frames(&SIGNAL, 4, 0, PaddingMode::None).unwrap_err(), HannWindowError::InvalidHopSize);
+    assert_eq!(
+      frames(&SIGNAL, 4, 1, PaddingMode::None).unwrap().with_window(&window).unwrap_err(),
+      HannWindowError::LengthMismatch
+    );
+    assert_eq!(
+      frames(&SIGNAL, 4, 1, PaddingMode::None).unwrap().next_into(&mut [0.0; 3]).unwrap_err(),
+      HannWindowError::LengthMismatch
+    );
+  }
+}
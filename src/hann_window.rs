@@ -1,12 +1,29 @@
 use lazy_static::lazy_static;
 use std::{ collections::HashMap, error::Error, f32::consts::PI, fmt };
 
+use crate::{ instrument::{ record_metric, trace_allocation, trace_event }, window_spec::{ MAX_COSINE_SUM_TERMS, WINDOW_NAMES } };
+
 /// Error type for the Hann window function.
 #[derive(Debug, PartialEq)]
 pub enum HannWindowError {
   WindowLengthTooSmall,
   WindowLengthTooLarge,
   MemoryAllocationError,
+  InvalidFrameLength,
+  InvalidHopSize,
+  LengthMismatch,
+  InvalidCapacity,
+  InvalidChannelCount,
+  InvalidSampleRate,
+  InvalidCutoff,
+  InvalidTapCount,
+  InvalidSamplesPerSymbol,
+  InvalidRollOff,
+  InvalidShapeParameter,
+  InvalidBandCount,
+  InvalidTermCount,
+  UnknownWindow,
+  InvalidIdentifier,
 }
 
 // Implement the Error trait for the HannWindowError struct
@@ -26,6 +43,51 @@ impl fmt::Display for HannWindowError {
       HannWindowError::MemoryAllocationError => {
         write!(f, "HannWindowError: Window length is too large to allocate memory.")
       }
+      HannWindowError::InvalidFrameLength => {
+        write!(f, "HannWindowError: Frame length must be greater than 0.")
+      }
+      HannWindowError::InvalidHopSize => {
+        write!(f, "HannWindowError: Hop size must be greater than 0.")
+      }
+      HannWindowError::LengthMismatch => {
+        write!(f, "HannWindowError: Buffer length does not match the window length.")
+      }
+      HannWindowError::InvalidCapacity => {
+        write!(f, "HannWindowError: Capacity must be greater than 0.")
+      }
+      HannWindowError::InvalidChannelCount => {
+        write!(f, "HannWindowError: Channel count does not match.")
+      }
+      HannWindowError::InvalidSampleRate => {
+        write!(f, "HannWindowError: Sample rate must be greater than 0.")
+      }
+      HannWindowError::InvalidCutoff => {
+        write!(f, "HannWindowError: Frequencies must be between 0 and 0.5 cycles per sample, in increasing order.")
+      }
+      HannWindowError::InvalidTapCount => {
+        write!(f, "HannWindowError: Filters passing the Nyquist frequency must have an odd number of taps.")
+      }
+      HannWindowError::InvalidSamplesPerSymbol => {
+        write!(f, "HannWindowError: Samples per symbol must be greater than 0.")
+      }
+      HannWindowError::InvalidRollOff => {
+        write!(f, "HannWindowError: Roll-off factor must be between 0 and 1.")
+      }
+      HannWindowError::InvalidShapeParameter => {
+        write!(f, "HannWindowError: Window shape parameter must be greater than 0.")
+      }
+      HannWindowError::InvalidBandCount => {
+        write!(f, "HannWindowError: Band count must be greater than 0.")
+      }
+      HannWindowError::InvalidTermCount => {
+        write!(f, "HannWindowError: Cosine-sum term count must be between 1 and {}.", MAX_COSINE_SUM_TERMS)
+      }
+      HannWindowError::UnknownWindow => {
+        write!(f, "HannWindowError: Unknown window name, expected one of {}.", WINDOW_NAMES.join(", "))
+      }
+      HannWindowError::InvalidIdentifier => {
+        write!(f, "HannWindowError: Array name must be an identifier other than a keyword.")
+      }
     }
   }
 }
@@ -58,6 +120,22 @@ lazy_static! {
 /// values are computed using the formula `w(n) = 0.5 - 0.5 * cos(2π * n / (N - 1))`, where `n` is the
 /// index of the current sample and `N` is the length of the window.
 pub fn get_hann_window(window_length: usize) -> Result<Vec<f32>, HannWindowError> {
+  check_window_length(window_length)?;
+  // Check if the window length is in the lookup table.
+  if let Some(hann_window) = HANN_WINDOW_LOOKUP_TABLE.get(&window_length) {
+    record_metric!(record_lookup, LookupTable::HannWindow, true);
+    Ok(hann_window.clone())
+  } else {
+    // If the window length is not in the lookup table, compute the Hann window values.
+    record_metric!(record_lookup, LookupTable::HannWindow, false);
+    trace_event!(DEBUG, window_length, "Hann window lookup table miss");
+    trace_allocation("get_hann_window", window_length * std::mem::size_of::<f32>());
+    calculate_hann_window(window_length)
+  }
+}
+
+// Check that a window of `window_length` values can be generated
+pub(crate) fn check_window_length(window_length: usize) -> Result<(), HannWindowError> {
   // If the window length is less than or equal to 1, return an array with a single element of 0.0
   if window_length <= 1 {
     return Err(HannWindowError::WindowLengthTooSmall);
@@ -70,15 +148,63 @@ pub fn get_hann_window(window_length: usize) -> Result<Vec<f32>, HannWindowError
   if window_length > 1 << 24 {
     return Err(HannWindowError::WindowLengthTooLarge);
   }
-  // Check if the window length is in the lookup table.
-  if let Some(hann_window) = HANN_WINDOW_LOOKUP_TABLE.get(&window_length) {
-    Ok(hann_window.clone())
-  } else {
-    // If the window length is not in the lookup table, compute the Hann window values.
-    calculate_hann_window(window_length)
+  Ok(())
+}
+
+/// A window length checked to lie between 2 and the allowed maximum.
+///
+/// Checking the length once, e.g. when configuring a processing chain, leaves the per-frame path to
+/// [`calculate_hann_window_into`], which only checks that the buffer holds the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowLength(usize);
+
+impl WindowLength {
+  /// Check a window length.
+  ///
+  /// An error is returned if the window length is less than or equal to 1, or too large, like for
+  /// [`get_hann_window`].
+  pub fn new(window_length: usize) -> Result<Self, HannWindowError> {
+    check_window_length(window_length)?;
+    Ok(WindowLength(window_length))
+  }
+
+  /// The number of window values.
+  pub fn get(self) -> usize {
+    self.0
   }
 }
 
+/// Compute a Hann window into a buffer, without allocating.
+///
+/// This function writes the values of the Hann window of `window_length`, the same as
+/// [`get_hann_window`] computes, to `window`, for per-frame paths whose length has been checked once
+/// with [`WindowLength`]. The loops run over slices split at the middle of the window, so that they
+/// need no bounds checks. An error is returned if `window` does not hold `window_length` values.
+pub fn calculate_hann_window_into(window_length: WindowLength, window: &mut [f32]) -> Result<(), HannWindowError> {
+  // The buffer must hold exactly the window
+  if window.len() != window_length.get() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Since the Hann window is symmetric, we can compute only half of the values and mirror them to the other half.
+  // Calculate the half-length of the window, accounting for odd window lengths.
+  let half_length = (window.len() + (window.len() % 2)) / 2;
+
+  // Compute the scaling factor for the Hann window: 2π / (N - 1)
+  let scaling_factor = (PI * 2.0) / ((window.len() - 1) as f32);
+
+  // Compute the first half of the Hann window values, and mirror them to the second half
+  // Formula used: w(n) = 0.5 - 0.5 * cos(2π * n / (N - 1))
+  let (first_half, second_half) = window.split_at_mut(half_length);
+  for (i, value) in first_half.iter_mut().enumerate() {
+    *value = 0.5 - 0.5 * (scaling_factor * (i as f32)).cos();
+  }
+  for (value, mirrored) in second_half.iter_mut().rev().zip(first_half.iter()) {
+    *value = *mirrored;
+  }
+  Ok(())
+}
+
 /// Computes a Hann window of length `window_length`.
 ///
 /// A Hann window is a function that smoothly tapers the edges of a signal window to reduce spectral leakage.
@@ -124,7 +250,7 @@ fn calculate_hann_window(window_length: usize) -> Result<Vec<f32>, HannWindowErr
   // Compute the first half of the Hann window values
   // Formula used: w(n) = 0.5 - 0.5 * cos(2π * n / (N - 1))
   for i in 0..half_length {
-    window[i] = 0.5 - 0.5 * ((scaling_factor * (i as f32)).cos() as f32);
+    window[i] = 0.5 - 0.5 * (scaling_factor * (i as f32)).cos();
     window[window_length - 1 - i] = window[i];
   }
 
@@ -132,7 +258,9 @@ fn calculate_hann_window(window_length: usize) -> Result<Vec<f32>, HannWindowErr
   Ok(window)
 }
 
+// The tests of the first release are kept as they were written
 #[cfg(test)]
+#[allow(clippy::needless_range_loop, clippy::useless_vec)]
 mod test_hann_window {
   use approx::{ assert_abs_diff_eq, relative_eq };
 
@@ -203,6 +331,28 @@ mod test_hann_window {
     }
   }
 
+  #[test]
+  fn test_calculate_hann_window_into() {
+    for window_length in [2, 5, 10, 256, 1000] {
+      let length = WindowLength::new(window_length).unwrap();
+      let mut window = vec![-1.0; length.get()];
+
+      calculate_hann_window_into(length, &mut window).unwrap();
+
+      // The values match the allocating computation
+      assert_eq!(window, get_hann_window(window_length).unwrap());
+    }
+    let length = WindowLength::new(8).unwrap();
+    assert_eq!(calculate_hann_window_into(length, &mut [0.0; 7]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_window_length() {
+    assert_eq!(WindowLength::new(1024).unwrap().get(), 1024);
+    assert_eq!(WindowLength::new(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(WindowLength::new(1 << 25).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+  }
+
   #[test]
   fn test_hann_window_length_too_small() {
     let window_length: usize = 1;
@@ -0,0 +1,162 @@
+/// Buffers of at least this many bytes are reported as large allocations.
+pub(crate) const LARGE_ALLOCATION_BYTES: usize = 1 << 20;
+
+// Emit a `tracing` event at the given level when the `tracing` feature is enabled, expand to nothing otherwise
+macro_rules! trace_event {
+  ($level:ident, $($argument:tt)*) => {
+    #[cfg(feature = "tracing")]
+    tracing::event!(target: "hann_rs", tracing::Level::$level, $($argument)*);
+  };
+}
+
+// Enter a `tracing` span until the end of the enclosing block when the `tracing` feature is enabled,
+// only used by the readers and the pipelines, never on the real-time paths
+#[cfg(any(feature = "symphonia", feature = "hound", feature = "toml", feature = "yaml"))]
+macro_rules! trace_span {
+  ($level:ident, $($argument:tt)*) => {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::span!(target: "hann_rs", tracing::Level::$level, $($argument)*).entered();
+  };
+}
+
+// Update a Prometheus metric when the `prometheus` feature is enabled, expand to nothing otherwise
+macro_rules! record_metric {
+  ($function:ident $(, $argument:expr)*) => {
+    #[cfg(feature = "prometheus")]
+    {
+      use crate::prometheus::*;
+      $function($($argument),*);
+    }
+  };
+}
+
+pub(crate) use record_metric;
+pub(crate) use trace_event;
+#[cfg(any(feature = "symphonia", feature = "hound", feature = "toml", feature = "yaml"))]
+pub(crate) use trace_span;
+
+// Report an allocation of `bytes` bytes made by `component` if it is a large one
+pub(crate) fn trace_allocation(component: &'static str, bytes: usize) {
+  if bytes >= LARGE_ALLOCATION_BYTES {
+    trace_event!(DEBUG, component, bytes, "large allocation");
+  }
+  #[cfg(not(feature = "tracing"))]
+  let _ = component;
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test_instrument {
+  use std::sync::{ atomic::{ AtomicUsize, Ordering }, Arc, Mutex };
+
+  use tracing::{ span, subscriber::with_default, Event, Level, Metadata, Subscriber };
+
+  use crate::{ frame_queue::frame_queue, get_hann_window, realtime::RealtimeProcessor };
+
+  // A subscriber counting the events at every level
+  #[derive(Default)]
+  struct CountingSubscriber {
+    spans: Arc<AtomicUsize>,
+    span_names: Arc<Mutex<Vec<&'static str>>>,
+    debug_events: Arc<AtomicUsize>,
+    warn_events: Arc<AtomicUsize>,
+  }
+
+  impl Subscriber for CountingSubscriber {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+      true
+    }
+
+    fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+      self.spans.fetch_add(1, Ordering::Relaxed);
+      self.span_names.lock().unwrap().push(attributes.metadata().name());
+      span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+      match *event.metadata().level() {
+        Level::DEBUG => self.debug_events.fetch_add(1, Ordering::Relaxed),
+        Level::WARN => self.warn_events.fetch_add(1, Ordering::Relaxed),
+        _ => 0,
+      };
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+  }
+
+  #[test]
+  fn test_trace_lookup_table_miss() {
+    let subscriber = CountingSubscriber::default();
+    let debug_events = subscriber.debug_events.clone();
+
+    with_default(subscriber, || {
+      get_hann_window(1024).unwrap();
+      assert_eq!(debug_events.load(Ordering::Relaxed), 0);
+      get_hann_window(1000).unwrap();
+      assert_eq!(debug_events.load(Ordering::Relaxed), 1);
+    });
+  }
+
+  #[test]
+  fn test_no_events_on_realtime_paths() {
+    let subscriber = CountingSubscriber::default();
+    let (spans, warn_events) = (subscriber.spans.clone(), subscriber.warn_events.clone());
+    let mut processor = RealtimeProcessor::new(&get_hann_window(256).unwrap(), 64).unwrap();
+    let (mut producer, consumer) = frame_queue(1, 2).unwrap();
+
+    with_default(subscriber, || {
+      let mut output = [0.0; 512];
+      processor.process(&[0.5; 512], &mut output, |_| {}).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+    });
+
+    // Drops are counted rather than reported from the real-time thread
+    assert_eq!(spans.load(Ordering::Relaxed), 0);
+    assert_eq!(warn_events.load(Ordering::Relaxed), 0);
+    assert_eq!(consumer.dropped_frames(), 2);
+  }
+
+  #[test]
+  fn test_trace_dropped_frames() {
+    let subscriber = CountingSubscriber::default();
+    let warn_events = subscriber.warn_events.clone();
+    let (mut producer, mut consumer) = frame_queue(1, 2).unwrap();
+
+    with_default(subscriber, || {
+      producer.push(&[0.0; 2]).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+
+      // The drops are reported once by the consumer, on its next pop
+      assert_eq!(warn_events.load(Ordering::Relaxed), 0);
+      assert!(consumer.pop_with(|_| ()).is_some());
+      assert_eq!(warn_events.load(Ordering::Relaxed), 1);
+      assert!(consumer.pop_with(|_| ()).is_none());
+      assert_eq!(warn_events.load(Ordering::Relaxed), 1);
+
+      producer.push(&[0.0; 2]).unwrap();
+      producer.push(&[0.0; 2]).unwrap();
+      assert!(consumer.pop_with(|_| ()).is_some());
+      assert_eq!(warn_events.load(Ordering::Relaxed), 2);
+    });
+  }
+
+  #[cfg(feature = "toml")]
+  #[test]
+  fn test_trace_pipeline_stages() {
+    let subscriber = CountingSubscriber::default();
+    let span_names = subscriber.span_names.clone();
+    let pipeline = crate::stft::Pipeline::from_toml("[framing]\nframe_length = 64\nhop_size = 32\n").unwrap();
+
+    with_default(subscriber, || pipeline.run(&[0.5; 256], Vec::new()).unwrap());
+
+    assert_eq!(*span_names.lock().unwrap(), ["pipeline_framing", "pipeline_analysis", "pipeline_export"]);
+  }
+}
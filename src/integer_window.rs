@@ -0,0 +1,138 @@
+use crate::hann_window::HannWindowError;
+
+/// A source of triangular probability density function (TPDF) dither.
+///
+/// Every offset is the difference of two uniform random values, spreading the rounding error of a
+/// windowed integer sample over (-1, 1) least significant bits, so that the quantization error does not
+/// correlate with the signal. The generator is a deterministic xorshift generator: the same seed always
+/// yields the same dither.
+#[derive(Debug, Clone)]
+pub struct TpdfDither {
+  state: u32,
+}
+
+impl TpdfDither {
+  /// Create a dither source from a seed.
+  pub fn new(seed: u32) -> Self {
+    // A xorshift generator never leaves the all-zero state
+    TpdfDither { state: if seed == 0 { 0x9e37_79b9 } else { seed } }
+  }
+
+  /// The next dither offset, in least significant bits, in the range (-1, 1).
+  pub fn next_offset(&mut self) -> f64 {
+    self.next_uniform() - self.next_uniform()
+  }
+
+  // The next uniform random value in [0, 1)
+  fn next_uniform(&mut self) -> f64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 17;
+    self.state ^= self.state << 5;
+    f64::from(self.state >> 8) / f64::from(1u32 << 24)
+  }
+}
+
+/// Multiply a 16-bit integer signal by a window in place.
+///
+/// This function takes a mutable slice `signal` and a slice `window` of the same length, multiplies
+/// every sample by the corresponding window value and rounds the product to the nearest integer,
+/// optionally after adding TPDF dither. Products outside the range of `i16` saturate. If the lengths of
+/// the two slices differ, an error is returned and the signal is left untouched.
+pub fn apply_window_i16(
+  signal: &mut [i16],
+  window: &[f32],
+  dither: Option<&mut TpdfDither>
+) -> Result<(), HannWindowError> {
+  apply_integer_window(signal, window, dither, f64::from, |value| value as i16)
+}
+
+/// Multiply a 32-bit integer signal by a window in place.
+///
+/// This function behaves like [`apply_window_i16`] for 32-bit samples, for example 24-bit samples
+/// stored in `i32`. The products are computed in double precision, so that every bit of the samples
+/// is kept.
+pub fn apply_window_i32(
+  signal: &mut [i32],
+  window: &[f32],
+  dither: Option<&mut TpdfDither>
+) -> Result<(), HannWindowError> {
+  apply_integer_window(signal, window, dither, f64::from, |value| value as i32)
+}
+
+// Multiply every integer sample by its window value, dither and round the product
+fn apply_integer_window<T: Copy>(
+  signal: &mut [T],
+  window: &[f32],
+  mut dither: Option<&mut TpdfDither>,
+  to_float: fn(T) -> f64,
+  from_float: fn(f64) -> T
+) -> Result<(), HannWindowError> {
+  // The window must cover the signal sample for sample
+  if signal.len() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  for (sample, &value) in signal.iter_mut().zip(window.iter()) {
+    let product = to_float(*sample) * f64::from(value);
+    let offset = dither.as_mut().map_or(0.0, |dither| dither.next_offset());
+    // Casting a float to an integer saturates at the bounds of the integer type
+    *sample = from_float((product + offset).round());
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_integer_window {
+  use crate::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_apply_window_i16_rounding() {
+    let window = get_hann_window(5).unwrap();
+    let mut signal = [1000, 3, -3, 7, i16::MIN];
+
+    apply_window_i16(&mut signal, &window, None).unwrap();
+
+    assert_eq!(signal, [0, 2, -3, 4, 0]);
+  }
+
+  #[test]
+  fn test_apply_window_i32_precision() {
+    let window = [1.0, 0.5, 0.5];
+    let mut signal = [i32::MAX, i32::MIN, 8388607];
+
+    apply_window_i32(&mut signal, &window, None).unwrap();
+
+    assert_eq!(signal, [i32::MAX, i32::MIN / 2, 4194304]);
+  }
+
+  #[test]
+  fn test_apply_window_dither() {
+    let window = vec![0.3; 10000];
+    let mut signal = vec![1001i16; 10000];
+    let mut dither = TpdfDither::new(1);
+
+    apply_window_i16(&mut signal, &window, Some(&mut dither)).unwrap();
+
+    // Every sample stays within one step of the exact product, and the error averages out
+    assert!(signal.iter().all(|sample| (299..=302).contains(sample)));
+    let mean = signal.iter().map(|sample| f64::from(*sample)).sum::<f64>() / 10000.0;
+    assert!((mean - 300.3).abs() < 0.05);
+
+    // The same seed yields the same dither
+    let mut repeated = vec![1001i16; 10000];
+    apply_window_i16(&mut repeated, &window, Some(&mut TpdfDither::new(1))).unwrap();
+    assert_eq!(signal, repeated);
+  }
+
+  #[test]
+  fn test_apply_integer_window_length_mismatch() {
+    let window = get_hann_window(5).unwrap();
+    let mut signal = [1; 4];
+
+    assert_eq!(apply_window_i16(&mut signal, &window, None).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(signal, [1; 4]);
+  }
+}
@@ -0,0 +1,17 @@
+//! The conversions to and from the types of other crates.
+//!
+//! This module gathers, with the `arrow`, `dasp`, `nalgebra` and `ndarray` features, the windowing of
+//! the signals, arrays and matrices of those crates.
+
+#[cfg(feature = "arrow")]
+pub use crate::arrow_interop::{ get_hann_window_arrow, window_to_arrow };
+#[cfg(all(feature = "arrow", feature = "realfft"))]
+pub use crate::arrow_interop::spectrogram_arrow;
+#[cfg(feature = "dasp")]
+pub use crate::dasp_interop::{ apply_window_to_frames, WindowSignalExt, WindowedSignal };
+#[cfg(feature = "nalgebra")]
+pub use crate::nalgebra_interop::{ apply_window_columns, frames_dmatrix, get_hann_window_dvector };
+#[cfg(feature = "ndarray")]
+pub use crate::ndarray_interop::{ apply_window_array, apply_window_rows, frames_array, get_hann_window_array };
+#[cfg(all(feature = "ndarray", feature = "realfft"))]
+pub use crate::ndarray_interop::spectrogram_array;
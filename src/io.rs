@@ -0,0 +1,17 @@
+//! The reading and writing of windows, spectra and audio files.
+//!
+//! This module gathers the export of windows as text and as Rust and C source, and, with the `npy`,
+//! `plotters`, `hound` and `symphonia` features, NumPy files, plots and the framing of audio files.
+
+pub use crate::{
+  codegen::{ rust_const, write_rust_const, CArrayGenerator, CElementType },
+  export::{ export_window, ExportFormat },
+};
+#[cfg(feature = "symphonia")]
+pub use crate::decode::{ DecodeError, DecodedFrameReader };
+#[cfg(feature = "npy")]
+pub use crate::npy::{ read_npy, read_npz, write_npy, write_npz, NpyArray, NpyError };
+#[cfg(feature = "plotters")]
+pub use crate::plot::{ draw_response_chart, draw_window_chart, plot_response, plot_window, Colormap, PlotError, SpectrogramPlot };
+#[cfg(feature = "hound")]
+pub use crate::wav_reader::{ WavError, WavFrameReader };
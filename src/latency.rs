@@ -0,0 +1,82 @@
+/// The algorithmic latency and group delay of a streaming component, in samples.
+///
+/// Frames are placed on the input timeline in two ways: by their first sample for the latency, and by
+/// their center for the group delay. For a frame producer such as the `StreamingWindower`, the latency
+/// is the delay between the first sample of a frame and its emission, and the group delay is the delay
+/// between the center of a frame and its emission. For a frame consumer such as the `OverlapAdd`, they
+/// are the delays between the first sample, or the center, of a pushed frame and the output of the
+/// corresponding sample. With these conventions, the latencies and group delays of chained components
+/// add up, see [`PipelineLatency`].
+pub trait Latency {
+  /// The number of samples the output of this component lags behind its input.
+  fn latency(&self) -> usize;
+
+  /// The delay in samples between the input and the instant the output of this component represents.
+  fn group_delay(&self) -> f64 {
+    self.latency() as f64
+  }
+}
+
+/// The total latency and group delay of a chain of streaming components.
+///
+/// Start with [`PipelineLatency::new`] and add every component of the chain with
+/// [`PipelineLatency::then`], in processing order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PipelineLatency {
+  latency: usize,
+  group_delay: f64,
+}
+
+impl PipelineLatency {
+  /// Create the latency of an empty chain.
+  pub fn new() -> Self {
+    PipelineLatency { latency: 0, group_delay: 0.0 }
+  }
+
+  /// Add a component to the end of the chain.
+  pub fn then<L: Latency + ?Sized>(self, component: &L) -> Self {
+    PipelineLatency {
+      latency: self.latency + component.latency(),
+      group_delay: self.group_delay + component.group_delay(),
+    }
+  }
+}
+
+impl Latency for PipelineLatency {
+  fn latency(&self) -> usize {
+    self.latency
+  }
+
+  fn group_delay(&self) -> f64 {
+    self.group_delay
+  }
+}
+
+#[cfg(test)]
+mod test_latency {
+  use crate::{ get_hann_window, overlap_add::OverlapAdd, realtime::RealtimeProcessor, streaming_windower::StreamingWindower };
+
+  use super::*;
+
+  #[test]
+  fn test_windower_latency() {
+    let windower = StreamingWindower::new(1024, 256).unwrap();
+
+    assert_eq!(windower.latency(), 1023);
+    assert_eq!(windower.group_delay(), 511.5);
+  }
+
+  #[test]
+  fn test_pipeline_latency_matches_realtime_processor() {
+    let window = get_hann_window(64).unwrap();
+    let windower = StreamingWindower::new(64, 16).unwrap();
+    let overlap_add = OverlapAdd::new(64, 16).unwrap();
+    let processor = RealtimeProcessor::new(&window, 16).unwrap();
+
+    let pipeline = PipelineLatency::new().then(&windower).then(&overlap_add);
+
+    assert_eq!(pipeline.latency(), processor.latency());
+    assert_eq!(pipeline.group_delay(), processor.group_delay());
+    assert_eq!(PipelineLatency::new().then(&pipeline).then(&processor).latency(), 126);
+  }
+}
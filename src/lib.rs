@@ -1,5 +1,122 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod apodize;
+#[cfg(feature = "std")]
+mod apply_window;
+#[cfg(feature = "arrow")]
+mod arrow_interop;
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "cpal")]
+mod capture;
+#[cfg(feature = "std")]
+mod codegen;
+#[cfg(feature = "std")]
+mod compare;
+#[cfg(feature = "std")]
+mod cosine_fit;
+#[cfg(feature = "dasp")]
+mod dasp_interop;
+#[cfg(feature = "symphonia")]
+mod decode;
+#[cfg(feature = "std")]
+mod downmix;
+#[cfg(feature = "std")]
+mod duration;
+#[cfg(feature = "std")]
+mod envelope;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "rustfft")]
+mod fft;
+#[cfg(feature = "std")]
+mod fft_length;
+#[cfg(feature = "std")]
+mod fir;
+#[cfg(feature = "std")]
+mod frame_queue;
+#[cfg(feature = "std")]
+mod frames;
+#[cfg(feature = "std")]
 mod hann_window;
+#[cfg(feature = "std")]
+mod instrument;
+#[cfg(feature = "std")]
+mod integer_window;
+#[cfg(feature = "std")]
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+mod latency;
+#[cfg(feature = "std")]
+mod mel;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "microfft")]
+mod micro_fft;
+#[cfg(feature = "std")]
+mod multichannel;
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+#[cfg(feature = "npy")]
+mod npy;
+#[cfg(feature = "std")]
+mod normalization;
+#[cfg(feature = "std")]
+mod ofdm;
+#[cfg(feature = "std")]
+mod overlap_add;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+mod pipeline;
+#[cfg(feature = "std")]
+mod planar;
+#[cfg(feature = "plotters")]
+mod plot;
+#[cfg(feature = "std")]
+mod polyphase;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "std")]
+mod pulse_shaping;
+#[cfg(feature = "realfft")]
+mod real_fft;
+#[cfg(feature = "std")]
+mod realtime;
+#[cfg(feature = "std")]
+mod resampler;
+#[cfg(feature = "std")]
+mod sample_format;
+#[cfg(feature = "std")]
+mod smooth;
+#[cfg(feature = "std")]
+mod sparse_window;
+#[cfg(feature = "realfft")]
+mod spectral;
+#[cfg(feature = "futures")]
+mod stream;
+pub mod stft;
+#[cfg(feature = "std")]
+pub mod streaming;
+#[cfg(feature = "std")]
+mod streaming_windower;
+#[cfg(feature = "std")]
 mod sum_of_hann_window_squares;
+#[cfg(feature = "std")]
+mod weighting;
+#[cfg(feature = "std")]
+mod window_spec;
+#[cfg(feature = "std")]
+pub mod windows;
+#[cfg(feature = "hound")]
+mod wav_reader;
 
-pub use hann_window::get_hann_window;
-pub use sum_of_hann_window_squares::get_hann_window_sum_squares;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub use hann_window::{ get_hann_window, HannWindowError };
+#[cfg(feature = "std")]
+pub use sum_of_hann_window_squares::get_hann_window_sum_squares;
@@ -0,0 +1,170 @@
+use crate::hann_window::HannWindowError;
+
+/// Convert a frequency in Hz to the mel scale, `2595 * log10(1 + f / 700)`.
+pub fn hz_to_mel(frequency: f32) -> f32 {
+  2595.0 * (1.0 + frequency / 700.0).log10()
+}
+
+/// Convert a frequency on the mel scale to Hz, the inverse of [`hz_to_mel`].
+pub fn mel_to_hz(mel: f32) -> f32 {
+  700.0 * (10.0f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// A bank of triangular filters spaced evenly on the mel scale.
+///
+/// The filters reduce the `fft_length / 2 + 1` bins of a power spectrum, e.g. a row of
+/// `spectrogram`, to `band_count` mel bands. Band `b` is a triangle rising from the center frequency
+/// of band `b - 1` to a peak of 1 at its own center frequency and falling to the center frequency of
+/// band `b + 1`, the outer edges being the minimum and the maximum frequency, as in HTK.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MelFilterbank {
+  // The first bin and the weights of every filter
+  filters: Vec<(usize, Vec<f32>)>,
+  center_frequencies: Vec<f32>,
+  bin_count: usize,
+}
+
+impl MelFilterbank {
+  /// Create `band_count` filters between `min_frequency` and `max_frequency` Hz for the spectra of
+  /// frames of `fft_length` samples at `sample_rate` Hz.
+  ///
+  /// An error is returned if the sample rate or the band count is 0, if the FFT length is less than or
+  /// equal to 1, or if the frequencies are not increasing from 0 to the Nyquist frequency.
+  pub fn new(
+    sample_rate: u32,
+    fft_length: usize,
+    band_count: usize,
+    min_frequency: f32,
+    max_frequency: f32
+  ) -> Result<Self, HannWindowError> {
+    if sample_rate == 0 {
+      return Err(HannWindowError::InvalidSampleRate);
+    }
+    if fft_length <= 1 {
+      return Err(HannWindowError::WindowLengthTooSmall);
+    }
+    if band_count == 0 {
+      return Err(HannWindowError::InvalidBandCount);
+    }
+    let nyquist = (sample_rate as f32) / 2.0;
+    if !(0.0 <= min_frequency && min_frequency < max_frequency && max_frequency <= nyquist) {
+      return Err(HannWindowError::InvalidCutoff);
+    }
+
+    // The band edges, evenly spaced on the mel scale
+    let min_mel = hz_to_mel(min_frequency);
+    let mel_step = (hz_to_mel(max_frequency) - min_mel) / ((band_count + 1) as f32);
+    let edges: Vec<f32> = (0..(band_count + 2)).map(|i| mel_to_hz(min_mel + (i as f32) * mel_step)).collect();
+
+    // Sample the triangles at the bin frequencies, keeping only their nonzero span
+    let bin_count = fft_length / 2 + 1;
+    let bin_width = (sample_rate as f32) / (fft_length as f32);
+    let filters = edges
+      .windows(3)
+      .map(|edges| {
+        let (lower, center, upper) = (edges[0], edges[1], edges[2]);
+        let weights: Vec<(usize, f32)> = (0..bin_count)
+          .map(|k| {
+            let frequency = (k as f32) * bin_width;
+            let rising = (frequency - lower) / (center - lower);
+            let falling = (upper - frequency) / (upper - center);
+            (k, rising.min(falling).max(0.0))
+          })
+          .filter(|&(_, weight)| weight > 0.0)
+          .collect();
+        let start = weights.first().map_or(0, |&(k, _)| k);
+        (start, weights.into_iter().map(|(_, weight)| weight).collect())
+      })
+      .collect();
+
+    Ok(MelFilterbank { filters, center_frequencies: edges[1..=band_count].to_vec(), bin_count })
+  }
+
+  /// The number of mel bands.
+  pub fn band_count(&self) -> usize {
+    self.filters.len()
+  }
+
+  /// The number of bins of the spectra the filters apply to, `fft_length / 2 + 1`.
+  pub fn bin_count(&self) -> usize {
+    self.bin_count
+  }
+
+  /// The center frequency of every band, in Hz.
+  pub fn center_frequencies(&self) -> &[f32] {
+    &self.center_frequencies
+  }
+
+  /// Reduce a power spectrum to the power of every mel band.
+  ///
+  /// An error is returned if the spectrum does not have `bin_count` bins.
+  pub fn apply(&self, power: &[f32]) -> Result<Vec<f32>, HannWindowError> {
+    // The spectrum must match the filters bin for bin
+    if power.len() != self.bin_count {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    let bands = self
+      .filters
+      .iter()
+      .map(|(start, weights)| weights.iter().zip(&power[*start..]).map(|(weight, power)| weight * power).sum())
+      .collect();
+
+    Ok(bands)
+  }
+}
+
+#[cfg(test)]
+mod test_mel {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_mel_scale() {
+    assert_abs_diff_eq!(hz_to_mel(1000.0), 1000.0, epsilon = 0.1);
+    assert_abs_diff_eq!(mel_to_hz(hz_to_mel(4321.0)), 4321.0, epsilon = 0.01);
+  }
+
+  #[test]
+  fn test_mel_filterbank_triangles() {
+    let filterbank = MelFilterbank::new(16000, 512, 20, 0.0, 8000.0).unwrap();
+
+    assert_eq!(filterbank.band_count(), 20);
+    assert_eq!(filterbank.bin_count(), 257);
+    // The centers rise, and a flat spectrum excites every band
+    assert!(filterbank.center_frequencies().windows(2).all(|pair| pair[0] < pair[1]));
+    let bands = filterbank.apply(&[1.0; 257]).unwrap();
+    assert!(bands.iter().all(|&band| band > 0.0));
+  }
+
+  #[test]
+  fn test_mel_filterbank_tone() {
+    let filterbank = MelFilterbank::new(16000, 512, 20, 0.0, 8000.0).unwrap();
+    // A single bin at 1 kHz
+    let mut power = vec![0.0; 257];
+    power[32] = 1.0;
+
+    let bands = filterbank.apply(&power).unwrap();
+
+    // The tone falls into the two bands around it, whose weights sum to 1
+    let peak = bands.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    let nearest = filterbank
+      .center_frequencies()
+      .iter()
+      .enumerate()
+      .min_by(|a, b| (a.1 - 1000.0).abs().total_cmp(&(b.1 - 1000.0).abs()))
+      .unwrap()
+      .0;
+    assert_eq!(peak, nearest);
+    assert_abs_diff_eq!(bands.iter().sum::<f32>(), 1.0, epsilon = 1e-5);
+  }
+
+  #[test]
+  fn test_mel_filterbank_invalid_parameters() {
+    assert_eq!(MelFilterbank::new(0, 512, 20, 0.0, 8000.0).unwrap_err(), HannWindowError::InvalidSampleRate);
+    assert_eq!(MelFilterbank::new(16000, 512, 0, 0.0, 8000.0).unwrap_err(), HannWindowError::InvalidBandCount);
+    assert_eq!(MelFilterbank::new(16000, 512, 20, 0.0, 9000.0).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(MelFilterbank::new(16000, 512, 20, 0.0, 8000.0).unwrap().apply(&[0.0; 256]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
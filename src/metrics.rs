@@ -0,0 +1,204 @@
+//! The measurement of windows and spectra.
+//!
+//! This module computes the figures of merit of windows with [`window_metrics`], and gathers the
+//! comparisons of windows and spectrograms within tolerances, the frequency weightings of power
+//! spectra and, with the `prometheus` feature, the export of the streaming metrics.
+
+use std::f64::consts::PI;
+
+use crate::hann_window::HannWindowError;
+
+pub use crate::{
+  compare::{ spectrograms_approx_eq, windows_approx_eq, Mismatch, Tolerance },
+  weighting::{ BandResolution, FrequencyWeighting, OctaveBands, WeightingCurve },
+};
+#[cfg(feature = "prometheus")]
+pub use crate::prometheus::{ LookupTable, StreamingMetrics, StreamingProcessor };
+
+// The smallest number of frequencies sampled per bin when searching the mainlobe and the sidelobes
+const RESPONSE_OVERSAMPLING: usize = 16;
+// The largest number of overlapping frames considered for the recommended overlap
+const MAX_OVERLAPPING_FRAMES: usize = 16;
+// The smallest ratio of the minimum to the maximum of the overlap-added windows considered flat
+const OVERLAP_FLATNESS: f64 = 0.98;
+
+/// The figures of merit of a window, as tabulated by Harris and by Heinzel et al.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowMetrics {
+  /// The mean of the coefficients, the gain of a tone on a bin relative to the rectangular window.
+  pub coherent_gain: f32,
+  /// The equivalent noise bandwidth, in bins: the width of the rectangular filter passing the same
+  /// white noise power, `N * sum(w^2) / sum(w)^2`.
+  pub enbw: f32,
+  /// The loss of a tone halfway between two bins relative to a tone on a bin, in decibels.
+  pub scalloping_loss: f32,
+  /// The level of the highest sidelobe relative to the mainlobe peak, in decibels.
+  pub sidelobe_level: f32,
+  /// The smallest overlap of consecutive frames, a fraction of the frame length, at which the
+  /// overlap-added windows are flat to within 2%, from the overlaps `1 - 1 / k` for `k` up to 16.
+  pub recommended_overlap: f32,
+}
+
+/// Compute the figures of merit of a window.
+///
+/// This function takes the `window` and returns its [`WindowMetrics`]. The response is computed in
+/// `f64` by an FFT of the window padded with zeros to at least 16 times its length, in `O(N log N)`,
+/// so that the sidelobes of windows as low as -150 dB are resolved. The sidelobe level is minus
+/// infinity for a window without sidelobes. An error is returned if the window length is less than or
+/// equal to 1, or too large.
+pub fn window_metrics(window: &[f32]) -> Result<WindowMetrics, HannWindowError> {
+  if window.len() <= 1 {
+    return Err(HannWindowError::WindowLengthTooSmall);
+  }
+  let fft_length = window
+    .len()
+    .checked_mul(RESPONSE_OVERSAMPLING)
+    .and_then(usize::checked_next_power_of_two)
+    .ok_or(HannWindowError::WindowLengthTooLarge)?;
+  let length = window.len() as f64;
+  let sum: f64 = window.iter().map(|&value| f64::from(value)).sum();
+  let sum_squares: f64 = window.iter().map(|&value| f64::from(value) * f64::from(value)).sum();
+
+  // The mainlobe ends at the first minimum of the response, and the sidelobes follow it
+  let response = magnitudes(window, fft_length);
+  let mainlobe_end = response.windows(2).position(|pair| pair[1] > pair[0]);
+  let sidelobe = mainlobe_end.map_or(0.0, |end| response[end..].iter().copied().fold(0.0, f64::max));
+
+  Ok(WindowMetrics {
+    coherent_gain: (sum / length) as f32,
+    enbw: (length * sum_squares / (sum * sum)) as f32,
+    scalloping_loss: (-20.0 * (magnitude(window, 0.5 / length) / sum.abs()).log10()) as f32,
+    sidelobe_level: (20.0 * (sidelobe / response[0]).log10()) as f32,
+    recommended_overlap: recommended_overlap(window),
+  })
+}
+
+// Compute the magnitude of the transform of the window at the frequencies `k / fft_length` cycles
+// per sample up to the Nyquist frequency, by a radix-2 FFT of the window padded with zeros to
+// `fft_length`, a power of two
+fn magnitudes(window: &[f32], fft_length: usize) -> Vec<f64> {
+  // Place the window in the bit-reversed order of the butterflies
+  let bits = fft_length.trailing_zeros();
+  let mut real = vec![0.0f64; fft_length];
+  let mut imaginary = vec![0.0f64; fft_length];
+  for (n, &value) in window.iter().enumerate() {
+    real[n.reverse_bits() >> (usize::BITS - bits)] = f64::from(value);
+  }
+
+  // The twiddle factors of the longest stage, computed directly rather than by recurrence so that
+  // the rounding errors do not accumulate, of which every shorter stage takes a stride
+  let twiddles: Vec<(f64, f64)> = (0..(fft_length / 2))
+    .map(|k| {
+      let phase = -2.0 * PI * (k as f64) / (fft_length as f64);
+      (phase.cos(), phase.sin())
+    })
+    .collect();
+  let mut size = 2;
+  while size <= fft_length {
+    let (half, stride) = (size / 2, fft_length / size);
+    for start in (0..fft_length).step_by(size) {
+      for k in 0..half {
+        let (a, b) = (start + k, start + k + half);
+        let (cos, sin) = twiddles[k * stride];
+        let (re, im) = (real[b] * cos - imaginary[b] * sin, real[b] * sin + imaginary[b] * cos);
+        real[b] = real[a] - re;
+        imaginary[b] = imaginary[a] - im;
+        real[a] += re;
+        imaginary[a] += im;
+      }
+    }
+    size *= 2;
+  }
+
+  real.iter().zip(&imaginary).take(fft_length / 2 + 1).map(|(re, im)| re.hypot(*im)).collect()
+}
+
+// Compute the magnitude of the transform of the window at `frequency` cycles per sample
+fn magnitude(window: &[f32], frequency: f64) -> f64 {
+  let (real, imaginary) = window.iter().enumerate().fold((0.0, 0.0), |(real, imaginary), (n, &value)| {
+    let phase = 2.0 * PI * frequency * (n as f64);
+    (real + f64::from(value) * phase.cos(), imaginary - f64::from(value) * phase.sin())
+  });
+  real.hypot(imaginary)
+}
+
+// Find the smallest overlap at which the overlap-added windows are flat
+fn recommended_overlap(window: &[f32]) -> f32 {
+  let length = window.len();
+  for frame_count in 1..=MAX_OVERLAPPING_FRAMES {
+    let hop_size = ((length as f64) / (frame_count as f64)).round().max(1.0) as usize;
+
+    // The overlap-added windows repeat every hop, so fold the window onto one hop
+    let mut sums = vec![0.0f64; hop_size];
+    for (n, &value) in window.iter().enumerate() {
+      sums[n % hop_size] += f64::from(value);
+    }
+    let min = sums.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = sums.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max > 0.0 && min >= OVERLAP_FLATNESS * max {
+      return 1.0 - (hop_size as f32) / (length as f32);
+    }
+  }
+
+  1.0 - 1.0 / (MAX_OVERLAPPING_FRAMES as f32)
+}
+
+#[cfg(test)]
+mod test_metrics {
+  use approx::assert_abs_diff_eq;
+
+  use crate::{ hann_window::get_hann_window, window_spec::WindowSpec };
+
+  use super::*;
+
+  #[test]
+  fn test_window_metrics_rectangular() {
+    let metrics = window_metrics(&[1.0; 1024]).unwrap();
+
+    assert_abs_diff_eq!(metrics.coherent_gain, 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(metrics.enbw, 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(metrics.scalloping_loss, 3.92, epsilon = 0.01);
+    assert_abs_diff_eq!(metrics.sidelobe_level, -13.26, epsilon = 0.05);
+    assert_eq!(metrics.recommended_overlap, 0.0);
+  }
+
+  #[test]
+  fn test_window_metrics_hann() {
+    let metrics = window_metrics(&get_hann_window(1024).unwrap()).unwrap();
+
+    // The figures of Harris, "On the use of windows for harmonic analysis with the DFT"
+    assert_abs_diff_eq!(metrics.coherent_gain, 0.5, epsilon = 1e-3);
+    assert_abs_diff_eq!(metrics.enbw, 1.5, epsilon = 2e-3);
+    assert_abs_diff_eq!(metrics.scalloping_loss, 1.42, epsilon = 0.01);
+    assert_abs_diff_eq!(metrics.sidelobe_level, -31.47, epsilon = 0.05);
+    assert_eq!(metrics.recommended_overlap, 0.5);
+  }
+
+  #[test]
+  fn test_window_metrics_blackman() {
+    let metrics = window_metrics(&WindowSpec::Blackman.generate(1024).unwrap()).unwrap();
+
+    assert_abs_diff_eq!(metrics.coherent_gain, 0.42, epsilon = 1e-3);
+    assert_abs_diff_eq!(metrics.enbw, 1.73, epsilon = 0.01);
+    assert_abs_diff_eq!(metrics.sidelobe_level, -58.1, epsilon = 0.2);
+    assert_abs_diff_eq!(metrics.recommended_overlap, 2.0 / 3.0, epsilon = 1e-3);
+  }
+
+  #[test]
+  fn test_window_metrics_fft_matches_direct_transform() {
+    let window = WindowSpec::Blackman.generate(100).unwrap();
+
+    // The padded FFT samples the response at the same frequencies as the direct transform
+    let response = magnitudes(&window, 2048);
+    for (k, value) in response.iter().enumerate().step_by(7) {
+      assert_abs_diff_eq!(*value, magnitude(&window, (k as f64) / 2048.0), epsilon = 1e-12);
+    }
+    assert_eq!(response.len(), 1025);
+  }
+
+  #[test]
+  fn test_window_metrics_too_small() {
+    assert_eq!(window_metrics(&[1.0]).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
@@ -0,0 +1,149 @@
+use core::f32::consts::PI;
+
+use microfft::{ real, Complex32 };
+
+mod sealed {
+  pub trait Sealed {}
+}
+
+/// A frame length supported by the real FFTs of `microfft`, from 4 to 4096 samples.
+///
+/// This trait is implemented for the arrays `[f32; N]` where `N` is a power of two in that range, and
+/// cannot be implemented outside of this crate.
+pub trait MicroFftFrame: sealed::Sealed {
+  // Transform the frame in place
+  #[doc(hidden)]
+  fn rfft(&mut self) -> &mut [Complex32];
+}
+
+// Implement the MicroFftFrame trait for every frame length of the default features of microfft
+macro_rules! micro_fft_frames {
+  ( $( $length:expr => $rfft:ident, )* ) => {
+    $(
+      impl sealed::Sealed for [f32; $length] {}
+
+      impl MicroFftFrame for [f32; $length] {
+        fn rfft(&mut self) -> &mut [Complex32] {
+          real::$rfft(self)
+        }
+      }
+    )*
+  };
+}
+
+micro_fft_frames! {
+  4 => rfft_4,
+  8 => rfft_8,
+  16 => rfft_16,
+  32 => rfft_32,
+  64 => rfft_64,
+  128 => rfft_128,
+  256 => rfft_256,
+  512 => rfft_512,
+  1024 => rfft_1024,
+  2048 => rfft_2048,
+  4096 => rfft_4096,
+}
+
+/// A fixed-size windowed real FFT for microcontrollers, built on `microfft`.
+///
+/// The frame length `N` is a const generic, the window is stored inline and frames are windowed and
+/// transformed in place, so that analysis never allocates. The window can be computed by
+/// [`MicroWindowedFft::new`] or supplied precomputed to the `const` constructor
+/// [`MicroWindowedFft::from_window`]. The transform only relies on `core`, `microfft` and `libm`, so it
+/// is available in the `no_std` build of the crate, without the `std` feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicroWindowedFft<const N: usize> {
+  window: [f32; N],
+}
+
+impl<const N: usize> MicroWindowedFft<N>
+where
+  [f32; N]: MicroFftFrame,
+{
+  /// Create a transform windowing every frame by the symmetric Hann window of `N` values.
+  pub fn new() -> Self {
+    let mut window = [0.0; N];
+    for (n, value) in window.iter_mut().enumerate() {
+      *value = 0.5 - 0.5 * libm::cosf((2.0 * PI * (n as f32)) / ((N - 1) as f32));
+    }
+    MicroWindowedFft { window }
+  }
+
+  /// Create a transform windowing every frame by a precomputed `window`.
+  pub const fn from_window(window: [f32; N]) -> Self {
+    MicroWindowedFft { window }
+  }
+
+  /// The window applied to every frame.
+  pub fn window(&self) -> &[f32; N] {
+    &self.window
+  }
+
+  /// Window a frame in place and transform it, returning its spectrum in the frame buffer.
+  ///
+  /// The spectrum has the `N / 2` bins from 0 Hz to just below the Nyquist frequency, bin `k` being the
+  /// frequency `k / N` cycles per sample. As in `microfft`, the real Nyquist bin is packed into the
+  /// imaginary part of the real 0 Hz bin.
+  pub fn process<'a>(&self, frame: &'a mut [f32; N]) -> &'a mut [Complex32] {
+    // Window the frame in place, then transform it in place
+    for (sample, &value) in frame.iter_mut().zip(self.window.iter()) {
+      *sample *= value;
+    }
+    frame.rfft()
+  }
+}
+
+impl<const N: usize> Default for MicroWindowedFft<N>
+where
+  [f32; N]: MicroFftFrame,
+{
+  fn default() -> Self {
+    MicroWindowedFft::new()
+  }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_micro_fft {
+  use approx::assert_abs_diff_eq;
+
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_micro_windowed_fft_window() {
+    let fft = MicroWindowedFft::<64>::new();
+
+    for (value, expected) in fft.window().iter().zip(get_hann_window(64).unwrap().iter()) {
+      assert_abs_diff_eq!(value, expected, epsilon = 1e-6);
+    }
+  }
+
+  #[test]
+  fn test_micro_windowed_fft_matches_direct_transform() {
+    const WINDOW: [f32; 8] = [1.0, 0.5, 0.25, 0.5, 1.0, 0.75, 0.5, 0.25];
+    let fft = MicroWindowedFft::from_window(WINDOW);
+    let input = [1.0, 2.0, -1.0, 0.5, 3.0, -2.0, 0.0, 1.5];
+    let mut frame = input;
+
+    let spectrum = fft.process(&mut frame).to_vec();
+
+    // The direct transform of the windowed frame
+    let dft = |k: usize| -> Complex32 {
+      (0..8)
+        .map(|n| {
+          let phase = (-2.0 * PI * ((k * n) as f32)) / 8.0;
+          Complex32::new(input[n] * WINDOW[n] * phase.cos(), input[n] * WINDOW[n] * phase.sin())
+        })
+        .sum()
+    };
+    assert_eq!(spectrum.len(), 4);
+    assert_abs_diff_eq!(spectrum[0].re, dft(0).re, epsilon = 1e-5);
+    assert_abs_diff_eq!(spectrum[0].im, dft(4).re, epsilon = 1e-5);
+    for (k, bin) in spectrum.iter().enumerate().skip(1) {
+      assert_abs_diff_eq!(bin.re, dft(k).re, epsilon = 1e-5);
+      assert_abs_diff_eq!(bin.im, dft(k).im, epsilon = 1e-5);
+    }
+  }
+}
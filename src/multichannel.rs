@@ -0,0 +1,209 @@
+use crate::{
+  hann_window::HannWindowError,
+  latency::Latency,
+  streaming_windower::StreamingWindower,
+};
+
+/// A frame emitted by a [`MultichannelWindower`], tagged with its channel and its position in the stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultichannelFrame<'a> {
+  /// The index of the channel the frame was taken from.
+  pub channel: usize,
+  /// The index of the first sample of the frame, counted from the start of the stream.
+  pub start: u64,
+  /// The (windowed) frame samples.
+  pub frame: &'a [f32],
+}
+
+impl MultichannelFrame<'_> {
+  /// The position of the center of the frame in samples, counted from the start of the stream.
+  ///
+  /// Frames of different lengths taken from different channels describe the same instant when their
+  /// centers are equal.
+  pub fn center(&self) -> f64 {
+    (self.start as f64) + ((self.frame.len() as f64) - 1.0) / 2.0
+  }
+}
+
+/// A windower for multichannel streams where every channel has its own window, frame length and hop size.
+///
+/// Every channel is framed by its own [`StreamingWindower`], while the windower keeps a single stream
+/// position for all of them, so that the start of every emitted frame is expressed on the same timeline.
+/// Frames are emitted in the order in which they complete: frames completed by the same sample are
+/// emitted in channel order. Processing samples does not allocate.
+#[derive(Debug, Clone)]
+pub struct MultichannelWindower {
+  channels: Vec<StreamingWindower>,
+  position: u64,
+}
+
+impl MultichannelWindower {
+  /// Create a multichannel windower from one configured streaming windower per channel.
+  ///
+  /// The windowers are reset, so that all channels start at the beginning of the stream. An error is
+  /// returned if no channel is given.
+  pub fn new(mut channels: Vec<StreamingWindower>) -> Result<Self, HannWindowError> {
+    // A stream must have at least one channel
+    if channels.is_empty() {
+      return Err(HannWindowError::InvalidChannelCount);
+    }
+    // Start every channel at the same point of the stream
+    channels.iter_mut().for_each(StreamingWindower::reset);
+
+    Ok(MultichannelWindower { channels, position: 0 })
+  }
+
+  /// The number of channels.
+  pub fn channel_count(&self) -> usize {
+    self.channels.len()
+  }
+
+  /// The streaming windower of the given channel.
+  pub fn channel(&self, channel: usize) -> Option<&StreamingWindower> {
+    self.channels.get(channel)
+  }
+
+  /// The streaming windower of the given channel, for example to reconfigure it while streaming.
+  pub fn channel_mut(&mut self, channel: usize) -> Option<&mut StreamingWindower> {
+    self.channels.get_mut(channel)
+  }
+
+  /// The number of samples received per channel since the start of the stream.
+  pub fn position(&self) -> u64 {
+    self.position
+  }
+
+  /// Feed one block of samples per channel and call `on_frame` with every frame completed by them.
+  ///
+  /// The blocks are given in channel order and must all have the same length. An error is returned if
+  /// the number of blocks is different from the number of channels or if their lengths differ. Returns
+  /// the number of emitted frames.
+  pub fn process<F: FnMut(MultichannelFrame<'_>)>(
+    &mut self,
+    blocks: &[&[f32]],
+    mut on_frame: F
+  ) -> Result<usize, HannWindowError> {
+    // Every channel must receive a block
+    if blocks.len() != self.channels.len() {
+      return Err(HannWindowError::InvalidChannelCount);
+    }
+    // Every channel must receive the same number of samples
+    let block_length = blocks[0].len();
+    if blocks.iter().any(|block| block.len() != block_length) {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    let mut offset = 0;
+    let mut frame_count = 0;
+    while offset < block_length {
+      // Advance all channels up to the closest frame boundary of any channel
+      let step = self.channels
+        .iter()
+        .map(StreamingWindower::samples_until_next_frame)
+        .min()
+        .unwrap_or(block_length)
+        .min(block_length - offset);
+
+      for (channel, (windower, block)) in self.channels.iter_mut().zip(blocks.iter()).enumerate() {
+        windower.process_frames(&block[offset..offset + step], |frame| {
+          // Frames of an outgoing configuration are not part of the channel output
+          if !frame.outgoing {
+            on_frame(MultichannelFrame { channel, start: frame.start, frame: frame.samples });
+            frame_count += 1;
+          }
+        });
+      }
+
+      offset += step;
+      self.position += step as u64;
+    }
+
+    Ok(frame_count)
+  }
+
+  /// Discard every buffered sample and restart the stream position at 0.
+  pub fn reset(&mut self) {
+    self.channels.iter_mut().for_each(StreamingWindower::reset);
+    self.position = 0;
+  }
+}
+
+/// The latency and group delay of the slowest channel.
+impl Latency for MultichannelWindower {
+  fn latency(&self) -> usize {
+    self.channels.iter().map(Latency::latency).max().unwrap_or(0)
+  }
+
+  fn group_delay(&self) -> f64 {
+    self.channels.iter().map(Latency::group_delay).fold(0.0, f64::max)
+  }
+}
+
+#[cfg(test)]
+mod test_multichannel {
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  #[test]
+  fn test_multichannel_windower_timestamps() {
+    let mid: Vec<f32> = (0..64).map(|i| i as f32).collect();
+    let side: Vec<f32> = (0..64).map(|i| -(i as f32)).collect();
+    let window = get_hann_window(16).unwrap();
+    let mut windower = MultichannelWindower::new(
+      vec![StreamingWindower::new(16, 8).unwrap().with_window(&window).unwrap(), StreamingWindower::new(4, 4).unwrap()]
+    ).unwrap();
+
+    // Feed both channels in uneven blocks and collect the emitted frames
+    let mut emitted = Vec::new();
+    for (start, end) in [(0, 5), (5, 30), (30, 64)] {
+      windower
+        .process(&[&mid[start..end], &side[start..end]], |frame| {
+          emitted.push((frame.channel, frame.start, frame.frame.to_vec()));
+        })
+        .unwrap();
+    }
+
+    // Every channel yields the same frames as framing it on its own
+    let expected_mid: Vec<_> = frames(&mid, 16, 8, PaddingMode::None).unwrap().with_window(&window).unwrap().collect();
+    let expected_side: Vec<_> = frames(&side, 4, 4, PaddingMode::None).unwrap().collect();
+    let emitted_mid: Vec<_> = emitted.iter().filter(|(channel, _, _)| *channel == 0).collect();
+    let emitted_side: Vec<_> = emitted.iter().filter(|(channel, _, _)| *channel == 1).collect();
+    assert_eq!(emitted_mid.len(), expected_mid.len());
+    assert_eq!(emitted_side.len(), expected_side.len());
+    for (i, (_, start, frame)) in emitted_mid.iter().enumerate() {
+      assert_eq!(*start, (i as u64) * 8);
+      assert_eq!(frame.as_slice(), &*expected_mid[i]);
+    }
+    for (i, (_, start, frame)) in emitted_side.iter().enumerate() {
+      assert_eq!(*start, (i as u64) * 4);
+      assert_eq!(frame.as_slice(), &*expected_side[i]);
+    }
+
+    // Frames are emitted in the order in which they complete
+    let ends: Vec<u64> = emitted
+      .iter()
+      .map(|(channel, start, _)| start + (if *channel == 0 { 16 } else { 4 }))
+      .collect();
+    assert!(ends.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(windower.position(), 64);
+  }
+
+  #[test]
+  fn test_multichannel_frame_center() {
+    let frame = MultichannelFrame { channel: 0, start: 8, frame: &[0.0; 5] };
+
+    assert_eq!(frame.center(), 10.0);
+  }
+
+  #[test]
+  fn test_multichannel_windower_invalid_blocks() {
+    let mut windower = MultichannelWindower::new(
+      vec![StreamingWindower::new(4, 2).unwrap(), StreamingWindower::new(8, 4).unwrap()]
+    ).unwrap();
+
+    assert_eq!(MultichannelWindower::new(Vec::new()).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(windower.process(&[&[0.0; 4]], |_| {}).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(windower.process(&[&[0.0; 4], &[0.0; 3]], |_| {}).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
@@ -0,0 +1,99 @@
+use nalgebra::{ DMatrix, DVector };
+
+use crate::{ frames::{ frames, PaddingMode }, hann_window::{ get_hann_window, HannWindowError } };
+
+/// Get the Hann window of `window_length` values as a `DVector`.
+///
+/// The window is moved into the vector without copying, see [`get_hann_window`] for the errors.
+pub fn get_hann_window_dvector(window_length: usize) -> Result<DVector<f32>, HannWindowError> {
+  Ok(DVector::from_vec(get_hann_window(window_length)?))
+}
+
+/// Multiply every column of a batch of frames by a window in place.
+///
+/// The `frames` have one frame per column, which is contiguous in the column-major storage of
+/// `nalgebra`. If the window length differs from the number of rows, an error is returned and the
+/// frames are left untouched.
+pub fn apply_window_columns(frames: &mut DMatrix<f32>, window: &DVector<f32>) -> Result<(), HannWindowError> {
+  // The window must cover every frame sample for sample
+  if frames.nrows() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Multiply every frame by the window
+  for mut frame in frames.column_iter_mut() {
+    frame.component_mul_assign(window);
+  }
+
+  Ok(())
+}
+
+/// Split a signal into frames, returned as the columns of a `DMatrix`.
+///
+/// This is the [`frames`] function collected into a matrix of `frame_length` rows and one column per
+/// frame, with the frames optionally multiplied by a `window` of `frame_length` values. The frames are
+/// written straight into the matrix. An error is returned if the frame length or the hop size is 0, or
+/// if the window length differs from the frame length.
+pub fn frames_dmatrix(
+  signal: &[f32],
+  frame_length: usize,
+  hop_size: usize,
+  padding_mode: PaddingMode,
+  window: Option<&DVector<f32>>
+) -> Result<DMatrix<f32>, HannWindowError> {
+  let mut iterator = frames(signal, frame_length, hop_size, padding_mode)?;
+  if let Some(window) = window {
+    iterator = iterator.with_window(window.as_slice())?;
+  }
+
+  let mut matrix = DMatrix::zeros(frame_length, iterator.len());
+  // The columns of the matrix are contiguous, one frame after the other
+  for column in matrix.as_mut_slice().chunks_exact_mut(frame_length) {
+    iterator.next_into(column)?;
+  }
+
+  Ok(matrix)
+}
+
+#[cfg(test)]
+mod test_nalgebra_interop {
+  use super::*;
+
+  #[test]
+  fn test_get_hann_window_dvector() {
+    let window = get_hann_window_dvector(5).unwrap();
+
+    assert_eq!(window, DVector::from_vec(vec![0.0, 0.5, 1.0, 0.5, 0.0]));
+    assert_eq!(get_hann_window_dvector(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+
+  #[test]
+  fn test_apply_window_columns() {
+    let window = get_hann_window_dvector(3).unwrap();
+    let mut batch = DMatrix::from_element(3, 2, 4.0);
+
+    apply_window_columns(&mut batch, &window).unwrap();
+
+    assert_eq!(batch, DMatrix::from_column_slice(3, 2, &[0.0, 4.0, 0.0, 0.0, 4.0, 0.0]));
+    assert_eq!(apply_window_columns(&mut batch.transpose(), &window).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_frames_dmatrix_matches_frames() {
+    let signal: Vec<f32> = (0..20).map(|i| i as f32).collect();
+    let window = get_hann_window_dvector(8).unwrap();
+
+    let matrix = frames_dmatrix(&signal, 8, 4, PaddingMode::CenterZero, Some(&window)).unwrap();
+    let expected: Vec<Vec<f32>> = frames(&signal, 8, 4, PaddingMode::CenterZero)
+      .unwrap()
+      .with_window(window.as_slice())
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(matrix.ncols(), expected.len());
+    for (column, frame) in matrix.column_iter().zip(expected.iter()) {
+      assert_eq!(column.as_slice(), frame.as_slice());
+    }
+  }
+}
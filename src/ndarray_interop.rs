@@ -0,0 +1,191 @@
+use std::borrow::Cow;
+
+use ndarray::{ Array1, Array2, ArrayView1, ArrayViewMut1, ArrayViewMut2, Axis, Zip };
+
+#[cfg(feature = "realfft")]
+use crate::real_fft::WindowedRealFft;
+use crate::{ frames::{ frames, PaddingMode }, hann_window::{ get_hann_window, HannWindowError } };
+
+/// Get the Hann window of `window_length` values as an `Array1`.
+///
+/// The window is moved into the array without copying, see [`get_hann_window`] for the errors.
+pub fn get_hann_window_array(window_length: usize) -> Result<Array1<f32>, HannWindowError> {
+  Ok(Array1::from(get_hann_window(window_length)?))
+}
+
+/// Multiply a signal view by a window view in place.
+///
+/// The views may have any stride. If the lengths of the two views differ, an error is returned and the
+/// signal is left untouched.
+pub fn apply_window_array(mut signal: ArrayViewMut1<f32>, window: ArrayView1<f32>) -> Result<(), HannWindowError> {
+  // The window must cover the signal sample for sample
+  if signal.len() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Multiply every sample by its window value, whatever the strides
+  Zip::from(&mut signal).and(&window).for_each(|sample, &value| {
+    *sample *= value;
+  });
+
+  Ok(())
+}
+
+/// Multiply every row of a batch of frames by a window in place.
+///
+/// The `frames` have one frame per row. If the window length differs from the number of columns, an
+/// error is returned and the frames are left untouched.
+pub fn apply_window_rows(mut frames: ArrayViewMut2<f32>, window: ArrayView1<f32>) -> Result<(), HannWindowError> {
+  // The window must cover every frame sample for sample
+  if frames.ncols() != window.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Multiply every frame by the window
+  for mut frame in frames.axis_iter_mut(Axis(0)) {
+    Zip::from(&mut frame).and(&window).for_each(|sample, &value| {
+      *sample *= value;
+    });
+  }
+
+  Ok(())
+}
+
+/// Split a signal view into frames, returned as the rows of an `Array2`.
+///
+/// This is the [`frames`] function collected into an array of shape `(frame_count, frame_length)`,
+/// with the frames optionally multiplied by a `window` of `frame_length` values. The frames are written
+/// straight into the array, and a signal view with a stride other than 1 is copied first. An error is
+/// returned if the frame length or the hop size is 0, or if the window length differs from the frame
+/// length.
+pub fn frames_array(
+  signal: ArrayView1<f32>,
+  frame_length: usize,
+  hop_size: usize,
+  padding_mode: PaddingMode,
+  window: Option<ArrayView1<f32>>
+) -> Result<Array2<f32>, HannWindowError> {
+  // The frames are cut from contiguous samples
+  let signal = signal.as_slice().map_or_else(|| Cow::Owned(signal.to_vec()), Cow::Borrowed);
+  let window = window.map(|window| window.to_slice().map_or_else(|| Cow::Owned(window.to_vec()), Cow::Borrowed));
+
+  let mut iterator = frames(&signal, frame_length, hop_size, padding_mode)?;
+  if let Some(window) = &window {
+    iterator = iterator.with_window(window)?;
+  }
+
+  let mut array = Array2::zeros((iterator.len(), frame_length));
+  for mut row in array.axis_iter_mut(Axis(0)) {
+    // The rows of a new array are contiguous
+    let row = row.as_slice_mut().expect("Array rows are contiguous");
+    iterator.next_into(row)?;
+  }
+
+  Ok(array)
+}
+
+/// Compute the power spectrogram of a signal view, returned as an `Array2`.
+///
+/// This is the [`spectrogram`](crate::stft::spectrogram) function, available with the `realfft` feature,
+/// returning an array of shape `(frame_count, window.len() / 2 + 1)` whose rows are the power spectra
+/// of the frames. The spectra are written straight into the array. An error is returned if the window
+/// length is less than or equal to 1 or if the hop size is 0.
+#[cfg(feature = "realfft")]
+pub fn spectrogram_array(
+  signal: ArrayView1<f32>,
+  window: ArrayView1<f32>,
+  hop_size: usize,
+  padding_mode: PaddingMode
+) -> Result<Array2<f32>, HannWindowError> {
+  let window = window.to_vec();
+  let mut fft = WindowedRealFft::new(window.len())?.with_window(&window)?;
+  let signal = signal.as_slice().map_or_else(|| Cow::Owned(signal.to_vec()), Cow::Borrowed);
+  let mut iterator = frames(&signal, window.len(), hop_size, padding_mode)?;
+
+  let mut array = Array2::zeros((iterator.len(), fft.bin_count()));
+  let mut frame = vec![0.0; window.len()];
+  for mut row in array.axis_iter_mut(Axis(0)) {
+    iterator.next_into(&mut frame)?;
+    Zip::from(&mut row).and(fft.process(&frame)?).for_each(|power, bin| {
+      *power = bin.norm_sqr();
+    });
+  }
+
+  Ok(array)
+}
+
+#[cfg(test)]
+mod test_ndarray_interop {
+  use ndarray::{ array, s };
+
+  use super::*;
+
+  #[test]
+  fn test_get_hann_window_array() {
+    let window = get_hann_window_array(5).unwrap();
+
+    assert_eq!(window, array![0.0, 0.5, 1.0, 0.5, 0.0]);
+    assert_eq!(get_hann_window_array(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+
+  #[test]
+  fn test_apply_window_array_strided() {
+    let window = get_hann_window_array(5).unwrap();
+    let mut signal = Array1::from_elem(10, 2.0);
+
+    // Every other sample is windowed
+    apply_window_array(signal.slice_mut(s![..;2]), window.view()).unwrap();
+
+    assert_eq!(signal, array![0.0, 2.0, 1.0, 2.0, 2.0, 2.0, 1.0, 2.0, 0.0, 2.0]);
+    assert_eq!(apply_window_array(signal.view_mut(), window.view()).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_apply_window_rows() {
+    let window = get_hann_window_array(3).unwrap();
+    let mut batch = Array2::from_elem((2, 3), 4.0);
+
+    apply_window_rows(batch.view_mut(), window.view()).unwrap();
+
+    assert_eq!(batch, array![[0.0, 4.0, 0.0], [0.0, 4.0, 0.0]]);
+    assert_eq!(apply_window_rows(batch.t().to_owned().view_mut(), window.view()).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_frames_array_matches_frames() {
+    let signal = Array1::from_iter((0..20).map(|i| i as f32));
+    let window = get_hann_window_array(8).unwrap();
+
+    let array = frames_array(signal.view(), 8, 4, PaddingMode::CenterReflect, Some(window.view())).unwrap();
+    let expected: Vec<Vec<f32>> = frames(signal.as_slice().unwrap(), 8, 4, PaddingMode::CenterReflect)
+      .unwrap()
+      .with_window(window.as_slice().unwrap())
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(array.nrows(), expected.len());
+    for (row, frame) in array.rows().into_iter().zip(expected.iter()) {
+      assert_eq!(row.to_vec(), *frame);
+    }
+
+    // A strided signal is copied before being framed
+    let strided = frames_array(signal.slice(s![..;2]), 4, 4, PaddingMode::None, None).unwrap();
+    assert_eq!(strided, array![[0.0, 2.0, 4.0, 6.0], [8.0, 10.0, 12.0, 14.0]]);
+  }
+
+  #[cfg(feature = "realfft")]
+  #[test]
+  fn test_spectrogram_array_matches_spectrogram() {
+    let signal = Array1::from_iter((0..300).map(|n| ((n * n) % 17) as f32));
+    let window = get_hann_window_array(32).unwrap();
+
+    let array = spectrogram_array(signal.view(), window.view(), 16, PaddingMode::CenterReflect).unwrap();
+    let expected = crate::stft::spectrogram(signal.as_slice().unwrap(), window.as_slice().unwrap(), 16, PaddingMode::CenterReflect).unwrap();
+
+    assert_eq!(array.dim(), (expected.len(), 17));
+    for (row, spectrum) in array.rows().into_iter().zip(expected.iter()) {
+      assert_eq!(row.to_vec(), *spectrum);
+    }
+  }
+}
@@ -0,0 +1,83 @@
+use crate::hann_window::HannWindowError;
+
+/// Compute the overlap envelope of a window repeated every `hop_size` samples.
+///
+/// This function returns a Vec of `signal_length` samples containing the sum of the copies of `window`
+/// placed at every multiple of `hop_size`, truncated to the signal length. Dividing an overlap-added
+/// signal by this envelope undoes the amplitude modulation introduced by the overlapping windows. When
+/// the frames are windowed both before and after processing, pass the product of the two windows.
+pub fn get_window_envelope(
+  window: &[f32],
+  hop_size: usize,
+  signal_length: usize
+) -> Result<Vec<f32>, HannWindowError> {
+  // The window must contain at least one sample
+  if window.is_empty() {
+    return Err(HannWindowError::InvalidFrameLength);
+  }
+  // The windows must advance by at least one sample
+  if hop_size == 0 {
+    return Err(HannWindowError::InvalidHopSize);
+  }
+
+  // Initialize the envelope with zeros and add a copy of the window at every hop
+  let mut envelope = vec![0.0; signal_length];
+  for start in (0..signal_length).step_by(hop_size) {
+    for (sample, &value) in envelope[start..].iter_mut().zip(window.iter()) {
+      *sample += value;
+    }
+  }
+
+  Ok(envelope)
+}
+
+/// Divide overlap-added samples by their overlap envelope in place.
+///
+/// Samples whose envelope value is too close to zero to be divided by are left untouched, which is what
+/// happens at the very edges of a signal framed with a window that tapers to zero.
+pub fn normalize_by_envelope(samples: &mut [f32], envelope: &[f32]) -> Result<(), HannWindowError> {
+  // The envelope must cover the samples one to one
+  if samples.len() != envelope.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Only divide where the envelope is large enough to be inverted
+  for (sample, &value) in samples.iter_mut().zip(envelope.iter()) {
+    if value > f32::MIN_POSITIVE {
+      *sample /= value;
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_normalization {
+  use crate::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_get_window_envelope_values() {
+    let envelope = get_window_envelope(&[1.0, 2.0, 1.0], 2, 6).unwrap();
+
+    assert_eq!(envelope, vec![1.0, 2.0, 2.0, 2.0, 2.0, 2.0]);
+  }
+
+  #[test]
+  fn test_normalize_by_envelope() {
+    let window = get_hann_window(5).unwrap();
+    let mut samples = window.clone();
+
+    normalize_by_envelope(&mut samples, &window).unwrap();
+
+    assert_eq!(samples, vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+    assert_eq!(normalize_by_envelope(&mut samples, &[1.0]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_get_window_envelope_invalid_parameters() {
+    assert_eq!(get_window_envelope(&[], 1, 4).unwrap_err(), HannWindowError::InvalidFrameLength);
+    assert_eq!(get_window_envelope(&[1.0], 0, 4).unwrap_err(), HannWindowError::InvalidHopSize);
+  }
+}
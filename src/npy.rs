@@ -0,0 +1,441 @@
+use std::{ error::Error, fmt, io::{ self, Read, Seek, Write } };
+
+use zip::{ result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter };
+
+// The magic string starting every .npy file
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+// The total length of the magic string, the version, the header length and the header is a multiple of this
+const NPY_HEADER_ALIGNMENT: usize = 64;
+
+// The longest header read, as numpy.load allows by default, so that a corrupt header length does not
+// make the reader allocate gigabytes
+const NPY_MAX_HEADER_LENGTH: usize = 10000;
+
+/// Error type for reading and writing `.npy` and `.npz` files.
+#[derive(Debug)]
+pub enum NpyError {
+  Io(io::Error),
+  Zip(ZipError),
+  InvalidFormat(String),
+  ShapeMismatch,
+  ArrayTooLarge,
+}
+
+// Implement the Error trait for the NpyError enum
+impl Error for NpyError {}
+
+// Implement the Display trait for the NpyError enum
+impl fmt::Display for NpyError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      NpyError::Io(error) => {
+        write!(f, "NpyError: Failed to read or write the file: {}", error)
+      }
+      NpyError::Zip(error) => {
+        write!(f, "NpyError: Failed to read or write the archive: {}", error)
+      }
+      NpyError::InvalidFormat(reason) => {
+        write!(f, "NpyError: Invalid or unsupported .npy file: {}", reason)
+      }
+      NpyError::ShapeMismatch => {
+        write!(f, "NpyError: The shape of the array does not match its values.")
+      }
+      NpyError::ArrayTooLarge => {
+        write!(f, "NpyError: The size of the array overflows the address space.")
+      }
+    }
+  }
+}
+
+// Convert IO errors into NpyError, so that they can be propagated with ?
+impl From<io::Error> for NpyError {
+  fn from(error: io::Error) -> Self {
+    NpyError::Io(error)
+  }
+}
+
+// Convert archive errors into NpyError, so that they can be propagated with ?
+impl From<ZipError> for NpyError {
+  fn from(error: ZipError) -> Self {
+    NpyError::Zip(error)
+  }
+}
+
+/// An n-dimensional `f32` array read from or written to a `.npy` file.
+///
+/// The values are stored in row-major (C) order, the last axis varying fastest, as NumPy does by
+/// default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+  shape: Vec<usize>,
+  values: Vec<f32>,
+}
+
+impl NpyArray {
+  /// Create an array of the given `shape` from its `values` in row-major order.
+  ///
+  /// An error is returned if the number of values differs from the product of the shape.
+  pub fn new(shape: Vec<usize>, values: Vec<f32>) -> Result<Self, NpyError> {
+    // The values must fill the shape exactly
+    if value_count(&shape) != Some(values.len()) {
+      return Err(NpyError::ShapeMismatch);
+    }
+    Ok(NpyArray { shape, values })
+  }
+
+  /// Create a one-dimensional array from a window.
+  pub fn from_window(window: Vec<f32>) -> Self {
+    NpyArray { shape: vec![window.len()], values: window }
+  }
+
+  /// Create a two-dimensional array from the rows of a matrix, e.g. a spectrogram with one spectrum
+  /// per row.
+  ///
+  /// An error is returned if the rows differ in length.
+  pub fn from_rows(rows: &[Vec<f32>]) -> Result<Self, NpyError> {
+    let column_count = rows.first().map_or(0, Vec::len);
+    // Every row must have the same length
+    if rows.iter().any(|row| row.len() != column_count) {
+      return Err(NpyError::ShapeMismatch);
+    }
+    Ok(NpyArray { shape: vec![rows.len(), column_count], values: rows.concat() })
+  }
+
+  /// The length of every axis of the array.
+  pub fn shape(&self) -> &[usize] {
+    &self.shape
+  }
+
+  /// The values of the array in row-major order.
+  pub fn values(&self) -> &[f32] {
+    &self.values
+  }
+
+  /// Return the values of a one-dimensional array, e.g. a window.
+  ///
+  /// An error is returned if the array has more than one dimension.
+  pub fn into_window(self) -> Result<Vec<f32>, NpyError> {
+    match self.shape.as_slice() {
+      [_] => Ok(self.values),
+      _ => Err(NpyError::ShapeMismatch),
+    }
+  }
+
+  /// Return the rows of a two-dimensional array, e.g. a spectrogram.
+  ///
+  /// An error is returned if the array does not have two dimensions.
+  pub fn into_rows(self) -> Result<Vec<Vec<f32>>, NpyError> {
+    match *self.shape.as_slice() {
+      [row_count, 0] => Ok(vec![Vec::new(); row_count]),
+      [_, column_count] => Ok(self.values.chunks(column_count).map(<[f32]>::to_vec).collect()),
+      _ => Err(NpyError::ShapeMismatch),
+    }
+  }
+}
+
+/// Write an array to `writer` in the `.npy` format, as little-endian `float32` values.
+///
+/// The file is written with version 1.0 of the format and can be read with `numpy.load`. The values are
+/// written bit for bit, so a round trip through NumPy is exact.
+pub fn write_npy<W: Write>(mut writer: W, array: &NpyArray) -> Result<(), NpyError> {
+  // A one-dimensional shape has a trailing comma in Python
+  let shape = match array.shape.as_slice() {
+    [length] => format!("({},)", length),
+    shape => format!("({})", shape.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+  };
+  let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+
+  // Pad the header with spaces and end it with a newline, so that the values are aligned
+  let prefix_length = NPY_MAGIC.len() + 4;
+  let padded_length = (prefix_length + header.len() + 1).div_ceil(NPY_HEADER_ALIGNMENT) * NPY_HEADER_ALIGNMENT;
+  header.extend(std::iter::repeat_n(' ', padded_length - prefix_length - header.len() - 1));
+  header.push('\n');
+  let header_length = u16::try_from(header.len())
+    .map_err(|_error| NpyError::InvalidFormat(String::from("the header is too long")))?;
+
+  writer.write_all(NPY_MAGIC)?;
+  writer.write_all(&[1, 0])?;
+  writer.write_all(&header_length.to_le_bytes())?;
+  writer.write_all(header.as_bytes())?;
+  let bytes: Vec<u8> = array.values.iter().flat_map(|value| value.to_le_bytes()).collect();
+  writer.write_all(&bytes)?;
+
+  Ok(())
+}
+
+/// Read an array in the `.npy` format from `reader`.
+///
+/// Versions 1.0 to 3.0 of the format are supported, with `float32` or `float64` values of either byte
+/// order in row-major or column-major order. `float64` values are rounded to `f32` and column-major
+/// arrays are reordered to row-major order. An error is returned for any other format.
+pub fn read_npy<R: Read>(mut reader: R) -> Result<NpyArray, NpyError> {
+  // The magic string and the version
+  let mut prefix = [0u8; 8];
+  reader.read_exact(&mut prefix)?;
+  if &prefix[..6] != NPY_MAGIC {
+    return Err(NpyError::InvalidFormat(String::from("the magic string is missing")));
+  }
+
+  // The header length is 2 bytes long in version 1.0 and 4 bytes long in later versions
+  let header_length = match prefix[6] {
+    1 => {
+      let mut length = [0u8; 2];
+      reader.read_exact(&mut length)?;
+      usize::from(u16::from_le_bytes(length))
+    }
+    2 | 3 => {
+      let mut length = [0u8; 4];
+      reader.read_exact(&mut length)?;
+      u32::from_le_bytes(length) as usize
+    }
+    version => return Err(NpyError::InvalidFormat(format!("version {} is not supported", version))),
+  };
+  if header_length > NPY_MAX_HEADER_LENGTH {
+    return Err(NpyError::InvalidFormat(format!("the header length {} is too long", header_length)));
+  }
+  let header = read_bytes(&mut reader, header_length)?;
+  let header = String::from_utf8_lossy(&header);
+
+  // Parse the fields of the header dictionary
+  let descr = header_value(&header, "descr")?;
+  let fortran_order = match header_value(&header, "fortran_order")? {
+    "True" => true,
+    "False" => false,
+    value => return Err(NpyError::InvalidFormat(format!("invalid fortran_order {}", value))),
+  };
+  let shape = parse_shape(header_value(&header, "shape")?)?;
+  let value_count = value_count(&shape).ok_or(NpyError::ArrayTooLarge)?;
+
+  // Decode the values, whatever their width and byte order
+  let values: Vec<f32> = match descr.trim_matches(|c| c == '\'' || c == '"') {
+    "<f4" => read_values(&mut reader, value_count, |bytes: [u8; 4]| f32::from_le_bytes(bytes))?,
+    ">f4" => read_values(&mut reader, value_count, |bytes: [u8; 4]| f32::from_be_bytes(bytes))?,
+    "<f8" => read_values(&mut reader, value_count, |bytes: [u8; 8]| f64::from_le_bytes(bytes) as f32)?,
+    ">f8" => read_values(&mut reader, value_count, |bytes: [u8; 8]| f64::from_be_bytes(bytes) as f32)?,
+    descr => return Err(NpyError::InvalidFormat(format!("dtype {} is not supported", descr))),
+  };
+
+  // Column-major arrays are reordered to row-major order
+  let values = if fortran_order && shape.len() > 1 { column_major_to_row_major(&shape, &values) } else { values };
+
+  NpyArray::new(shape, values)
+}
+
+/// Write a set of named arrays to `writer` in the `.npz` format, as `numpy.savez` does.
+///
+/// Every array is stored uncompressed in the archive as `<name>.npy`, see [`write_npy`], and the
+/// archive can be read with `numpy.load`.
+pub fn write_npz<W: Write + Seek>(writer: W, arrays: &[(&str, &NpyArray)]) -> Result<(), NpyError> {
+  let mut archive = ZipWriter::new(writer);
+  let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+  for (name, array) in arrays {
+    archive.start_file(format!("{}.npy", name), options)?;
+    write_npy(&mut archive, array)?;
+  }
+  archive.finish()?;
+
+  Ok(())
+}
+
+/// Read every array of an archive in the `.npz` format from `reader`, in the order of the archive.
+///
+/// The names are returned without the `.npy` extension. Archives written by `numpy.savez` and
+/// `numpy.savez_compressed` are supported, see [`read_npy`] for the supported arrays.
+pub fn read_npz<R: Read + Seek>(reader: R) -> Result<Vec<(String, NpyArray)>, NpyError> {
+  let mut archive = ZipArchive::new(reader)?;
+  let mut arrays = Vec::with_capacity(archive.len());
+  for index in 0..archive.len() {
+    let file = archive.by_index(index)?;
+    let name = file.name().trim_end_matches(".npy").to_string();
+    arrays.push((name, read_npy(file)?));
+  }
+
+  Ok(arrays)
+}
+
+// Find the value of a key of the header dictionary, up to the next comma outside of parentheses
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, NpyError> {
+  let missing = || NpyError::InvalidFormat(format!("the header has no {}", key));
+  let start = header
+    .find(&format!("'{}'", key))
+    .map(|position| position + key.len() + 2)
+    .ok_or_else(missing)?;
+  let value = header[start..].trim_start().strip_prefix(':').ok_or_else(missing)?.trim_start();
+
+  let mut depth = 0;
+  for (position, character) in value.char_indices() {
+    match character {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      ',' | '}' if depth == 0 => return Ok(value[..position].trim()),
+      _ => {}
+    }
+  }
+  Err(missing())
+}
+
+// Parse a Python tuple of lengths, e.g. "(3, 4)" or "(5,)"
+fn parse_shape(value: &str) -> Result<Vec<usize>, NpyError> {
+  let invalid = || NpyError::InvalidFormat(format!("invalid shape {}", value));
+  value
+    .strip_prefix('(')
+    .and_then(|value| value.strip_suffix(')'))
+    .ok_or_else(invalid)?
+    .split(',')
+    .map(str::trim)
+    .filter(|length| !length.is_empty())
+    .map(|length| length.parse().map_err(|_error| invalid()))
+    .collect()
+}
+
+// The number of values of an array of the given shape, or `None` if it overflows
+fn value_count(shape: &[usize]) -> Option<usize> {
+  shape.iter().try_fold(1usize, |count, &length| count.checked_mul(length))
+}
+
+// Read exactly `length` bytes, growing the buffer only as bytes arrive, so that a length taken from
+// the file cannot allocate more than the file holds
+fn read_bytes<R: Read>(reader: &mut R, length: usize) -> Result<Vec<u8>, NpyError> {
+  let mut bytes = Vec::new();
+  reader.take(length as u64).read_to_end(&mut bytes)?;
+  if bytes.len() != length {
+    return Err(NpyError::Io(io::Error::from(io::ErrorKind::UnexpectedEof)));
+  }
+  Ok(bytes)
+}
+
+// Read `count` values of `N` bytes each, decoded by `decode`
+fn read_values<R: Read, const N: usize>(
+  reader: &mut R,
+  count: usize,
+  decode: impl Fn([u8; N]) -> f32
+) -> Result<Vec<f32>, NpyError> {
+  let bytes = read_bytes(reader, count.checked_mul(N).ok_or(NpyError::ArrayTooLarge)?)?;
+  Ok(bytes.chunks_exact(N).map(|chunk| decode(chunk.try_into().expect("Chunks have N bytes"))).collect())
+}
+
+// Reorder values stored with the first axis varying fastest so that the last axis varies fastest
+fn column_major_to_row_major(shape: &[usize], values: &[f32]) -> Vec<f32> {
+  let mut reordered = Vec::with_capacity(values.len());
+  let mut index = vec![0; shape.len()];
+  for _ in 0..values.len() {
+    // The column-major offset of the current row-major index
+    let mut offset = 0;
+    let mut stride = 1;
+    for (&position, &length) in index.iter().zip(shape.iter()) {
+      offset += position * stride;
+      stride *= length;
+    }
+    reordered.push(values[offset]);
+
+    // Advance the row-major index, the last axis first
+    for (position, &length) in index.iter_mut().zip(shape.iter()).rev() {
+      *position += 1;
+      if *position < length {
+        break;
+      }
+      *position = 0;
+    }
+  }
+  reordered
+}
+
+#[cfg(test)]
+mod test_npy {
+  use std::io::Cursor;
+
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_npy_round_trip() {
+    let window = get_hann_window(7).unwrap();
+    let mut bytes = Vec::new();
+
+    write_npy(&mut bytes, &NpyArray::from_window(window.clone())).unwrap();
+
+    // The header is padded so that the values start on a 64-byte boundary
+    assert_eq!(&bytes[..10], b"\x93NUMPY\x01\x00\x76\x00");
+    assert_eq!(bytes.len(), 128 + 7 * 4);
+    assert_eq!(bytes[127], b'\n');
+    assert_eq!(read_npy(Cursor::new(bytes)).unwrap().into_window().unwrap(), window);
+  }
+
+  #[test]
+  fn test_npy_matrix_round_trip() {
+    let rows = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, f32::MIN_POSITIVE]];
+    let mut bytes = Vec::new();
+
+    write_npy(&mut bytes, &NpyArray::from_rows(&rows).unwrap()).unwrap();
+    let array = read_npy(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(array.shape(), &[2, 3]);
+    assert_eq!(array.into_rows().unwrap(), rows);
+    assert!(matches!(NpyArray::from_rows(&[vec![1.0], vec![]]), Err(NpyError::ShapeMismatch)));
+  }
+
+  #[test]
+  fn test_read_npy_float64_column_major() {
+    // The file numpy.save writes for numpy.array([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]], order='F')
+    let mut header = String::from("{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }");
+    header.extend(std::iter::repeat_n(' ', 118 - header.len() - 1));
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+    bytes.extend(header.as_bytes());
+    for value in [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0] {
+      bytes.extend(value.to_le_bytes());
+    }
+
+    let array = read_npy(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(array.into_rows().unwrap(), vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+  }
+
+  #[test]
+  fn test_npz_round_trip() {
+    let window = NpyArray::from_window(get_hann_window(16).unwrap());
+    let spectrogram = NpyArray::new(vec![2, 2], vec![0.5, 1.5, 2.5, 3.5]).unwrap();
+    let mut archive = Cursor::new(Vec::new());
+
+    write_npz(&mut archive, &[("window", &window), ("spectrogram", &spectrogram)]).unwrap();
+    let arrays = read_npz(archive).unwrap();
+
+    assert_eq!(arrays, vec![(String::from("window"), window), (String::from("spectrogram"), spectrogram)]);
+  }
+
+  #[test]
+  fn test_read_npy_invalid_files() {
+    assert!(matches!(read_npy(Cursor::new(b"NUMPY".to_vec())), Err(NpyError::Io(_))));
+    assert!(matches!(read_npy(Cursor::new(b"\x93NUMPZ\x01\x00".to_vec())), Err(NpyError::InvalidFormat(_))));
+
+    // Integer arrays are not supported
+    let header = b"{'descr': '<i4', 'fortran_order': False, 'shape': (1,), }\n";
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header);
+    bytes.extend(1i32.to_le_bytes());
+    assert!(matches!(read_npy(Cursor::new(bytes)), Err(NpyError::InvalidFormat(_))));
+  }
+
+  #[test]
+  fn test_read_npy_untrusted_lengths() {
+    let npy = |version: u8, length: &[u8], header: &[u8]| {
+      let mut bytes = vec![0x93, b'N', b'U', b'M', b'P', b'Y', version, 0];
+      bytes.extend(length);
+      bytes.extend(header);
+      Cursor::new(bytes)
+    };
+
+    // A header length beyond the cap is rejected before reading it
+    assert!(matches!(read_npy(npy(2, &u32::MAX.to_le_bytes(), b"")), Err(NpyError::InvalidFormat(_))));
+    // A shape overflowing the address space is rejected
+    let header = b"{'descr': '<f4', 'fortran_order': False, 'shape': (4294967296, 4294967296), }\n";
+    assert!(matches!(read_npy(npy(1, &(header.len() as u16).to_le_bytes(), header)), Err(NpyError::ArrayTooLarge)));
+    // A large shape with no values fails on the missing values without allocating for them
+    let header = b"{'descr': '<f8', 'fortran_order': False, 'shape': (1000000000000,), }\n";
+    assert!(matches!(read_npy(npy(1, &(header.len() as u16).to_le_bytes(), header)), Err(NpyError::Io(_))));
+  }
+}
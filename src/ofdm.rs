@@ -0,0 +1,139 @@
+use std::f64::consts::PI;
+
+use crate::hann_window::HannWindowError;
+
+/// Generate the rising raised-cosine edge taper of a windowed OFDM symbol.
+///
+/// The taper `0.5 - 0.5 * cos(π * (n + 0.5) / W)` rises over `taper_length` samples, and the falling
+/// edge is the same taper reversed. The rising edge of a symbol and the falling edge of the previous one
+/// sum to 1 where they overlap, so that the taper only shapes the transitions between symbols. A taper
+/// length of 0 gives an empty taper.
+pub fn ofdm_edge_taper(taper_length: usize) -> Vec<f32> {
+  (0..taper_length)
+    .map(|n| (0.5 - 0.5 * ((PI * ((n as f64) + 0.5)) / (taper_length as f64)).cos()) as f32)
+    .collect()
+}
+
+/// Add cyclic prefixes to consecutive OFDM symbols, taper their edges and overlap adjacent symbols.
+///
+/// This function takes the time-domain `symbols` as they come out of the inverse FFT, one after the
+/// other with `symbol_length` samples each, and extends every symbol with a cyclic prefix of
+/// `cyclic_prefix_length` samples and a cyclic suffix of `taper_length` samples. The first and last
+/// `taper_length` samples of every extended symbol are tapered by [`ofdm_edge_taper`], and the suffix
+/// of each symbol overlaps the start of the prefix of the next one, which lowers the out-of-band
+/// emission of the spectrum. The symbols stay spaced by `symbol_length + cyclic_prefix_length`
+/// samples, the output ending with the suffix of the last symbol, while the taper shortens the part of
+/// the cyclic prefix protecting against intersymbol interference. Complex baseband symbols are
+/// windowed by applying this function to the in-phase and quadrature parts separately.
+///
+/// An error is returned if the symbol length is 0, if the number of samples is not a multiple of the
+/// symbol length, if the cyclic prefix is longer than a symbol or if the taper is longer than the
+/// cyclic prefix.
+pub fn window_ofdm_symbols(
+  symbols: &[f32],
+  symbol_length: usize,
+  cyclic_prefix_length: usize,
+  taper_length: usize
+) -> Result<Vec<f32>, HannWindowError> {
+  // The symbols must be whole
+  if symbol_length == 0 {
+    return Err(HannWindowError::InvalidFrameLength);
+  }
+  if !symbols.len().is_multiple_of(symbol_length) {
+    return Err(HannWindowError::LengthMismatch);
+  }
+  // The cyclic extensions are taken from the symbol itself, and the rising edge lies within the prefix
+  if cyclic_prefix_length > symbol_length || taper_length > cyclic_prefix_length {
+    return Err(HannWindowError::WindowLengthTooLarge);
+  }
+
+  let taper = ofdm_edge_taper(taper_length);
+  let spacing = symbol_length + cyclic_prefix_length;
+  let symbol_count = symbols.len() / symbol_length;
+  let mut output = vec![0.0; symbol_count * spacing + taper_length];
+
+  for (index, symbol) in symbols.chunks_exact(symbol_length).enumerate() {
+    // The symbol extended by its prefix and its suffix
+    let extended = symbol[symbol_length - cyclic_prefix_length..]
+      .iter()
+      .chain(symbol.iter())
+      .chain(symbol[..taper_length].iter());
+    let start = index * spacing;
+    let extended_length = spacing + taper_length;
+
+    for (n, (output, &sample)) in output[start..start + extended_length].iter_mut().zip(extended).enumerate() {
+      // Taper the edges and add the overlapping neighbor
+      let gain = if n < taper_length {
+        taper[n]
+      } else if n >= extended_length - taper_length {
+        taper[extended_length - 1 - n]
+      } else {
+        1.0
+      };
+      *output += sample * gain;
+    }
+  }
+
+  Ok(output)
+}
+
+#[cfg(test)]
+mod test_ofdm {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_ofdm_edge_taper() {
+    let taper = ofdm_edge_taper(8);
+
+    // The rising and falling edges are complementary
+    for n in 0..8 {
+      assert_abs_diff_eq!(taper[n] + taper[7 - n], 1.0, epsilon = 1e-7);
+      assert!(taper[n] > 0.0 && taper[n] < 1.0);
+    }
+    assert!(ofdm_edge_taper(0).is_empty());
+  }
+
+  #[test]
+  fn test_window_ofdm_symbols_cyclic_prefix() {
+    let symbols = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+
+    // Without a taper, the symbols are only given their cyclic prefix
+    let output = window_ofdm_symbols(&symbols, 4, 2, 0).unwrap();
+
+    assert_eq!(output, vec![3.0, 4.0, 1.0, 2.0, 3.0, 4.0, 7.0, 8.0, 5.0, 6.0, 7.0, 8.0]);
+  }
+
+  #[test]
+  fn test_window_ofdm_symbols_overlap() {
+    let symbols: Vec<f32> = (0..32).map(|n| ((n * 7) % 11) as f32 - 5.0).collect();
+    let taper = ofdm_edge_taper(2);
+
+    let output = window_ofdm_symbols(&symbols, 16, 4, 2).unwrap();
+
+    // The first symbol rises at the start of its prefix
+    assert_eq!(output.len(), 2 * 20 + 2);
+    assert_abs_diff_eq!(output[0], symbols[12] * taper[0], epsilon = 1e-6);
+    assert_abs_diff_eq!(output[1], symbols[13] * taper[1], epsilon = 1e-6);
+    // The symbols and the rest of their prefixes are untouched
+    assert_eq!(&output[2..4], &symbols[14..16]);
+    assert_eq!(&output[4..20], &symbols[0..16]);
+    // The suffix of the first symbol overlaps the rising edge of the second one
+    for n in 0..2 {
+      let expected = symbols[n] * taper[1 - n] + symbols[16 + 12 + n] * taper[n];
+      assert_abs_diff_eq!(output[20 + n], expected, epsilon = 1e-6);
+    }
+    assert_eq!(&output[22..24], &symbols[30..32]);
+    // The output ends with the falling edge of the last symbol
+    assert_abs_diff_eq!(output[41], symbols[17] * taper[0], epsilon = 1e-6);
+  }
+
+  #[test]
+  fn test_window_ofdm_symbols_invalid_parameters() {
+    assert_eq!(window_ofdm_symbols(&[0.0; 8], 0, 0, 0).unwrap_err(), HannWindowError::InvalidFrameLength);
+    assert_eq!(window_ofdm_symbols(&[0.0; 10], 4, 1, 1).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(window_ofdm_symbols(&[0.0; 8], 4, 1, 2).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+    assert_eq!(window_ofdm_symbols(&[0.0; 8], 4, 5, 0).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+  }
+}
@@ -0,0 +1,347 @@
+use crate::{
+  hann_window::HannWindowError,
+  instrument::{ record_metric, trace_allocation },
+  latency::Latency,
+  normalization::normalize_by_envelope,
+};
+
+/// How the output of an [`OverlapAdd`] is compensated for the gain of the windows and the overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GainCompensation {
+  /// Output the raw overlap-added samples, for callers who handle the scaling themselves.
+  None,
+  /// Multiply the output by `hop_size / sum(analysis * synthesis)`.
+  ///
+  /// This is the inverse of the steady-state overlap gain: `hop_size / sum(window)` for overlap-add and
+  /// `hop_size / sum(window^2)` for weighted overlap-add with the same analysis and synthesis window.
+  /// It is exact for windows satisfying the constant overlap-add condition, away from the edges.
+  Constant,
+  /// Divide every output sample by the overlap envelope of the windows, which is exact for any window
+  /// and hop size, including the edges of the signal.
+  Envelope,
+}
+
+/// An overlap-add accumulator reconstructing a signal from frames pushed every `hop_size` samples.
+///
+/// Every pushed frame is optionally multiplied by a synthesis window (weighted overlap-add) and added
+/// onto the frames before it. Once a frame has been pushed, the first `hop_size` accumulated samples
+/// can no longer be touched by later frames and are returned as finished output. Call
+/// [`OverlapAdd::flush`] after the last frame to get the remaining tail.
+///
+/// The output can be compensated for the gain of the windows and the overlap, see
+/// [`GainCompensation`], so that frames produced by the `frames` iterator are reconstructed with unit
+/// gain. All buffers are allocated on construction, pushing frames does not allocate.
+#[derive(Debug, Clone)]
+pub struct OverlapAdd {
+  frame_length: usize,
+  hop_size: usize,
+  synthesis_window: Option<Vec<f32>>,
+  analysis_window: Option<Vec<f32>>,
+  gain_compensation: GainCompensation,
+  // The gain applied by `GainCompensation::Constant`
+  constant_gain: f32,
+  accumulator: Vec<f32>,
+  envelope: Vec<f32>,
+  output: Vec<f32>,
+}
+
+impl OverlapAdd {
+  /// Create an overlap-add accumulator for frames of `frame_length` samples pushed every `hop_size` samples.
+  ///
+  /// An error is returned if the frame length or the hop size is 0.
+  pub fn new(frame_length: usize, hop_size: usize) -> Result<Self, HannWindowError> {
+    // A frame must contain at least one sample
+    if frame_length == 0 {
+      return Err(HannWindowError::InvalidFrameLength);
+    }
+    // The frames must advance by at least one sample
+    if hop_size == 0 {
+      return Err(HannWindowError::InvalidHopSize);
+    }
+
+    // The accumulator must hold a whole frame and a whole hop, whichever is longer
+    let accumulator_length = frame_length.max(hop_size);
+    trace_allocation("OverlapAdd", 3 * accumulator_length * std::mem::size_of::<f32>());
+
+    let mut overlap_add = OverlapAdd {
+      frame_length,
+      hop_size,
+      synthesis_window: None,
+      analysis_window: None,
+      gain_compensation: GainCompensation::None,
+      constant_gain: 1.0,
+      accumulator: vec![0.0; accumulator_length],
+      envelope: vec![0.0; accumulator_length],
+      output: vec![0.0; accumulator_length],
+    };
+    overlap_add.update_constant_gain();
+
+    Ok(overlap_add)
+  }
+
+  /// Multiply every pushed frame by `synthesis_window` before adding it.
+  ///
+  /// An error is returned if the length of the window is different from the frame length.
+  pub fn with_window(mut self, synthesis_window: &[f32]) -> Result<Self, HannWindowError> {
+    // The window must cover the frame sample for sample
+    if synthesis_window.len() != self.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.synthesis_window = Some(synthesis_window.to_vec());
+    self.update_constant_gain();
+    Ok(self)
+  }
+
+  /// Divide the output by the overlap envelope of the frames analyzed with `analysis_window`.
+  ///
+  /// The envelope is accumulated from the product of the analysis window and the synthesis window, if
+  /// one is set, using the same rule as [`normalize_by_envelope`](crate::stft::normalize_by_envelope). This
+  /// is the same as [`GainCompensation::Envelope`]. An error is returned if the length of the window is
+  /// different from the frame length.
+  pub fn with_normalization(self, analysis_window: &[f32]) -> Result<Self, HannWindowError> {
+    self.with_gain_compensation(analysis_window, GainCompensation::Envelope)
+  }
+
+  /// Compensate the output for the gain of the frames analyzed with `analysis_window` and the synthesis
+  /// window, if one is set.
+  ///
+  /// An error is returned if the length of the window is different from the frame length.
+  pub fn with_gain_compensation(
+    mut self,
+    analysis_window: &[f32],
+    gain_compensation: GainCompensation
+  ) -> Result<Self, HannWindowError> {
+    // The window must cover the frame sample for sample
+    if analysis_window.len() != self.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.analysis_window = Some(analysis_window.to_vec());
+    self.update_constant_gain();
+    self.set_gain_compensation(gain_compensation);
+    Ok(self)
+  }
+
+  /// Switch the gain compensation on or off, for example to let the caller scale the output.
+  ///
+  /// Without an analysis window, the frames are assumed to be unwindowed. Samples that are accumulated
+  /// already are compensated in the new way, which does not allocate.
+  pub fn set_gain_compensation(&mut self, gain_compensation: GainCompensation) {
+    self.gain_compensation = gain_compensation;
+  }
+
+  /// The gain compensation applied to the output.
+  pub fn gain_compensation(&self) -> GainCompensation {
+    self.gain_compensation
+  }
+
+  /// The length of the pushed frames.
+  pub fn frame_length(&self) -> usize {
+    self.frame_length
+  }
+
+  /// The number of finished samples returned for every pushed frame.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// Add a frame onto the accumulated signal and return the `hop_size` samples it finished.
+  ///
+  /// An error is returned if the length of the frame is different from the frame length.
+  pub fn push(&mut self, frame: &[f32]) -> Result<&[f32], HannWindowError> {
+    // The frame must have the configured length
+    if frame.len() != self.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    record_metric!(record_frames, StreamingProcessor::OverlapAdd, 1);
+
+    // Add the (windowed) frame and its envelope onto the accumulated samples
+    for (i, &sample) in frame.iter().enumerate() {
+      let synthesis = self.synthesis_window.as_ref().map_or(1.0, |window| window[i]);
+      self.accumulator[i] += sample * synthesis;
+      let analysis = self.analysis_window.as_ref().map_or(1.0, |window| window[i]);
+      self.envelope[i] += analysis * synthesis;
+    }
+
+    // The first hop of samples is not overlapped by any later frame
+    let finished = self.hop_size;
+    self.finish(finished);
+
+    // Shift the accumulated samples by one hop to make room for the next frame
+    self.accumulator.copy_within(finished.., 0);
+    self.envelope.copy_within(finished.., 0);
+    let remaining = self.accumulator.len() - finished;
+    self.accumulator[remaining..].fill(0.0);
+    self.envelope[remaining..].fill(0.0);
+
+    Ok(&self.output[..finished])
+  }
+
+  /// Return the samples still overlapping the last pushed frame and reset the accumulator.
+  ///
+  /// The tail contains `frame_length - hop_size` samples, or none if the hop size is not smaller
+  /// than the frame length.
+  pub fn flush(&mut self) -> &[f32] {
+    // Everything left in the accumulator is final now
+    let tail = self.frame_length.saturating_sub(self.hop_size);
+    self.finish(tail);
+
+    // Start over with an empty accumulator
+    self.accumulator.fill(0.0);
+    self.envelope.fill(0.0);
+
+    &self.output[..tail]
+  }
+
+  /// Discard every accumulated sample.
+  pub fn reset(&mut self) {
+    self.accumulator.fill(0.0);
+    self.envelope.fill(0.0);
+  }
+
+  // Copy the first `count` accumulated samples into the output buffer and compensate their gain
+  fn finish(&mut self, count: usize) {
+    let output = &mut self.output[..count];
+    output.copy_from_slice(&self.accumulator[..count]);
+    match self.gain_compensation {
+      GainCompensation::None => {}
+      GainCompensation::Constant => {
+        output.iter_mut().for_each(|sample| {
+          *sample *= self.constant_gain;
+        });
+      }
+      GainCompensation::Envelope => {
+        normalize_by_envelope(output, &self.envelope[..count]).expect("Output and envelope have the same length");
+      }
+    }
+  }
+
+  // Compute the inverse of the steady-state overlap gain of the analysis and synthesis windows
+  fn update_constant_gain(&mut self) {
+    let window_gain: f32 = (0..self.frame_length)
+      .map(|i| {
+        let analysis = self.analysis_window.as_ref().map_or(1.0, |window| window[i]);
+        let synthesis = self.synthesis_window.as_ref().map_or(1.0, |window| window[i]);
+        analysis * synthesis
+      })
+      .sum();
+    // Leave the output untouched where the windows have no gain, like the envelope normalization
+    self.constant_gain = if window_gain > f32::MIN_POSITIVE { (self.hop_size as f32) / window_gain } else { 1.0 };
+  }
+}
+
+/// The first sample of a pushed frame is output right away, its center `(frame_length - 1) / 2` samples later.
+impl Latency for OverlapAdd {
+  fn latency(&self) -> usize {
+    0
+  }
+
+  fn group_delay(&self) -> f64 {
+    ((self.frame_length - 1) as f64) / 2.0
+  }
+}
+
+#[cfg(test)]
+mod test_overlap_add {
+  use approx::assert_abs_diff_eq;
+
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  #[test]
+  fn test_overlap_add_sum() {
+    let mut overlap_add = OverlapAdd::new(4, 2).unwrap();
+
+    assert_eq!(overlap_add.push(&[1.0; 4]).unwrap(), &[1.0, 1.0]);
+    assert_eq!(overlap_add.push(&[1.0; 4]).unwrap(), &[2.0, 2.0]);
+    assert_eq!(overlap_add.flush(), &[1.0, 1.0]);
+    assert_eq!(overlap_add.push(&[3.0; 4]).unwrap(), &[3.0, 3.0]);
+  }
+
+  #[test]
+  fn test_overlap_add_hop_larger_than_frame() {
+    let mut overlap_add = OverlapAdd::new(2, 3).unwrap();
+
+    assert_eq!(overlap_add.push(&[1.0, 2.0]).unwrap(), &[1.0, 2.0, 0.0]);
+    assert!(overlap_add.flush().is_empty());
+  }
+
+  #[test]
+  fn test_overlap_add_reconstruction() {
+    let signal: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.3).sin()).collect();
+    let window = get_hann_window(16).unwrap();
+    let mut overlap_add = OverlapAdd::new(16, 4)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .with_normalization(&window)
+      .unwrap();
+
+    // Analyze and resynthesize the signal with the same window
+    let mut output = Vec::new();
+    for frame in frames(&signal, 16, 4, PaddingMode::Zero).unwrap().with_window(&window).unwrap() {
+      output.extend_from_slice(overlap_add.push(&frame).unwrap());
+    }
+    output.extend_from_slice(overlap_add.flush());
+
+    // The very first and last samples are multiplied by zero window values and cannot be recovered
+    for i in 1..signal.len() - 1 {
+      assert_abs_diff_eq!(output[i], signal[i], epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_overlap_add_constant_gain_compensation() {
+    // A periodic Hann window satisfies the constant overlap-add condition at a quarter-frame hop
+    let window: Vec<f32> = (0..16).map(|i| 0.5 - 0.5 * ((2.0 * std::f32::consts::PI * (i as f32)) / 16.0).cos()).collect();
+    let signal: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.3).sin()).collect();
+    let mut overlap_add = OverlapAdd::new(16, 4)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .with_gain_compensation(&window, GainCompensation::Constant)
+      .unwrap();
+
+    let mut output = Vec::new();
+    for frame in frames(&signal, 16, 4, PaddingMode::None).unwrap().with_window(&window).unwrap() {
+      output.extend_from_slice(overlap_add.push(&frame).unwrap());
+    }
+
+    // Once four frames overlap, the reconstruction has unit gain
+    for i in 12..output.len() {
+      assert_abs_diff_eq!(output[i], signal[i], epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_overlap_add_toggle_gain_compensation() {
+    let mut overlap_add = OverlapAdd::new(4, 2).unwrap();
+    assert_eq!(overlap_add.gain_compensation(), GainCompensation::None);
+
+    // Unwindowed frames overlapping twice are compensated by half
+    overlap_add.set_gain_compensation(GainCompensation::Constant);
+    assert_eq!(overlap_add.push(&[1.0; 4]).unwrap(), &[0.5, 0.5]);
+    assert_eq!(overlap_add.push(&[1.0; 4]).unwrap(), &[1.0, 1.0]);
+
+    // The envelope is exact at the edges as well
+    overlap_add.set_gain_compensation(GainCompensation::Envelope);
+    assert_eq!(overlap_add.flush(), &[1.0, 1.0]);
+
+    overlap_add.set_gain_compensation(GainCompensation::None);
+    assert_eq!(overlap_add.push(&[3.0; 4]).unwrap(), &[3.0, 3.0]);
+  }
+
+  #[test]
+  fn test_overlap_add_invalid_parameters() {
+    assert_eq!(OverlapAdd::new(0, 1).unwrap_err(), HannWindowError::InvalidFrameLength);
+    assert_eq!(OverlapAdd::new(4, 0).unwrap_err(), HannWindowError::InvalidHopSize);
+    assert_eq!(OverlapAdd::new(4, 2).unwrap().with_window(&[1.0]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(OverlapAdd::new(4, 2).unwrap().push(&[1.0]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(
+      OverlapAdd::new(4, 2).unwrap().with_gain_compensation(&[1.0], GainCompensation::Constant).unwrap_err(),
+      HannWindowError::LengthMismatch
+    );
+  }
+}
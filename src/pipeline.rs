@@ -0,0 +1,544 @@
+use std::{ error::Error, fmt, io::{ self, Write }, time::Duration };
+
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+  duration::{ duration_to_samples, DurationRounding },
+  export::format_value,
+  fft_length::{ LengthAdjustment, LengthRounding },
+  frames::{ get_frame_count, PaddingMode },
+  hann_window::HannWindowError,
+  instrument::{ trace_event, trace_span },
+  mel::MelFilterbank,
+  spectral::{ spectrogram, welch },
+  window_spec::WindowSpec,
+};
+#[cfg(feature = "npy")]
+use crate::npy::{ write_npy, NpyArray, NpyError };
+
+/// Error type for the analysis pipelines.
+#[derive(Debug)]
+pub enum PipelineError {
+  Config(String),
+  Window(HannWindowError),
+  Io(io::Error),
+  #[cfg(feature = "npy")]
+  Npy(NpyError),
+}
+
+// Implement the Error trait for the PipelineError enum
+impl Error for PipelineError {}
+
+// Implement the Display trait for the PipelineError enum
+impl fmt::Display for PipelineError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      PipelineError::Config(reason) => {
+        write!(f, "PipelineError: Invalid pipeline configuration: {}", reason)
+      }
+      PipelineError::Window(error) => {
+        write!(f, "PipelineError: {}", error)
+      }
+      PipelineError::Io(error) => {
+        write!(f, "PipelineError: Failed to write the output: {}", error)
+      }
+      #[cfg(feature = "npy")]
+      PipelineError::Npy(error) => {
+        write!(f, "PipelineError: {}", error)
+      }
+    }
+  }
+}
+
+// Convert window errors into PipelineError, so that they can be propagated with ?
+impl From<HannWindowError> for PipelineError {
+  fn from(error: HannWindowError) -> Self {
+    PipelineError::Window(error)
+  }
+}
+
+// Convert IO errors into PipelineError, so that they can be propagated with ?
+impl From<io::Error> for PipelineError {
+  fn from(error: io::Error) -> Self {
+    PipelineError::Io(error)
+  }
+}
+
+// Convert .npy errors into PipelineError, so that they can be propagated with ?
+#[cfg(feature = "npy")]
+impl From<NpyError> for PipelineError {
+  fn from(error: NpyError) -> Self {
+    PipelineError::Npy(error)
+  }
+}
+
+/// The declarative configuration of an analysis [`Pipeline`].
+///
+/// In TOML, a mel spectrogram of 64 bands at 16 kHz written as a `.npy` file is configured as
+///
+/// ```toml
+/// sample_rate = 16000
+///
+/// [framing]
+/// frame_length = 1024
+/// hop_size = { milliseconds = 16 }
+/// padding = "center_reflect"
+/// window = { kaiser = { beta = 8.6 } }
+///
+/// [analysis]
+/// type = "mel"
+/// band_count = 64
+///
+/// [output]
+/// format = "npy"
+/// ```
+///
+/// and the same fields are used in YAML, where windows with parameters are tagged, e.g.
+/// `window: !kaiser { beta: 8.6 }`. The frame length and the hop size are numbers of samples or
+/// durations, see [`SampleCount`]. Only the framing is required: the window defaults to Hann, the
+/// padding to none, the rounding of durations to the nearest sample, the rounding of the frame length
+/// to `exact`, the analysis to the STFT and the output to CSV.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+  /// The sample rate of the signals in Hz, required by the mel analysis and by durations.
+  #[serde(default)]
+  pub sample_rate: Option<u32>,
+  /// How the signals are cut into frames.
+  pub framing: FramingConfig,
+  /// The analysis of the frames.
+  #[serde(default)]
+  pub analysis: AnalysisConfig,
+  /// The format of the results.
+  #[serde(default)]
+  pub output: OutputConfig,
+}
+
+/// The framing stage of a [`PipelineConfig`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FramingConfig {
+  /// The number of samples of every frame, which is also the FFT length.
+  pub frame_length: SampleCount,
+  /// The number of samples between the starts of consecutive frames.
+  pub hop_size: SampleCount,
+  /// The padding of the edges of the signals, see [`PaddingMode`].
+  #[serde(default = "default_padding")]
+  pub padding: PaddingMode,
+  /// The window applied to every frame, see [`WindowSpec`].
+  #[serde(default = "default_window")]
+  pub window: WindowSpec,
+  /// The rounding of durations to numbers of samples, see [`DurationRounding`].
+  #[serde(default)]
+  pub duration_rounding: DurationRounding,
+  /// The rounding of the frame length to an efficient FFT length, see [`LengthRounding`]. The
+  /// adjusted length is reported by [`Pipeline::frame_length_adjustment`].
+  #[serde(default)]
+  pub rounding: LengthRounding,
+}
+
+/// A frame length or a hop size of a [`FramingConfig`], in samples or as a duration.
+///
+/// A plain number is a number of samples, and `{ milliseconds = 25 }` a duration, converted at the
+/// sample rate of the [`PipelineConfig`] with the `duration_rounding` of the framing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SampleCount {
+  /// A number of samples.
+  Samples(usize),
+  /// A duration in milliseconds.
+  Duration { milliseconds: f64 },
+}
+
+// Convert a number of samples into a SampleCount, so that configurations can be written with numbers
+impl From<usize> for SampleCount {
+  fn from(samples: usize) -> Self {
+    SampleCount::Samples(samples)
+  }
+}
+
+impl SampleCount {
+  /// The number of samples, converting durations at `sample_rate` Hz with `rounding`, see
+  /// [`duration_to_samples`].
+  ///
+  /// An error is returned for a duration without a sample rate, or a negative or non-finite duration.
+  pub fn resolve(self, sample_rate: Option<u32>, rounding: DurationRounding) -> Result<usize, PipelineError> {
+    match self {
+      SampleCount::Samples(samples) => Ok(samples),
+      SampleCount::Duration { milliseconds } => {
+        let sample_rate = sample_rate.ok_or_else(|| PipelineError::Config(String::from("durations require a sample rate")))?;
+        if !(milliseconds >= 0.0 && milliseconds.is_finite()) {
+          return Err(PipelineError::Config(format!("invalid duration of {} ms", milliseconds)));
+        }
+        // Whole nanoseconds keep durations like 25 ms exact
+        let duration = Duration::from_nanos((milliseconds * 1e6).round() as u64);
+        Ok(duration_to_samples(duration, sample_rate, rounding))
+      }
+    }
+  }
+}
+
+/// The analysis stage of a [`PipelineConfig`], selected by its `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AnalysisConfig {
+  /// The power spectrogram, `frame_length / 2 + 1` bins per frame, see [`spectrogram`].
+  #[default]
+  Stft,
+  /// The power of `band_count` mel bands per frame, see [`MelFilterbank`]. The bands span the whole
+  /// spectrum unless the frequencies are given.
+  Mel {
+    band_count: usize,
+    #[serde(default)]
+    min_frequency: f32,
+    #[serde(default)]
+    max_frequency: Option<f32>,
+  },
+  /// The power spectral density of Welch's method, a single row, see [`welch`]. The frames lie
+  /// entirely inside the signal, whatever the padding.
+  Welch,
+}
+
+/// The output stage of a [`PipelineConfig`], selected by its `format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OutputConfig {
+  /// One line of comma-separated values per row, with `precision` digits after the decimal point or
+  /// the shortest exact representation.
+  Csv {
+    #[serde(default)]
+    precision: Option<usize>,
+  },
+  /// A JSON array of rows, with non-finite values written as `null`.
+  Json {
+    #[serde(default)]
+    precision: Option<usize>,
+  },
+  /// A two-dimensional NumPy `.npy` array, one row per frame.
+  #[cfg(feature = "npy")]
+  Npy,
+}
+
+impl Default for OutputConfig {
+  fn default() -> Self {
+    OutputConfig::Csv { precision: None }
+  }
+}
+
+// The padding of configurations without one
+fn default_padding() -> PaddingMode {
+  PaddingMode::None
+}
+
+// The window of configurations without one
+fn default_window() -> WindowSpec {
+  WindowSpec::Hann
+}
+
+/// A framing, windowing, STFT, mel or Welch and export chain built from a declarative configuration.
+///
+/// The pipeline is validated and its window and filterbank are computed once, when it is built, so
+/// that batch jobs fail before processing their first file and every signal is processed the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+  config: PipelineConfig,
+  // The frame length changed by the rounding policy, if any
+  adjustment: Option<LengthAdjustment>,
+  hop_size: usize,
+  window: Vec<f32>,
+  filterbank: Option<MelFilterbank>,
+}
+
+impl Pipeline {
+  /// Build the pipeline described by `config`.
+  ///
+  /// Durations are converted into numbers of samples first, and the frame length is then rounded by
+  /// the rounding policy of the framing. An error is returned if a duration cannot be converted, if the
+  /// window length is invalid, if the hop size is 0, or if the mel analysis has no sample rate or
+  /// invalid bands.
+  pub fn new(config: PipelineConfig) -> Result<Self, PipelineError> {
+    let framing = &config.framing;
+    let requested_length = framing.frame_length.resolve(config.sample_rate, framing.duration_rounding)?;
+    let hop_size = framing.hop_size.resolve(config.sample_rate, framing.duration_rounding)?;
+    let adjustment = framing.rounding.adjust(requested_length);
+    let frame_length = adjustment.map_or(requested_length, |adjustment| adjustment.adjusted);
+    let window = framing.window.generate(frame_length)?;
+    if hop_size == 0 {
+      return Err(PipelineError::Window(HannWindowError::InvalidHopSize));
+    }
+    let filterbank = match config.analysis {
+      AnalysisConfig::Mel { band_count, min_frequency, max_frequency } => {
+        let sample_rate = config.sample_rate.ok_or_else(|| PipelineError::Config(String::from("the mel analysis requires a sample rate")))?;
+        let max_frequency = max_frequency.unwrap_or((sample_rate as f32) / 2.0);
+        Some(MelFilterbank::new(sample_rate, frame_length, band_count, min_frequency, max_frequency)?)
+      }
+      AnalysisConfig::Stft | AnalysisConfig::Welch => None,
+    };
+
+    Ok(Pipeline { config, adjustment, hop_size, window, filterbank })
+  }
+
+  /// Build the pipeline described by a TOML or a YAML configuration, see [`PipelineConfig`].
+  ///
+  /// The configuration is read as TOML with the `toml` feature and, if that fails, as YAML with the
+  /// `yaml` feature. An error is returned if it is invalid in every enabled format.
+  pub fn from_config(config: &str) -> Result<Self, PipelineError> {
+    let mut reasons = Vec::new();
+    #[cfg(feature = "toml")]
+    match toml::from_str::<PipelineConfig>(config) {
+      Ok(config) => return Pipeline::new(config),
+      Err(error) => reasons.push(format!("not TOML: {}", error.message())),
+    }
+    #[cfg(feature = "yaml")]
+    match serde_norway::from_str::<PipelineConfig>(config) {
+      Ok(config) => return Pipeline::new(config),
+      Err(error) => reasons.push(format!("not YAML: {}", error)),
+    }
+    Err(PipelineError::Config(reasons.join("; ")))
+  }
+
+  /// Build the pipeline described by a TOML configuration.
+  #[cfg(feature = "toml")]
+  pub fn from_toml(config: &str) -> Result<Self, PipelineError> {
+    Pipeline::new(toml::from_str(config).map_err(|error| PipelineError::Config(error.message().to_string()))?)
+  }
+
+  /// Build the pipeline described by a YAML configuration.
+  #[cfg(feature = "yaml")]
+  pub fn from_yaml(config: &str) -> Result<Self, PipelineError> {
+    Pipeline::new(serde_norway::from_str(config).map_err(|error| PipelineError::Config(error.to_string()))?)
+  }
+
+  /// The configuration of the pipeline, with the frame length and the hop size as configured.
+  pub fn config(&self) -> &PipelineConfig {
+    &self.config
+  }
+
+  /// The number of samples of every frame, after rounding.
+  pub fn frame_length(&self) -> usize {
+    self.window.len()
+  }
+
+  /// The number of samples between the starts of consecutive frames.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// The change of the frame length by the rounding policy, `None` if the configured length is used.
+  /// The requested length of a duration is its number of samples.
+  pub fn frame_length_adjustment(&self) -> Option<LengthAdjustment> {
+    self.adjustment
+  }
+
+  /// Frame, window and analyze a signal.
+  ///
+  /// The result has one row per frame, the power of every bin or mel band, or a single row of density
+  /// for the Welch analysis. An error is returned if the signal is shorter than a frame for the Welch
+  /// analysis.
+  pub fn process(&self, signal: &[f32]) -> Result<Vec<Vec<f32>>, PipelineError> {
+    let framing = &self.config.framing;
+    {
+      // The frames are cut lazily by the analysis, so the framing stage only counts them
+      trace_span!(DEBUG, "pipeline_framing", signal_length = signal.len());
+      let frame_count = get_frame_count(signal.len(), self.window.len(), self.hop_size, framing.padding);
+      trace_event!(DEBUG, frame_count, "pipeline frames");
+      #[cfg(not(feature = "tracing"))]
+      let _ = frame_count;
+    }
+
+    trace_span!(DEBUG, "pipeline_analysis");
+    let rows = match self.config.analysis {
+      AnalysisConfig::Stft => spectrogram(signal, &self.window, self.hop_size, framing.padding)?,
+      AnalysisConfig::Mel { .. } => {
+        let filterbank = self.filterbank.as_ref().expect("The mel analysis has a filterbank");
+        spectrogram(signal, &self.window, self.hop_size, framing.padding)?
+          .iter()
+          .map(|power| filterbank.apply(power))
+          .collect::<Result<_, _>>()?
+      }
+      AnalysisConfig::Welch => vec![welch(signal, &self.window, self.hop_size)?],
+    };
+
+    Ok(rows)
+  }
+
+  /// Write the rows of [`Pipeline::process`] to `writer` in the output format.
+  pub fn export<W: Write>(&self, rows: &[Vec<f32>], mut writer: W) -> Result<(), PipelineError> {
+    trace_span!(DEBUG, "pipeline_export", row_count = rows.len());
+    match self.config.output {
+      OutputConfig::Csv { precision } => {
+        for row in rows {
+          let values: Vec<String> = row.iter().map(|&value| format_value(value, precision)).collect();
+          writeln!(writer, "{}", values.join(","))?;
+        }
+      }
+      OutputConfig::Json { precision } => {
+        // JSON has no representation of NaN and infinities
+        let rows: Vec<String> = rows
+          .iter()
+          .map(|row| {
+            let values: Vec<String> = row
+              .iter()
+              .map(|&value| if value.is_finite() { format_value(value, precision) } else { String::from("null") })
+              .collect();
+            format!("[{}]", values.join(", "))
+          })
+          .collect();
+        writeln!(writer, "[{}]", rows.join(", "))?;
+      }
+      #[cfg(feature = "npy")]
+      OutputConfig::Npy => write_npy(&mut writer, &NpyArray::from_rows(rows)?)?,
+    }
+
+    writer.flush()?;
+    Ok(())
+  }
+
+  /// Process a signal and write the result to `writer`, see [`Pipeline::process`] and
+  /// [`Pipeline::export`].
+  pub fn run<W: Write>(&self, signal: &[f32], writer: W) -> Result<(), PipelineError> {
+    self.export(&self.process(signal)?, writer)
+  }
+}
+
+#[cfg(test)]
+mod test_pipeline {
+  use std::f32::consts::PI;
+
+  use super::*;
+
+  // A tone at an eighth of the sample rate
+  fn tone() -> Vec<f32> {
+    (0..4096).map(|n| (2.0 * PI * 0.125 * (n as f32)).cos()).collect()
+  }
+
+  #[cfg(feature = "toml")]
+  #[test]
+  fn test_pipeline_from_toml() {
+    let pipeline = Pipeline::from_config(
+      r#"
+        sample_rate = 16000
+
+        [framing]
+        frame_length = 512
+        hop_size = { milliseconds = 8 }
+        window = { kaiser = { beta = 8.6 } }
+
+        [analysis]
+        type = "mel"
+        band_count = 40
+      "#
+    )
+    .unwrap();
+
+    assert_eq!(pipeline.config().framing.window, WindowSpec::Kaiser { beta: 8.6 });
+    assert_eq!(pipeline.config().output, OutputConfig::Csv { precision: None });
+    assert_eq!(pipeline.config().framing.hop_size, SampleCount::Duration { milliseconds: 8.0 });
+    assert_eq!(pipeline.hop_size(), 128);
+    let rows = pipeline.process(&tone()).unwrap();
+    assert_eq!(rows.len(), (4096 - 512) / 128 + 1);
+    assert!(rows.iter().all(|row| row.len() == 40));
+  }
+
+  #[cfg(feature = "yaml")]
+  #[test]
+  fn test_pipeline_from_yaml() {
+    let pipeline = Pipeline::from_yaml(
+      "framing:\n  frame_length: 256\n  hop_size: 128\n  padding: center_zero\n  window: !kaiser { beta: 8.6 }\nanalysis:\n  type: welch\noutput:\n  format: json\n  precision: 2\n"
+    )
+    .unwrap();
+
+    // The density peaks at bin 32 of 256
+    assert_eq!(pipeline.config().framing.window, WindowSpec::Kaiser { beta: 8.6 });
+    let rows = pipeline.process(&tone()).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0, 32);
+    let mut output = Vec::new();
+    pipeline.export(&[vec![0.5, 1.0], vec![f32::NAN, 0.25]], &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "[[0.50, 1.00], [null, 0.25]]\n");
+  }
+
+  #[test]
+  fn test_pipeline_stft_csv() {
+    let config = PipelineConfig {
+      sample_rate: None,
+      framing: FramingConfig {
+        frame_length: SampleCount::Samples(8),
+        hop_size: SampleCount::Samples(8),
+        padding: PaddingMode::None,
+        window: WindowSpec::Rectangular,
+        duration_rounding: DurationRounding::Nearest,
+        rounding: LengthRounding::Exact,
+      },
+      analysis: AnalysisConfig::Stft,
+      output: OutputConfig::Csv { precision: Some(1) },
+    };
+    let mut output = Vec::new();
+
+    Pipeline::new(config).unwrap().run(&[1.0; 16], &mut output).unwrap();
+
+    // All the power of a constant signal is at 0 Hz
+    assert_eq!(String::from_utf8(output).unwrap(), "64.0,0.0,0.0,0.0,0.0\n64.0,0.0,0.0,0.0,0.0\n");
+  }
+
+  #[test]
+  fn test_pipeline_rounding() {
+    // 10 ms at 44.1 kHz
+    let framing = FramingConfig {
+      frame_length: SampleCount::Samples(441),
+      hop_size: SampleCount::Samples(441),
+      padding: PaddingMode::None,
+      window: WindowSpec::Hann,
+      duration_rounding: DurationRounding::Nearest,
+      rounding: LengthRounding::NextFastLength,
+    };
+    let config = PipelineConfig { sample_rate: None, framing, analysis: AnalysisConfig::Stft, output: OutputConfig::default() };
+
+    let pipeline = Pipeline::new(config.clone()).unwrap();
+    let exact = Pipeline::new(PipelineConfig { framing: FramingConfig { rounding: LengthRounding::Exact, ..config.framing.clone() }, ..config }).unwrap();
+
+    assert_eq!(pipeline.frame_length(), 450);
+    assert_eq!(pipeline.frame_length_adjustment(), Some(LengthAdjustment { requested: 441, adjusted: 450 }));
+    assert_eq!(pipeline.config().framing.frame_length, SampleCount::Samples(441));
+    assert!(pipeline.process(&tone()).unwrap().iter().all(|row| row.len() == 226));
+    assert_eq!(exact.frame_length(), 441);
+    assert_eq!(exact.frame_length_adjustment(), None);
+  }
+
+  #[test]
+  fn test_sample_count_resolve() {
+    let duration = SampleCount::Duration { milliseconds: 25.0 };
+
+    assert_eq!(SampleCount::from(512).resolve(None, DurationRounding::Nearest).unwrap(), 512);
+    assert_eq!(duration.resolve(Some(48_000), DurationRounding::Nearest).unwrap(), 1200);
+    assert_eq!(duration.resolve(Some(22_050), DurationRounding::Up).unwrap(), 552);
+    assert!(matches!(duration.resolve(None, DurationRounding::Nearest), Err(PipelineError::Config(_))));
+    assert!(matches!(SampleCount::Duration { milliseconds: -1.0 }.resolve(Some(48_000), DurationRounding::Nearest), Err(PipelineError::Config(_))));
+  }
+
+  #[test]
+  fn test_pipeline_invalid_config() {
+    let framing = FramingConfig {
+      frame_length: SampleCount::Samples(256),
+      hop_size: SampleCount::Samples(64),
+      padding: PaddingMode::None,
+      window: WindowSpec::Hann,
+      duration_rounding: DurationRounding::Nearest,
+      rounding: LengthRounding::Exact,
+    };
+    let mel = AnalysisConfig::Mel { band_count: 40, min_frequency: 0.0, max_frequency: None };
+
+    let no_sample_rate = Pipeline::new(PipelineConfig { sample_rate: None, framing: framing.clone(), analysis: mel, output: OutputConfig::default() });
+    let no_hop = Pipeline::new(PipelineConfig {
+      sample_rate: None,
+      framing: FramingConfig { hop_size: SampleCount::Samples(0), ..framing },
+      analysis: AnalysisConfig::Stft,
+      output: OutputConfig::default(),
+    });
+
+    assert!(matches!(no_sample_rate, Err(PipelineError::Config(_))));
+    assert!(matches!(no_hop, Err(PipelineError::Window(HannWindowError::InvalidHopSize))));
+    assert!(matches!(Pipeline::from_config("framing = 3"), Err(PipelineError::Config(_))));
+  }
+}
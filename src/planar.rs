@@ -0,0 +1,258 @@
+use crate::{ apply_window::multiply_by_window, hann_window::HannWindowError, instrument::trace_allocation };
+
+// Check that `interleaved` holds whole sample frames of `channel_count` channels, and return their number
+fn sample_frame_count(interleaved: &[f32], channel_count: usize) -> Result<usize, HannWindowError> {
+  // There must be at least one channel
+  if channel_count == 0 {
+    return Err(HannWindowError::InvalidChannelCount);
+  }
+  // The samples must form whole sample frames
+  if !interleaved.len().is_multiple_of(channel_count) {
+    return Err(HannWindowError::LengthMismatch);
+  }
+  Ok(interleaved.len() / channel_count)
+}
+
+/// Split interleaved multichannel samples into planar channels.
+///
+/// This function takes a slice `interleaved` holding whole sample frames of `channel_count` channels
+/// and writes the samples of channel `c` to `planar[c * n..(c + 1) * n]`, where `n` is the number of
+/// sample frames. An error is returned if `channel_count` is 0, if the input does not hold whole
+/// sample frames, or if `planar` does not have the length of the input.
+pub fn deinterleave(interleaved: &[f32], channel_count: usize, planar: &mut [f32]) -> Result<(), HannWindowError> {
+  let length = sample_frame_count(interleaved, channel_count)?;
+  // Every input sample must have its place in the output
+  if planar.len() != interleaved.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  for (channel, output) in planar.chunks_exact_mut(length.max(1)).enumerate() {
+    for (sample, input) in output.iter_mut().zip(interleaved.iter().skip(channel).step_by(channel_count)) {
+      *sample = *input;
+    }
+  }
+  Ok(())
+}
+
+/// Merge planar channels into interleaved multichannel samples, the inverse of [`deinterleave`].
+///
+/// An error is returned if `channel_count` is 0, if `planar` does not hold channels of equal lengths,
+/// or if `interleaved` does not have the length of the input.
+pub fn interleave(planar: &[f32], channel_count: usize, interleaved: &mut [f32]) -> Result<(), HannWindowError> {
+  let length = sample_frame_count(planar, channel_count)?;
+  // Every input sample must have its place in the output
+  if interleaved.len() != planar.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  for (channel, input) in planar.chunks_exact(length.max(1)).enumerate() {
+    for (sample, output) in input.iter().zip(interleaved.iter_mut().skip(channel).step_by(channel_count)) {
+      *output = *sample;
+    }
+  }
+  Ok(())
+}
+
+/// A reusable planar buffer for processing interleaved multichannel blocks channel by channel.
+///
+/// The buffer is allocated once for a number of channels and a maximum number of sample frames per
+/// block. Every interleaved block is split into it, every channel is processed as one contiguous
+/// slice, and the result is merged back into the interleaved block, without allocating. This is the
+/// glue between interleaved audio I/O and the single-channel functions of the crate, like
+/// [`crate::windows::apply_window`].
+#[derive(Debug, Clone)]
+pub struct PlanarBuffer {
+  channel_count: usize,
+  capacity: usize,
+  // The channels, each in its own span of `capacity` samples, of which the first `length` are used
+  samples: Vec<f32>,
+  length: usize,
+}
+
+impl PlanarBuffer {
+  /// Create a planar buffer for blocks of up to `capacity` sample frames of `channel_count` channels.
+  ///
+  /// An error is returned if the channel count or the capacity is 0.
+  pub fn new(channel_count: usize, capacity: usize) -> Result<Self, HannWindowError> {
+    if channel_count == 0 {
+      return Err(HannWindowError::InvalidChannelCount);
+    }
+    if capacity == 0 {
+      return Err(HannWindowError::InvalidCapacity);
+    }
+    let sample_count = channel_count.checked_mul(capacity).ok_or(HannWindowError::InvalidCapacity)?;
+    trace_allocation("PlanarBuffer", sample_count * std::mem::size_of::<f32>());
+
+    Ok(PlanarBuffer { channel_count, capacity, samples: vec![0.0; sample_count], length: 0 })
+  }
+
+  /// The number of channels.
+  pub fn channel_count(&self) -> usize {
+    self.channel_count
+  }
+
+  /// The largest number of sample frames of a block.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// The number of sample frames of the last block, the length of every channel.
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  /// Whether the buffer holds no samples.
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  /// The samples of the given channel.
+  pub fn channel(&self, channel: usize) -> Option<&[f32]> {
+    (channel < self.channel_count).then(|| &self.samples[channel * self.capacity..channel * self.capacity + self.length])
+  }
+
+  /// The samples of the given channel, to process them in place.
+  pub fn channel_mut(&mut self, channel: usize) -> Option<&mut [f32]> {
+    (channel < self.channel_count).then(|| &mut self.samples[channel * self.capacity..channel * self.capacity + self.length])
+  }
+
+  /// The samples of every channel, in channel order.
+  pub fn channels_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+    let length = self.length;
+    self.samples.chunks_exact_mut(self.capacity).map(move |channel| &mut channel[..length])
+  }
+
+  /// Split an interleaved block into the channels of the buffer, replacing the previous block.
+  ///
+  /// An error is returned if the block does not hold whole sample frames or holds more than
+  /// `capacity` of them, and the buffer is left untouched.
+  pub fn deinterleave(&mut self, interleaved: &[f32]) -> Result<(), HannWindowError> {
+    let length = sample_frame_count(interleaved, self.channel_count)?;
+    // The block must fit into the preallocated channels
+    if length > self.capacity {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    self.length = length;
+    for (channel, output) in self.samples.chunks_exact_mut(self.capacity).enumerate() {
+      for (sample, input) in output.iter_mut().zip(interleaved.iter().skip(channel).step_by(self.channel_count)) {
+        *sample = *input;
+      }
+    }
+    Ok(())
+  }
+
+  /// Merge the channels of the buffer into an interleaved block.
+  ///
+  /// An error is returned if the block does not hold `len` sample frames.
+  pub fn interleave(&self, interleaved: &mut [f32]) -> Result<(), HannWindowError> {
+    // Every sample of the buffer must have its place in the block
+    if interleaved.len() != self.length * self.channel_count {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    for (channel, input) in self.samples.chunks_exact(self.capacity).enumerate() {
+      for (sample, output) in input[..self.length].iter().zip(interleaved.iter_mut().skip(channel).step_by(self.channel_count)) {
+        *output = *sample;
+      }
+    }
+    Ok(())
+  }
+
+  /// Process an interleaved block in place, calling `process_channel` with the index and the samples
+  /// of every channel.
+  ///
+  /// The block is split into the buffer, every channel is processed, and the result is merged back
+  /// into the block. An error is returned if the block does not hold whole sample frames or holds
+  /// more than `capacity` of them, and the block is left untouched.
+  pub fn process<F: FnMut(usize, &mut [f32])>(
+    &mut self,
+    interleaved: &mut [f32],
+    mut process_channel: F
+  ) -> Result<(), HannWindowError> {
+    self.deinterleave(interleaved)?;
+    for (channel, samples) in self.channels_mut().enumerate() {
+      process_channel(channel, samples);
+    }
+    self.interleave(interleaved)
+  }
+
+  /// Multiply every channel of an interleaved block by `window` in place.
+  ///
+  /// An error is returned if the block does not hold whole sample frames, or holds more than
+  /// `capacity` of them or a number of them different from the window length, and the block is left
+  /// untouched.
+  pub fn apply_window(&mut self, interleaved: &mut [f32], window: &[f32]) -> Result<(), HannWindowError> {
+    // The window must cover every channel sample for sample
+    if sample_frame_count(interleaved, self.channel_count)? != window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.process(interleaved, |_, samples| multiply_by_window(samples, window))
+  }
+}
+
+#[cfg(test)]
+mod test_planar {
+  use crate::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_deinterleave_and_interleave() {
+    let interleaved = [1.0, -1.0, 10.0, 2.0, -2.0, 20.0, 3.0, -3.0, 30.0];
+    let mut planar = [0.0; 9];
+    let mut merged = [0.0; 9];
+
+    deinterleave(&interleaved, 3, &mut planar).unwrap();
+    interleave(&planar, 3, &mut merged).unwrap();
+
+    assert_eq!(planar, [1.0, 2.0, 3.0, -1.0, -2.0, -3.0, 10.0, 20.0, 30.0]);
+    assert_eq!(merged, interleaved);
+    assert_eq!(deinterleave(&[0.0; 4], 0, &mut planar).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(deinterleave(&[0.0; 8], 3, &mut planar).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(interleave(&planar, 3, &mut [0.0; 6]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+
+  #[test]
+  fn test_planar_buffer_process() {
+    let mut buffer = PlanarBuffer::new(2, 8).unwrap();
+    let mut block = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+    // Blocks of any length up to the capacity reuse the same buffer
+    buffer.process(&mut block, |channel, samples| samples.iter_mut().for_each(|sample| *sample *= (channel + 1) as f32 * 10.0)).unwrap();
+    assert_eq!(block, [10.0, 40.0, 30.0, 80.0, 50.0, 120.0]);
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.channel(1), Some(&[40.0, 80.0, 120.0][..]));
+    assert_eq!(buffer.channel(2), None);
+
+    buffer.deinterleave(&[7.0, 8.0]).unwrap();
+    assert_eq!(buffer.channel(0), Some(&[7.0][..]));
+  }
+
+  #[test]
+  fn test_planar_buffer_apply_window() {
+    let window = get_hann_window(16).unwrap();
+    let mut buffer = PlanarBuffer::new(3, 16).unwrap();
+    let mut block: Vec<f32> = (0..48).map(|i| 1.0 + (i % 3) as f32).collect();
+
+    buffer.apply_window(&mut block, &window).unwrap();
+
+    // Every channel is windowed on its own
+    for (n, sample_frame) in block.chunks_exact(3).enumerate() {
+      assert_eq!(sample_frame, [window[n], 2.0 * window[n], 3.0 * window[n]]);
+    }
+  }
+
+  #[test]
+  fn test_planar_buffer_invalid_blocks() {
+    let mut buffer = PlanarBuffer::new(2, 4).unwrap();
+    let mut block = [1.0; 10];
+
+    assert_eq!(PlanarBuffer::new(0, 4).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(PlanarBuffer::new(2, 0).unwrap_err(), HannWindowError::InvalidCapacity);
+    assert_eq!(buffer.process(&mut block, |_, _| {}).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(buffer.process(&mut block[..7], |_, _| {}).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(buffer.apply_window(&mut block[..6], &[0.5; 4]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(block, [1.0; 10]);
+  }
+}
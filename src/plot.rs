@@ -0,0 +1,513 @@
+use std::{ error::Error, fmt, path::Path };
+
+use plotters::{ coord::Shift, drawing::DrawingAreaErrorKind, prelude::* };
+
+use crate::{ hann_window::HannWindowError, spectral::frequency_response };
+
+// The size of the images written by the plot functions, in pixels
+const PLOT_SIZE: (u32, u32) = (1024, 512);
+// The floor of the magnitude axis, in decibels
+const RESPONSE_FLOOR_DB: f32 = -150.0;
+// The power assigned to silent bins, so that their level is finite
+const POWER_FLOOR: f32 = 1e-20;
+
+// The colors of the viridis and magma colormaps of matplotlib at 9 evenly spaced levels
+const VIRIDIS: [(u8, u8, u8); 9] = [
+  (68, 1, 84),
+  (71, 44, 122),
+  (59, 81, 139),
+  (44, 113, 142),
+  (33, 144, 141),
+  (39, 173, 129),
+  (92, 200, 99),
+  (170, 220, 50),
+  (253, 231, 37),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+  (0, 0, 4),
+  (28, 16, 68),
+  (79, 18, 123),
+  (129, 37, 129),
+  (181, 54, 122),
+  (229, 80, 100),
+  (251, 135, 97),
+  (254, 194, 135),
+  (252, 253, 191),
+];
+
+/// Error type for the plotting functions.
+#[derive(Debug)]
+pub enum PlotError {
+  Window(HannWindowError),
+  Drawing(String),
+}
+
+// Implement the Error trait for the PlotError enum
+impl Error for PlotError {}
+
+// Implement the Display trait for the PlotError enum
+impl fmt::Display for PlotError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      PlotError::Window(error) => {
+        write!(f, "PlotError: {}", error)
+      }
+      PlotError::Drawing(error) => {
+        write!(f, "PlotError: Failed to draw the chart: {}", error)
+      }
+    }
+  }
+}
+
+impl From<HannWindowError> for PlotError {
+  fn from(error: HannWindowError) -> Self {
+    PlotError::Window(error)
+  }
+}
+
+impl<E: Error + Send + Sync> From<DrawingAreaErrorKind<E>> for PlotError {
+  fn from(error: DrawingAreaErrorKind<E>) -> Self {
+    PlotError::Drawing(error.to_string())
+  }
+}
+
+/// Draw the shapes of labeled windows on a drawing area.
+///
+/// The windows are drawn against the sample index, one color per window, with a legend of their
+/// labels. The area can be any `plotters` drawing area, e.g. a part of a larger report; it is not
+/// presented, so that other charts can be drawn on the same backend.
+pub fn draw_window_chart<DB: DrawingBackend>(area: &DrawingArea<DB, Shift>, windows: &[(&str, &[f32])]) -> Result<(), PlotError> {
+  let length = windows.iter().map(|(_, window)| window.len()).max().unwrap_or(2).max(2);
+  let max = windows.iter().flat_map(|(_, window)| window.iter().copied()).fold(1.0f32, f32::max);
+
+  let mut chart = ChartBuilder::on(area)
+    .caption("Window", ("sans-serif", 22))
+    .margin(12)
+    .x_label_area_size(36)
+    .y_label_area_size(48)
+    .build_cartesian_2d(0.0f32..((length - 1) as f32), 0.0f32..(1.05 * max))?;
+  chart.configure_mesh().x_desc("Sample").y_desc("Amplitude").draw()?;
+  for (index, (label, window)) in windows.iter().enumerate() {
+    let color = Palette99::pick(index).to_rgba();
+    chart
+      .draw_series(LineSeries::new(window.iter().enumerate().map(|(n, &value)| (n as f32, value)), color.stroke_width(2)))?
+      .label(*label)
+      .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+  }
+  chart.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).draw()?;
+
+  Ok(())
+}
+
+/// Draw the magnitude responses of labeled windows on a drawing area.
+///
+/// The responses are computed by [`frequency_response`] with windows zero-padded to `fft_length`
+/// samples, and drawn in decibels against the frequency in cycles per sample, down to -150 dB. The area
+/// is not presented. An error is returned if the FFT length is less than or equal to 1 or shorter than
+/// a window.
+pub fn draw_response_chart<DB: DrawingBackend>(
+  area: &DrawingArea<DB, Shift>,
+  windows: &[(&str, &[f32])],
+  fft_length: usize
+) -> Result<(), PlotError> {
+  let mut chart = ChartBuilder::on(area)
+    .caption("Magnitude response", ("sans-serif", 22))
+    .margin(12)
+    .x_label_area_size(36)
+    .y_label_area_size(48)
+    .build_cartesian_2d(0.0f32..0.5f32, RESPONSE_FLOOR_DB..5.0f32)?;
+  chart.configure_mesh().x_desc("Frequency (cycles per sample)").y_desc("Magnitude (dB)").draw()?;
+  for (index, (label, window)) in windows.iter().enumerate() {
+    let color = Palette99::pick(index).to_rgba();
+    let response = frequency_response(window, fft_length)?;
+    let points = response.into_iter().enumerate().map(|(k, db)| ((k as f32) / (fft_length as f32), db.max(RESPONSE_FLOOR_DB)));
+    chart
+      .draw_series(LineSeries::new(points, color.stroke_width(1)))?
+      .label(*label)
+      .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(1)));
+  }
+  chart.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).draw()?;
+
+  Ok(())
+}
+
+/// The colormaps of spectrogram images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Colormap {
+  /// The perceptually uniform viridis colormap, from dark blue through green to yellow.
+  Viridis,
+  /// The perceptually uniform magma colormap, from black through purple and orange to light yellow.
+  Magma,
+  /// From black to white.
+  Grayscale,
+  /// From black through copper to light orange, as in matplotlib.
+  Copper,
+}
+
+impl Colormap {
+  /// The color of a `level` from 0 to 1, clamped to that range.
+  pub fn color(self, level: f32) -> RGBColor {
+    let level = if level.is_nan() { 0.0 } else { level.clamp(0.0, 1.0) };
+    let colors = match self {
+      Colormap::Viridis => &VIRIDIS,
+      Colormap::Magma => &MAGMA,
+      Colormap::Grayscale => {
+        let gray = (255.0 * level).round() as u8;
+        return RGBColor(gray, gray, gray);
+      }
+      // The linear ramps of matplotlib, the red one saturating at 80% of the range
+      Colormap::Copper => {
+        let channel = |slope: f32| (255.0 * (slope * level).min(1.0)).round() as u8;
+        return RGBColor(channel(1.25), channel(0.7812), channel(0.4975));
+      }
+    };
+
+    // Interpolate linearly between the two nearest colors of the table
+    let position = level * ((colors.len() - 1) as f32);
+    let index = (position as usize).min(colors.len() - 2);
+    let fraction = position - (index as f32);
+    let channel = |lower: u8, upper: u8| ((lower as f32) + fraction * ((upper as f32) - (lower as f32))).round() as u8;
+    let (lower, upper) = (colors[index], colors[index + 1]);
+    RGBColor(channel(lower.0, upper.0), channel(lower.1, upper.1), channel(lower.2, upper.2))
+  }
+}
+
+/// A renderer of power spectrograms to images.
+///
+/// The rows are power spectra, e.g. of [`spectrogram`](crate::stft::spectrogram), one per frame and `hop_size`
+/// samples apart, converted to decibels as `10 * log10(power)`. The levels are clamped to a range,
+/// by default the 80 dB below the loudest bin, and colored by a [`Colormap`]. Row `i` spans the time
+/// from `i * hop_size / sample_rate` seconds, and bin `k` of a row of `n` bins is the frequency
+/// `k * sample_rate / (2 * (n - 1))` Hz unless the frequencies of the rows are given, e.g. the center
+/// frequencies of a `MelFilterbank`. The frequency axis can be logarithmic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpectrogramPlot {
+  sample_rate: u32,
+  hop_size: usize,
+  colormap: Colormap,
+  dynamic_range: f32,
+  db_range: Option<(f32, f32)>,
+  min_log_frequency: Option<f32>,
+  frequencies: Option<Vec<f32>>,
+  title: Option<String>,
+  size: (u32, u32),
+}
+
+impl SpectrogramPlot {
+  /// Create a renderer of spectrograms of frames `hop_size` samples apart at `sample_rate` Hz, with the
+  /// viridis colormap, 80 dB of dynamic range, a linear frequency axis and images of 1024 by 512 pixels.
+  pub fn new(sample_rate: u32, hop_size: usize) -> Self {
+    SpectrogramPlot {
+      sample_rate,
+      hop_size,
+      colormap: Colormap::Viridis,
+      dynamic_range: 80.0,
+      db_range: None,
+      min_log_frequency: None,
+      frequencies: None,
+      title: None,
+      size: PLOT_SIZE,
+    }
+  }
+
+  /// Color the levels with `colormap`.
+  pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+    self.colormap = colormap;
+    self
+  }
+
+  /// Show the `dynamic_range` decibels below the loudest bin.
+  pub fn with_dynamic_range(mut self, dynamic_range: f32) -> Self {
+    self.dynamic_range = dynamic_range;
+    self.db_range = None;
+    self
+  }
+
+  /// Show the levels from `min_db` to `max_db` decibels, whatever the loudest bin, e.g. to compare
+  /// spectrograms with the same scale.
+  pub fn with_db_range(mut self, min_db: f32, max_db: f32) -> Self {
+    self.db_range = Some((min_db, max_db));
+    self
+  }
+
+  /// Use a logarithmic frequency axis from `min_frequency` Hz to the Nyquist frequency.
+  pub fn with_log_frequency(mut self, min_frequency: f32) -> Self {
+    self.min_log_frequency = Some(min_frequency);
+    self
+  }
+
+  /// Place bin `k` of every row at `frequencies[k]` Hz instead of evenly from 0 Hz to the Nyquist
+  /// frequency.
+  pub fn with_frequencies(mut self, frequencies: Vec<f32>) -> Self {
+    self.frequencies = Some(frequencies);
+    self
+  }
+
+  /// Caption the chart with `title`.
+  pub fn with_title(mut self, title: &str) -> Self {
+    self.title = Some(title.to_string());
+    self
+  }
+
+  /// Write images of `width` by `height` pixels.
+  pub fn with_size(mut self, width: u32, height: u32) -> Self {
+    self.size = (width, height);
+    self
+  }
+
+  /// The range of levels shown for a spectrogram, in decibels.
+  ///
+  /// Levels below the lower bound are drawn with the first color of the colormap and levels above the
+  /// upper bound with the last one.
+  pub fn db_range(&self, power: &[Vec<f32>]) -> (f32, f32) {
+    self.db_range.unwrap_or_else(|| {
+      let peak = power_to_db(power.iter().flatten().copied().fold(POWER_FLOOR, f32::max));
+      (peak - self.dynamic_range, peak)
+    })
+  }
+
+  /// Draw the spectrogram `power` on a drawing area, time running left to right and frequency bottom
+  /// to top.
+  ///
+  /// The area is not presented. An error is returned if the sample rate or the hop size is 0, if the
+  /// rows differ in length from each other or from the frequencies, or if the minimum frequency of a
+  /// logarithmic axis is not between 0 and the Nyquist frequency.
+  pub fn draw<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>, power: &[Vec<f32>]) -> Result<(), PlotError> {
+    if self.sample_rate == 0 {
+      return Err(PlotError::Window(HannWindowError::InvalidSampleRate));
+    }
+    if self.hop_size == 0 {
+      return Err(PlotError::Window(HannWindowError::InvalidHopSize));
+    }
+    let nyquist = (self.sample_rate as f32) / 2.0;
+    if self.min_log_frequency.is_some_and(|frequency| !(0.0 < frequency && frequency < nyquist)) {
+      return Err(PlotError::Window(HannWindowError::InvalidCutoff));
+    }
+    let edges = self.frequency_edges(power.first().map_or(0, Vec::len), nyquist)?;
+    if power.iter().any(|row| row.len() + 1 != edges.len().max(1)) {
+      return Err(PlotError::Window(HannWindowError::LengthMismatch));
+    }
+
+    // A logarithmic axis is drawn as a linear axis of the decimal logarithm of the frequency
+    let axis = |frequency: f32| match self.min_log_frequency {
+      Some(min_frequency) => frequency.max(min_frequency).log10(),
+      None => frequency,
+    };
+    let frame_duration = (self.hop_size as f32) / (self.sample_rate as f32);
+    let duration = (power.len().max(1) as f32) * frame_duration;
+    let mut builder = ChartBuilder::on(area);
+    builder.margin(12).x_label_area_size(36).y_label_area_size(60);
+    if let Some(title) = &self.title {
+      builder.caption(title, ("sans-serif", 22));
+    }
+    let mut chart = builder.build_cartesian_2d(0.0f32..duration, axis(self.min_log_frequency.unwrap_or(0.0))..axis(nyquist))?;
+    let label = |value: &f32| match self.min_log_frequency {
+      Some(_) => format!("{:.0}", 10.0f32.powf(*value)),
+      None => format!("{:.0}", value),
+    };
+    chart.configure_mesh().disable_mesh().x_desc("Time (s)").y_desc("Frequency (Hz)").y_label_formatter(&label).draw()?;
+
+    // Every cell spans one hop in time and the frequencies closest to its bin
+    let (min_db, max_db) = self.db_range(power);
+    let cells = power.iter().enumerate().flat_map(|(frame, row)| {
+      let x = (frame as f32) * frame_duration;
+      row.iter().zip(edges.windows(2)).filter(|(_, edges)| axis(edges[1]) > axis(edges[0])).map(move |(&power, edges)| {
+        let level = (power_to_db(power) - min_db) / (max_db - min_db);
+        Rectangle::new([(x, axis(edges[0])), (x + frame_duration, axis(edges[1]))], self.colormap.color(level).filled())
+      })
+    });
+    chart.draw_series(cells)?;
+
+    Ok(())
+  }
+
+  /// Render the spectrogram `power` to an image file, an SVG file if the extension of `path` is `svg`
+  /// and a PNG file otherwise.
+  pub fn plot<P: AsRef<Path>>(&self, power: &[Vec<f32>], path: P) -> Result<(), PlotError> {
+    plot_to_file(path.as_ref(), self.size, Chart::Spectrogram(self, power))
+  }
+
+  // The frequencies between the bins, from the lower edge of the first to the upper edge of the last
+  fn frequency_edges(&self, bin_count: usize, nyquist: f32) -> Result<Vec<f32>, PlotError> {
+    let centers = match &self.frequencies {
+      Some(frequencies) if frequencies.len() != bin_count => {
+        return Err(PlotError::Window(HannWindowError::LengthMismatch));
+      }
+      Some(frequencies) => frequencies.clone(),
+      None => (0..bin_count).map(|k| (k as f32) * nyquist / ((bin_count.max(2) - 1) as f32)).collect(),
+    };
+    if centers.len() < 2 {
+      return Ok(centers.first().map_or(Vec::new(), |_| vec![0.0, nyquist]));
+    }
+
+    // The edges are halfway between the centers, and as far beyond the outer centers
+    let last = centers.len() - 1;
+    let mut edges = Vec::with_capacity(centers.len() + 1);
+    edges.push((centers[0] - (centers[1] - centers[0]) / 2.0).max(0.0));
+    edges.extend(centers.windows(2).map(|pair| (pair[0] + pair[1]) / 2.0));
+    edges.push((centers[last] + (centers[last] - centers[last - 1]) / 2.0).min(nyquist));
+    Ok(edges)
+  }
+}
+
+// Convert a power to decibels, clamping silence to a finite level
+fn power_to_db(power: f32) -> f32 {
+  10.0 * power.max(POWER_FLOOR).log10()
+}
+
+/// Plot the shape of a window to an image file.
+///
+/// The chart of [`draw_window_chart`] is written to `path`, an SVG file if its extension is `svg` and a
+/// PNG file otherwise, of 1024 by 512 pixels.
+pub fn plot_window<P: AsRef<Path>>(window: &[f32], path: P) -> Result<(), PlotError> {
+  plot_to_file(path.as_ref(), PLOT_SIZE, Chart::Window(window))
+}
+
+/// Plot the magnitude response of a window to an image file.
+///
+/// The chart of [`draw_response_chart`], with an FFT of 16 times the window length, is written to
+/// `path`, an SVG file if its extension is `svg` and a PNG file otherwise, of 1024 by 512 pixels. An
+/// error is returned if the window length is less than or equal to 1.
+pub fn plot_response<P: AsRef<Path>>(window: &[f32], path: P) -> Result<(), PlotError> {
+  if window.len() <= 1 {
+    return Err(PlotError::Window(HannWindowError::WindowLengthTooSmall));
+  }
+  plot_to_file(path.as_ref(), PLOT_SIZE, Chart::Response(window))
+}
+
+// The charts of the plot functions
+#[derive(Clone, Copy)]
+enum Chart<'a> {
+  Window(&'a [f32]),
+  Response(&'a [f32]),
+  Spectrogram(&'a SpectrogramPlot, &'a [Vec<f32>]),
+}
+
+// Draw a chart on a white image file with the backend matching its extension
+fn plot_to_file(path: &Path, size: (u32, u32), chart: Chart) -> Result<(), PlotError> {
+  if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) {
+    draw_chart(SVGBackend::new(path, size).into_drawing_area(), chart)
+  } else {
+    draw_chart(BitMapBackend::new(path, size).into_drawing_area(), chart)
+  }
+}
+
+// Draw a chart on the whole area and present it
+fn draw_chart<DB: DrawingBackend>(area: DrawingArea<DB, Shift>, chart: Chart) -> Result<(), PlotError> {
+  area.fill(&WHITE)?;
+  match chart {
+    Chart::Window(window) => draw_window_chart(&area, &[("window", window)])?,
+    Chart::Response(window) => draw_response_chart(&area, &[("window", window)], 16 * window.len())?,
+    Chart::Spectrogram(plot, power) => plot.draw(&area, power)?,
+  }
+  area.present()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_plot {
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_draw_window_chart() {
+    let hann = get_hann_window(32).unwrap();
+    let mut svg = String::new();
+
+    {
+      let area = SVGBackend::with_string(&mut svg, (640, 480)).into_drawing_area();
+      draw_window_chart(&area, &[("hann", &hann)]).unwrap();
+      area.present().unwrap();
+    }
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("Amplitude"));
+    assert!(svg.contains("hann"));
+  }
+
+  #[test]
+  fn test_plot_files() {
+    let hann = get_hann_window(64).unwrap();
+    let directory = std::env::temp_dir();
+    let svg = directory.join("hann_rs_test_plot_response.svg");
+    let png = directory.join("hann_rs_test_plot_window.png");
+
+    plot_response(&hann, &svg).unwrap();
+    plot_window(&hann, &png).unwrap();
+
+    assert!(std::fs::read_to_string(&svg).unwrap().contains("Magnitude response"));
+    assert_eq!(&std::fs::read(&png).unwrap()[1..4], b"PNG");
+    std::fs::remove_file(svg).unwrap();
+    std::fs::remove_file(png).unwrap();
+  }
+
+  #[test]
+  fn test_draw_response_chart_too_short() {
+    let mut svg = String::new();
+    let area = SVGBackend::with_string(&mut svg, (640, 480)).into_drawing_area();
+
+    let result = draw_response_chart(&area, &[("hann", &get_hann_window(64).unwrap())], 32);
+
+    assert!(matches!(result, Err(PlotError::Window(HannWindowError::LengthMismatch))));
+  }
+
+  #[test]
+  fn test_colormaps() {
+    // The ends of the colormaps, the levels between them and the levels outside of [0, 1]
+    assert_eq!(Colormap::Viridis.color(0.0), RGBColor(68, 1, 84));
+    assert_eq!(Colormap::Viridis.color(1.0), RGBColor(253, 231, 37));
+    assert_eq!(Colormap::Magma.color(0.5), RGBColor(181, 54, 122));
+    assert_eq!(Colormap::Magma.color(-3.0), RGBColor(0, 0, 4));
+    assert_eq!(Colormap::Grayscale.color(0.5), RGBColor(128, 128, 128));
+    assert_eq!(Colormap::Grayscale.color(f32::NAN), RGBColor(0, 0, 0));
+    assert_eq!(Colormap::Copper.color(0.0), RGBColor(0, 0, 0));
+    assert_eq!(Colormap::Copper.color(1.0), RGBColor(255, 199, 127));
+  }
+
+  #[test]
+  fn test_spectrogram_plot_db_range() {
+    let power = vec![vec![1.0, 0.01, 0.0], vec![0.1, 0.0, 100.0]];
+
+    // 80 dB below the loudest bin by default, or a fixed range
+    assert_eq!(SpectrogramPlot::new(8000, 4).db_range(&power), (-60.0, 20.0));
+    assert_eq!(SpectrogramPlot::new(8000, 4).with_dynamic_range(40.0).db_range(&power), (-20.0, 20.0));
+    assert_eq!(SpectrogramPlot::new(8000, 4).with_db_range(-90.0, 0.0).db_range(&power), (-90.0, 0.0));
+  }
+
+  #[test]
+  fn test_spectrogram_plot_draw() {
+    let power = vec![vec![1.0, 0.5, 0.25, 0.125, 0.0]; 4];
+    let plot = SpectrogramPlot::new(8000, 64).with_colormap(Colormap::Magma).with_log_frequency(50.0).with_title("Tone");
+    let mut svg = String::new();
+
+    {
+      let area = SVGBackend::with_string(&mut svg, (640, 320)).into_drawing_area();
+      plot.draw(&area, &power).unwrap();
+      area.present().unwrap();
+    }
+
+    assert!(svg.contains("Tone"));
+    assert!(svg.contains("Frequency (Hz)"));
+    // The loudest bins are drawn with the last color of magma
+    assert!(svg.contains("#FCFDBF"));
+  }
+
+  #[test]
+  fn test_spectrogram_plot_invalid() {
+    let mut svg = String::new();
+    let area = SVGBackend::with_string(&mut svg, (640, 320)).into_drawing_area();
+    let power = vec![vec![1.0; 5], vec![1.0; 4]];
+
+    let ragged = SpectrogramPlot::new(8000, 64).draw(&area, &power);
+    let frequencies = SpectrogramPlot::new(8000, 64).with_frequencies(vec![100.0, 200.0]).draw(&area, &power[..1]);
+    let log = SpectrogramPlot::new(8000, 64).with_log_frequency(5000.0).draw(&area, &power[..1]);
+
+    assert!(matches!(ragged, Err(PlotError::Window(HannWindowError::LengthMismatch))));
+    assert!(matches!(frequencies, Err(PlotError::Window(HannWindowError::LengthMismatch))));
+    assert!(matches!(log, Err(PlotError::Window(HannWindowError::InvalidCutoff))));
+  }
+}
@@ -0,0 +1,95 @@
+use crate::{ fir::fir_lowpass, hann_window::HannWindowError, window_spec::WindowSpec };
+
+/// Design the prototype lowpass filter of a polyphase filterbank channelizer.
+///
+/// This function takes the number of channels `channel_count` of the filterbank, the number of
+/// `taps_per_channel` (the number of past input frames each output spectrum depends on) and the
+/// `window` tapering the sinc, and returns the `channel_count * taps_per_channel` taps. The cutoff is
+/// half the channel spacing, `1 / (2 * channel_count)` cycles per sample, so that the responses of
+/// adjacent channels cross at -6 dB and sum to a flat response. The taps are normalized to unit gain at
+/// 0 Hz. An error is returned if there are fewer than 2 channels or if there are no taps per channel.
+pub fn pfb_prototype(channel_count: usize, taps_per_channel: usize, window: WindowSpec) -> Result<Vec<f32>, HannWindowError> {
+  // A single channel is not a filterbank
+  if channel_count < 2 {
+    return Err(HannWindowError::InvalidChannelCount);
+  }
+  // Every branch of the filterbank needs at least one tap
+  if taps_per_channel == 0 {
+    return Err(HannWindowError::WindowLengthTooSmall);
+  }
+
+  fir_lowpass(channel_count * taps_per_channel, 0.5 / (channel_count as f32), window)
+}
+
+/// Reshape the taps of a prototype filter into the polyphase matrix of a filterbank.
+///
+/// Row `k` of the returned matrix is the branch filter `taps[k], taps[k + M], taps[k + 2M], ...` of
+/// channel `k`, where `M` is the `channel_count`, which is the layout the weighted overlap-add of a
+/// polyphase filterbank reads the taps in. An error is returned if the channel count is 0 or if the
+/// number of taps is not a multiple of the channel count.
+pub fn polyphase_matrix(taps: &[f32], channel_count: usize) -> Result<Vec<Vec<f32>>, HannWindowError> {
+  // The taps must be split evenly between the channels
+  if channel_count == 0 {
+    return Err(HannWindowError::InvalidChannelCount);
+  }
+  if !taps.len().is_multiple_of(channel_count) {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  let matrix = (0..channel_count)
+    .map(|channel| taps.iter().skip(channel).step_by(channel_count).copied().collect())
+    .collect();
+
+  Ok(matrix)
+}
+
+#[cfg(test)]
+mod test_polyphase {
+  use std::f64::consts::PI;
+
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  // The magnitude of the frequency response at `frequency`, in cycles per sample
+  fn gain(taps: &[f32], frequency: f64) -> f64 {
+    let (real, imaginary) = taps.iter().enumerate().fold((0.0, 0.0), |(real, imaginary), (n, &tap)| {
+      let phase = 2.0 * PI * frequency * (n as f64);
+      (real + f64::from(tap) * phase.cos(), imaginary - f64::from(tap) * phase.sin())
+    });
+    (real * real + imaginary * imaginary).sqrt()
+  }
+
+  #[test]
+  fn test_pfb_prototype_response() {
+    let channel_count = 16;
+    let taps = pfb_prototype(channel_count, 8, WindowSpec::Hamming).unwrap();
+
+    // The response crosses -6 dB at the edge of the channel and rejects the channels further away
+    assert_eq!(taps.len(), 128);
+    assert_abs_diff_eq!(gain(&taps, 0.0), 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(gain(&taps, 0.5 / 16.0), 0.5, epsilon = 0.01);
+    for channel in 2..8 {
+      assert!(gain(&taps, (channel as f64) / 16.0) < 1e-2);
+    }
+    for i in 0..64 {
+      assert_abs_diff_eq!(taps[i], taps[127 - i], epsilon = 1e-7);
+    }
+  }
+
+  #[test]
+  fn test_polyphase_matrix() {
+    let taps: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let matrix = polyphase_matrix(&taps, 4).unwrap();
+
+    assert_eq!(matrix, vec![vec![0.0, 4.0, 8.0], vec![1.0, 5.0, 9.0], vec![2.0, 6.0, 10.0], vec![3.0, 7.0, 11.0]]);
+  }
+
+  #[test]
+  fn test_polyphase_invalid_parameters() {
+    assert_eq!(pfb_prototype(1, 8, WindowSpec::Hann).unwrap_err(), HannWindowError::InvalidChannelCount);
+    assert_eq!(pfb_prototype(8, 0, WindowSpec::Hann).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(polyphase_matrix(&[0.0; 10], 4).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(polyphase_matrix(&[0.0; 10], 0).unwrap_err(), HannWindowError::InvalidChannelCount);
+  }
+}
@@ -0,0 +1,42 @@
+//! The items most analyses need, for a single glob import.
+//!
+//! `use hann_rs::prelude::*;` brings in the window catalogue, the Hann window, framing and window
+//! application, the common streaming processors, the [`Latency`] trait and, with the `dasp` and
+//! `futures` features, the extension traits windowing signals and streams.
+
+pub use crate::{
+  apply_window::apply_window,
+  frames::{ frames, PaddingMode },
+  hann_window::{ get_hann_window, HannWindowError },
+  latency::Latency,
+  metrics::{ window_metrics, WindowMetrics },
+  overlap_add::OverlapAdd,
+  realtime::RealtimeProcessor,
+  streaming_windower::StreamingWindower,
+  window_spec::WindowSpec,
+};
+#[cfg(feature = "dasp")]
+pub use crate::dasp_interop::WindowSignalExt;
+#[cfg(feature = "futures")]
+pub use crate::stream::WindowedStreamExt;
+
+#[cfg(test)]
+mod test_prelude {
+  use super::*;
+
+  #[test]
+  fn test_prelude_analysis() {
+    let signal = vec![1.0; 64];
+    let window = WindowSpec::Hann.generate(16).unwrap();
+
+    // Framing, windowing and measuring need nothing beyond the prelude
+    let frame_count = frames(&signal, 16, 8, PaddingMode::None).unwrap().with_window(&window).unwrap().count();
+    let mut frame = vec![1.0; 16];
+    apply_window(&mut frame, &get_hann_window(16).unwrap()).unwrap();
+
+    assert_eq!(frame_count, 7);
+    assert_eq!(frame, window);
+    assert!(window_metrics(&window).unwrap().sidelobe_level < -31.0);
+    assert_eq!(StreamingWindower::new(16, 8).unwrap().latency(), 15);
+  }
+}
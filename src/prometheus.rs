@@ -0,0 +1,320 @@
+use std::{
+  fmt::Write as _,
+  io::{ self, Write },
+  sync::atomic::{ AtomicU64, Ordering },
+  time::Duration,
+};
+
+// The upper bound of the first bucket of processing times, in nanoseconds
+const FIRST_BUCKET_NANOS: f64 = 1000.0;
+// The number of buckets of processing times, each a factor of the square root of 2 wider than the last,
+// from 1 µs to about 16 s
+const BUCKET_COUNT: usize = 48;
+// The quantiles of the processing times in the text format
+const QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// The streaming processors reporting the frames they process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingProcessor {
+  /// Frames emitted by a `StreamingWindower`, including the channels of a `MultichannelWindower`.
+  StreamingWindower,
+  /// Frames added by an `OverlapAdd`.
+  OverlapAdd,
+  /// Frames processed by a `RealtimeProcessor`.
+  RealtimeProcessor,
+}
+
+impl StreamingProcessor {
+  // Every processor, in the order of the counters
+  const ALL: [StreamingProcessor; 3] =
+    [StreamingProcessor::StreamingWindower, StreamingProcessor::OverlapAdd, StreamingProcessor::RealtimeProcessor];
+
+  /// The value of the `processor` label of the processor.
+  pub fn label(self) -> &'static str {
+    match self {
+      StreamingProcessor::StreamingWindower => "streaming_windower",
+      StreamingProcessor::OverlapAdd => "overlap_add",
+      StreamingProcessor::RealtimeProcessor => "realtime_processor",
+    }
+  }
+}
+
+/// The lookup tables reporting their hits and misses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupTable {
+  /// The precomputed Hann windows of `get_hann_window`.
+  HannWindow,
+  /// The precomputed sums of squares of `get_hann_window_sum_squares`.
+  HannWindowSumSquares,
+}
+
+impl LookupTable {
+  // Every lookup table, in the order of the counters
+  const ALL: [LookupTable; 2] = [LookupTable::HannWindow, LookupTable::HannWindowSumSquares];
+
+  /// The value of the `table` label of the lookup table.
+  pub fn label(self) -> &'static str {
+    match self {
+      LookupTable::HannWindow => "hann_window",
+      LookupTable::HannWindowSumSquares => "hann_window_sum_squares",
+    }
+  }
+}
+
+// The process-wide counters, updated with relaxed atomic operations so that the real-time paths never
+// lock or allocate
+static FRAMES_PROCESSED: [AtomicU64; 3] = [const { AtomicU64::new(0) }; 3];
+static CACHE_HITS: [AtomicU64; 2] = [const { AtomicU64::new(0) }; 2];
+static CACHE_MISSES: [AtomicU64; 2] = [const { AtomicU64::new(0) }; 2];
+static DROPPED_FRAMES: AtomicU64 = AtomicU64::new(0);
+static PROCESSING_BUCKETS: [AtomicU64; BUCKET_COUNT] = [const { AtomicU64::new(0) }; BUCKET_COUNT];
+static PROCESSING_NANOS: AtomicU64 = AtomicU64::new(0);
+
+// Count frames processed by a processor
+pub(crate) fn record_frames(processor: StreamingProcessor, frame_count: usize) {
+  FRAMES_PROCESSED[processor as usize].fetch_add(frame_count as u64, Ordering::Relaxed);
+}
+
+// Count a hit or a miss of a lookup table
+pub(crate) fn record_lookup(table: LookupTable, hit: bool) {
+  let counters = if hit { &CACHE_HITS } else { &CACHE_MISSES };
+  counters[table as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+// Count a frame dropped by a full frame queue
+pub(crate) fn record_dropped_frame() {
+  DROPPED_FRAMES.fetch_add(1, Ordering::Relaxed);
+}
+
+// Add the time taken to process a frame of a `RealtimeProcessor` to the histogram
+pub(crate) fn record_processing_time(duration: Duration) {
+  let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+  PROCESSING_BUCKETS[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+  PROCESSING_NANOS.fetch_add(nanos, Ordering::Relaxed);
+}
+
+// The bucket of a processing time, the last bucket holding every longer time
+fn bucket_index(nanos: u64) -> usize {
+  if (nanos as f64) <= FIRST_BUCKET_NANOS {
+    return 0;
+  }
+  let index = (2.0 * ((nanos as f64) / FIRST_BUCKET_NANOS).log2()).ceil() as usize;
+  index.min(BUCKET_COUNT - 1)
+}
+
+// The upper bound of a bucket, in seconds
+fn bucket_bound(index: usize) -> f64 {
+  FIRST_BUCKET_NANOS * 2.0f64.powf((index as f64) / 2.0) * 1e-9
+}
+
+/// A snapshot of the metrics of the streaming processors.
+///
+/// The metrics are process-wide and updated by every processor, lookup table and frame queue with the
+/// `prometheus` feature, at the cost of a relaxed atomic addition per call and, for the frames of a
+/// `RealtimeProcessor`, two reads of the monotonic clock. Take a snapshot with
+/// [`StreamingMetrics::snapshot`], and serve [`StreamingMetrics::to_prometheus`] from the `/metrics`
+/// endpoint of a service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamingMetrics {
+  frames_processed: [u64; 3],
+  cache_hits: [u64; 2],
+  cache_misses: [u64; 2],
+  dropped_frames: u64,
+  processing_buckets: [u64; BUCKET_COUNT],
+  processing_seconds: f64,
+}
+
+impl StreamingMetrics {
+  /// Read the current values of every metric.
+  pub fn snapshot() -> Self {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    StreamingMetrics {
+      frames_processed: FRAMES_PROCESSED.each_ref().map(load),
+      cache_hits: CACHE_HITS.each_ref().map(load),
+      cache_misses: CACHE_MISSES.each_ref().map(load),
+      dropped_frames: load(&DROPPED_FRAMES),
+      processing_buckets: PROCESSING_BUCKETS.each_ref().map(load),
+      processing_seconds: (load(&PROCESSING_NANOS) as f64) * 1e-9,
+    }
+  }
+
+  /// The number of frames processed by `processor`.
+  pub fn frames_processed(&self, processor: StreamingProcessor) -> u64 {
+    self.frames_processed[processor as usize]
+  }
+
+  /// The number of lookups of `table` served from the table.
+  pub fn cache_hits(&self, table: LookupTable) -> u64 {
+    self.cache_hits[table as usize]
+  }
+
+  /// The number of lookups of `table` computed because the table did not hold them.
+  pub fn cache_misses(&self, table: LookupTable) -> u64 {
+    self.cache_misses[table as usize]
+  }
+
+  /// The number of frames dropped by full frame queues.
+  pub fn dropped_frames(&self) -> u64 {
+    self.dropped_frames
+  }
+
+  /// The number of frames whose processing time was measured.
+  pub fn processing_count(&self) -> u64 {
+    self.processing_buckets.iter().sum()
+  }
+
+  /// The total processing time of the measured frames, in seconds.
+  pub fn processing_seconds(&self) -> f64 {
+    self.processing_seconds
+  }
+
+  /// Estimate the `quantile` of the processing time of a frame, in seconds, or `None` if no frame was
+  /// measured.
+  ///
+  /// The times are counted in buckets a factor of the square root of 2 wide, so the estimate is
+  /// interpolated within a bucket and is accurate to about 20%.
+  pub fn processing_time_quantile(&self, quantile: f64) -> Option<f64> {
+    let count = self.processing_count();
+    if count == 0 {
+      return None;
+    }
+
+    // Find the bucket holding the rank and interpolate geometrically within it
+    let rank = quantile.clamp(0.0, 1.0) * (count as f64);
+    let mut cumulative = 0.0;
+    for (index, &bucket_count) in self.processing_buckets.iter().enumerate() {
+      let next = cumulative + (bucket_count as f64);
+      if bucket_count > 0 && next >= rank {
+        let upper = bucket_bound(index);
+        let lower = if index == 0 { 0.0 } else { bucket_bound(index - 1) };
+        let fraction = (rank - cumulative) / (bucket_count as f64);
+        return Some(if index == 0 { upper * fraction } else { lower * (upper / lower).powf(fraction) });
+      }
+      cumulative = next;
+    }
+    Some(bucket_bound(BUCKET_COUNT - 1))
+  }
+
+  /// Write the metrics in the Prometheus text exposition format to `writer`.
+  pub fn write_prometheus<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    writer.write_all(self.to_prometheus().as_bytes())?;
+    writer.flush()
+  }
+
+  /// Return the metrics in the Prometheus text exposition format.
+  ///
+  /// The families are `hann_rs_frames_processed_total{processor}`, `hann_rs_cache_hits_total{table}`,
+  /// `hann_rs_cache_misses_total{table}`, `hann_rs_dropped_frames_total` and the summary
+  /// `hann_rs_frame_processing_seconds` with the 0.5, 0.9, 0.99 and 0.999 quantiles.
+  pub fn to_prometheus(&self) -> String {
+    let mut text = String::new();
+    // Writing to a String does not fail
+    let _ = self.write_families(&mut text);
+    text
+  }
+
+  // Write every metric family to `text`
+  fn write_families(&self, text: &mut String) -> std::fmt::Result {
+    writeln!(text, "# HELP hann_rs_frames_processed_total Frames processed by the streaming processors.")?;
+    writeln!(text, "# TYPE hann_rs_frames_processed_total counter")?;
+    for processor in StreamingProcessor::ALL {
+      writeln!(text, "hann_rs_frames_processed_total{{processor=\"{}\"}} {}", processor.label(), self.frames_processed(processor))?;
+    }
+
+    for (name, help, counters) in [
+      ("hann_rs_cache_hits_total", "Lookups served from the precomputed tables.", &self.cache_hits),
+      ("hann_rs_cache_misses_total", "Lookups computed because the precomputed tables did not hold them.", &self.cache_misses),
+    ] {
+      writeln!(text, "# HELP {} {}", name, help)?;
+      writeln!(text, "# TYPE {} counter", name)?;
+      for table in LookupTable::ALL {
+        writeln!(text, "{}{{table=\"{}\"}} {}", name, table.label(), counters[table as usize])?;
+      }
+    }
+
+    writeln!(text, "# HELP hann_rs_dropped_frames_total Frames dropped by full frame queues.")?;
+    writeln!(text, "# TYPE hann_rs_dropped_frames_total counter")?;
+    writeln!(text, "hann_rs_dropped_frames_total {}", self.dropped_frames)?;
+
+    writeln!(text, "# HELP hann_rs_frame_processing_seconds Time taken to process a frame of a realtime processor.")?;
+    writeln!(text, "# TYPE hann_rs_frame_processing_seconds summary")?;
+    for quantile in QUANTILES {
+      match self.processing_time_quantile(quantile) {
+        Some(seconds) => writeln!(text, "hann_rs_frame_processing_seconds{{quantile=\"{}\"}} {:e}", quantile, seconds)?,
+        None => writeln!(text, "hann_rs_frame_processing_seconds{{quantile=\"{}\"}} NaN", quantile)?,
+      }
+    }
+    writeln!(text, "hann_rs_frame_processing_seconds_sum {:e}", self.processing_seconds)?;
+    writeln!(text, "hann_rs_frame_processing_seconds_count {}", self.processing_count())
+  }
+}
+
+#[cfg(test)]
+mod test_prometheus {
+  use crate::{ frame_queue::frame_queue, get_hann_window, realtime::RealtimeProcessor };
+
+  use super::*;
+
+  #[test]
+  fn test_bucket_index() {
+    assert_eq!(bucket_index(0), 0);
+    assert_eq!(bucket_index(1000), 0);
+    assert_eq!(bucket_index(1001), 1);
+    assert_eq!(bucket_index(2000), 2);
+    assert_eq!(bucket_index(u64::MAX), BUCKET_COUNT - 1);
+    assert!((bucket_bound(2) - 2e-6).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_streaming_metrics_counts() {
+    let before = StreamingMetrics::snapshot();
+    // The metrics are process-wide and other tests run concurrently, so only increases are checked
+    get_hann_window(1024).unwrap();
+    get_hann_window(1000).unwrap();
+    let (mut producer, _consumer) = frame_queue(1, 4).unwrap();
+    for _ in 0..3 {
+      producer.push(&[0.0; 4]).unwrap();
+    }
+    let mut processor = RealtimeProcessor::new(&get_hann_window(64).unwrap(), 16).unwrap();
+    let mut output = vec![0.0; 256];
+    processor.process(&vec![0.5; 256], &mut output, |_| {}).unwrap();
+
+    let after = StreamingMetrics::snapshot();
+
+    assert!(after.cache_hits(LookupTable::HannWindow) > before.cache_hits(LookupTable::HannWindow));
+    assert!(after.cache_misses(LookupTable::HannWindow) > before.cache_misses(LookupTable::HannWindow));
+    assert!(after.dropped_frames() >= before.dropped_frames() + 2);
+    for processor in StreamingProcessor::ALL {
+      assert!(after.frames_processed(processor) >= before.frames_processed(processor) + 13);
+    }
+    assert!(after.processing_count() >= before.processing_count() + 13);
+  }
+
+  #[test]
+  fn test_processing_time_quantile() {
+    let mut metrics = StreamingMetrics::snapshot();
+    metrics.processing_buckets = [0; BUCKET_COUNT];
+    assert_eq!(metrics.processing_time_quantile(0.5), None);
+
+    // 90 frames of about 10 µs and 10 frames of about 1 ms
+    metrics.processing_buckets[bucket_index(10_000)] = 90;
+    metrics.processing_buckets[bucket_index(1_000_000)] = 10;
+
+    let median = metrics.processing_time_quantile(0.5).unwrap();
+    let tail = metrics.processing_time_quantile(0.99).unwrap();
+    assert!((7e-6..=1e-5).contains(&median), "median {}", median);
+    assert!((7e-4..=1.1e-3).contains(&tail), "tail {}", tail);
+  }
+
+  #[test]
+  fn test_to_prometheus() {
+    let text = StreamingMetrics::snapshot().to_prometheus();
+
+    assert!(text.contains("# TYPE hann_rs_frames_processed_total counter\n"));
+    assert!(text.contains("hann_rs_frames_processed_total{processor=\"realtime_processor\"} "));
+    assert!(text.contains("hann_rs_cache_misses_total{table=\"hann_window_sum_squares\"} "));
+    assert!(text.contains("hann_rs_frame_processing_seconds{quantile=\"0.99\"} "));
+    // Every sample line has a name, optional labels and a value
+    assert!(text.lines().filter(|line| !line.starts_with('#')).all(|line| line.split(' ').count() == 2));
+  }
+}
@@ -0,0 +1,158 @@
+use std::f64::consts::{ FRAC_1_SQRT_2, PI };
+
+use crate::hann_window::{ check_window_length, HannWindowError };
+
+/// Generate a raised-cosine pulse-shaping filter.
+///
+/// This function takes the number of `samples_per_symbol`, the `span` of the filter in symbols and
+/// the `roll_off` factor in [0, 1] setting the excess bandwidth, and returns the
+/// `span * samples_per_symbol + 1` taps centered on the symbol. The pulse has a peak of 1 and crosses
+/// zero at every other symbol, so that it causes no intersymbol interference. An error is returned if
+/// the roll-off is not in [0, 1], if there are no samples per symbol, if the span is 0 or if the
+/// filter would have more taps than a window can have.
+pub fn raised_cosine(samples_per_symbol: usize, span: usize, roll_off: f32) -> Result<Vec<f32>, HannWindowError> {
+  let (roll_off, tap_count) = check_pulse(samples_per_symbol, span, roll_off)?;
+
+  let taps = pulse_times(samples_per_symbol, tap_count)
+    .map(|t| {
+      // The pulse is finite where the denominator vanishes, at half the inverse roll-off
+      if roll_off > 0.0 && ((2.0 * roll_off * t).abs() - 1.0).abs() < 1e-9 {
+        (PI / 4.0) * sinc(1.0 / (2.0 * roll_off))
+      } else {
+        (sinc(t) * (PI * roll_off * t).cos()) / (1.0 - (2.0 * roll_off * t).powi(2))
+      }
+    })
+    .map(|value| value as f32)
+    .collect();
+
+  Ok(taps)
+}
+
+/// Generate a root-raised-cosine pulse-shaping filter.
+///
+/// The parameters are those of [`raised_cosine`]. The taps are normalized to unit energy, so that a
+/// transmit filter followed by the same matched receive filter forms a raised-cosine pulse with a peak
+/// of 1.
+pub fn root_raised_cosine(samples_per_symbol: usize, span: usize, roll_off: f32) -> Result<Vec<f32>, HannWindowError> {
+  let (roll_off, tap_count) = check_pulse(samples_per_symbol, span, roll_off)?;
+
+  let taps: Vec<f64> = pulse_times(samples_per_symbol, tap_count)
+    .map(|t| {
+      if t == 0.0 {
+        1.0 - roll_off + (4.0 * roll_off) / PI
+      } else if roll_off > 0.0 && ((4.0 * roll_off * t).abs() - 1.0).abs() < 1e-9 {
+        // The pulse is finite where the denominator vanishes, at a quarter of the inverse roll-off
+        let angle = PI / (4.0 * roll_off);
+        roll_off * FRAC_1_SQRT_2 * ((1.0 + 2.0 / PI) * angle.sin() + (1.0 - 2.0 / PI) * angle.cos())
+      } else {
+        let numerator = (PI * t * (1.0 - roll_off)).sin() + 4.0 * roll_off * t * (PI * t * (1.0 + roll_off)).cos();
+        numerator / (PI * t * (1.0 - (4.0 * roll_off * t).powi(2)))
+      }
+    })
+    .collect();
+
+  // Normalize to unit energy
+  let energy = taps.iter().map(|tap| tap * tap).sum::<f64>().sqrt();
+
+  Ok(taps.iter().map(|tap| (tap / energy) as f32).collect())
+}
+
+// Check the pulse parameters and return the roll-off in double precision and the number of taps
+fn check_pulse(samples_per_symbol: usize, span: usize, roll_off: f32) -> Result<(f64, usize), HannWindowError> {
+  // A symbol must contain at least one sample
+  if samples_per_symbol == 0 {
+    return Err(HannWindowError::InvalidSamplesPerSymbol);
+  }
+  // The pulse must cover at least one symbol
+  if span == 0 {
+    return Err(HannWindowError::WindowLengthTooSmall);
+  }
+  // The excess bandwidth ranges from none to twice the symbol rate
+  if !(0.0..=1.0).contains(&roll_off) {
+    return Err(HannWindowError::InvalidRollOff);
+  }
+  // The taps must fit in a window
+  let tap_count = span
+    .checked_mul(samples_per_symbol)
+    .and_then(|length| length.checked_add(1))
+    .ok_or(HannWindowError::WindowLengthTooLarge)?;
+  check_window_length(tap_count)?;
+  Ok((f64::from(roll_off), tap_count))
+}
+
+// The time of every tap in symbols, relative to the center of the pulse
+fn pulse_times(samples_per_symbol: usize, tap_count: usize) -> impl Iterator<Item = f64> {
+  let middle = ((tap_count - 1) as f64) / 2.0;
+  (0..tap_count).map(move |n| ((n as f64) - middle) / (samples_per_symbol as f64))
+}
+
+// The normalized sinc function `sin(πx) / (πx)`
+fn sinc(x: f64) -> f64 {
+  if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+#[cfg(test)]
+mod test_pulse_shaping {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_raised_cosine_zero_crossings() {
+    let taps = raised_cosine(8, 6, 0.35).unwrap();
+
+    // The pulse peaks at the center symbol and vanishes at every other symbol
+    assert_eq!(taps.len(), 49);
+    assert_abs_diff_eq!(taps[24], 1.0, epsilon = 1e-7);
+    for symbol in [0, 8, 16, 32, 40, 48] {
+      assert_abs_diff_eq!(taps[symbol], 0.0, epsilon = 1e-7);
+    }
+    for i in 0..24 {
+      assert_abs_diff_eq!(taps[i], taps[48 - i], epsilon = 1e-7);
+    }
+  }
+
+  #[test]
+  fn test_raised_cosine_singularity() {
+    // With a roll-off of 0.3 the denominator vanishes 5/3 symbols from the center
+    let taps = raised_cosine(3, 4, 0.3).unwrap();
+    let fine_taps = raised_cosine(3000, 4, 0.3).unwrap();
+
+    // The tap takes the limit of the pulse, which its neighbors approach
+    assert!(taps.iter().all(|tap| tap.is_finite()));
+    assert_abs_diff_eq!(taps[6 + 5], ((PI / 4.0) * sinc(5.0 / 3.0)) as f32, epsilon = 1e-7);
+    assert_abs_diff_eq!(fine_taps[6000 + 4999], taps[6 + 5], epsilon = 5e-4);
+  }
+
+  #[test]
+  fn test_root_raised_cosine_matched_filter() {
+    let samples_per_symbol = 8;
+    let taps = root_raised_cosine(samples_per_symbol, 16, 0.25).unwrap();
+
+    // The pulse has unit energy and its tap at a quarter of the inverse roll-off is finite
+    assert_abs_diff_eq!(taps.iter().map(|tap| tap * tap).sum::<f32>(), 1.0, epsilon = 1e-5);
+    assert!(taps.iter().all(|tap| tap.is_finite()));
+
+    // The matched filter cascade is a raised cosine without intersymbol interference
+    let cascade = |lag: usize| -> f32 { taps[lag..].iter().zip(taps.iter()).map(|(a, b)| a * b).sum() };
+    assert_abs_diff_eq!(cascade(0), 1.0, epsilon = 1e-5);
+    for symbol in 1..8 {
+      assert_abs_diff_eq!(cascade(symbol * samples_per_symbol), 0.0, epsilon = 5e-3);
+    }
+  }
+
+  #[test]
+  fn test_pulse_shaping_invalid_parameters() {
+    assert_eq!(raised_cosine(0, 4, 0.5).unwrap_err(), HannWindowError::InvalidSamplesPerSymbol);
+    assert_eq!(raised_cosine(4, 0, 0.5).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(root_raised_cosine(4, 4, 1.5).unwrap_err(), HannWindowError::InvalidRollOff);
+    assert_eq!(root_raised_cosine(4, 4, -0.1).unwrap_err(), HannWindowError::InvalidRollOff);
+  }
+
+  #[test]
+  fn test_pulse_shaping_too_many_taps() {
+    assert_eq!(raised_cosine(usize::MAX, 2, 0.5).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+    assert_eq!(root_raised_cosine(usize::MAX, 1, 0.5).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+    assert_eq!(raised_cosine(1 << 20, 1 << 20, 0.5).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+  }
+}
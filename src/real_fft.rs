@@ -0,0 +1,142 @@
+use std::{ fmt, sync::Arc };
+
+use realfft::{ num_complex::Complex, RealFftPlanner, RealToComplex };
+
+use crate::{ hann_window::{ get_hann_window, HannWindowError }, instrument::trace_allocation };
+
+/// A windowed real-input FFT plan owning the window, the `realfft` plan and every buffer of the transform.
+///
+/// Real frames of `N` samples have a conjugate-symmetric spectrum, so only the `N / 2 + 1` bins from 0
+/// Hz to the Nyquist frequency are computed, with half the work and memory of a complex FFT. As with
+/// `WindowedFft` behind the `rustfft` feature, the window always matches the FFT length, every buffer
+/// is allocated by [`WindowedRealFft::new`] and [`WindowedRealFft::process`] does not allocate.
+#[derive(Clone)]
+pub struct WindowedRealFft {
+  window: Vec<f32>,
+  fft: Arc<dyn RealToComplex<f32>>,
+  frame: Vec<f32>,
+  spectrum: Vec<Complex<f32>>,
+  scratch: Vec<Complex<f32>>,
+}
+
+impl WindowedRealFft {
+  /// Create a plan for frames of `fft_length` samples, windowed by the Hann window of the same length.
+  ///
+  /// An error is returned if the FFT length is less than or equal to 1, or too large.
+  pub fn new(fft_length: usize) -> Result<Self, HannWindowError> {
+    let window = get_hann_window(fft_length)?;
+    let fft = RealFftPlanner::new().plan_fft_forward(fft_length);
+    let spectrum = fft.make_output_vec();
+    let scratch = fft.make_scratch_vec();
+    trace_allocation("WindowedRealFft", (spectrum.len() + scratch.len()) * std::mem::size_of::<Complex<f32>>());
+
+    Ok(WindowedRealFft { window, frame: fft.make_input_vec(), spectrum, scratch, fft })
+  }
+
+  /// Use `window` instead of the Hann window.
+  ///
+  /// An error is returned if the window length differs from the FFT length.
+  pub fn with_window(mut self, window: &[f32]) -> Result<Self, HannWindowError> {
+    // The window must cover the transform sample for sample
+    if window.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.window.copy_from_slice(window);
+    Ok(self)
+  }
+
+  /// The length of the frames.
+  pub fn fft_length(&self) -> usize {
+    self.window.len()
+  }
+
+  /// The number of bins of the spectra, `fft_length / 2 + 1`.
+  pub fn bin_count(&self) -> usize {
+    self.spectrum.len()
+  }
+
+  /// The window applied to every frame.
+  pub fn window(&self) -> &[f32] {
+    &self.window
+  }
+
+  /// Window a frame and return the non-negative frequency half of its spectrum.
+  ///
+  /// The spectrum has `fft_length / 2 + 1` bins, bin `k` being the frequency `k / fft_length` cycles per
+  /// sample. It is stored in the plan and overwritten by the next call. An error is returned if the
+  /// frame length differs from the FFT length.
+  pub fn process(&mut self, frame: &[f32]) -> Result<&[Complex<f32>], HannWindowError> {
+    // The frame must match the window sample for sample
+    if frame.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    // Window the frame into the input buffer, which the transform uses as scratch space
+    for ((input, &sample), &value) in self.frame.iter_mut().zip(frame.iter()).zip(self.window.iter()) {
+      *input = sample * value;
+    }
+    self
+      .fft
+      .process_with_scratch(&mut self.frame, &mut self.spectrum, &mut self.scratch)
+      .map_err(|_error| HannWindowError::LengthMismatch)?;
+
+    Ok(&self.spectrum)
+  }
+}
+
+// Implement the Debug trait for the WindowedRealFft struct, the plan itself is not Debug
+impl fmt::Debug for WindowedRealFft {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("WindowedRealFft").field("fft_length", &self.fft_length()).finish_non_exhaustive()
+  }
+}
+
+#[cfg(test)]
+mod test_real_fft {
+  use std::f32::consts::PI;
+
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_windowed_real_fft_matches_direct_transform() {
+    let window = [1.0, 0.5, 0.25, 0.5, 1.0];
+    let frame = [1.0, 2.0, -1.0, 0.5, 3.0];
+    let mut fft = WindowedRealFft::new(5).unwrap().with_window(&window).unwrap();
+
+    let spectrum = fft.process(&frame).unwrap().to_vec();
+
+    // An odd length has no Nyquist bin
+    assert_eq!(spectrum.len(), 3);
+    for (k, bin) in spectrum.iter().enumerate() {
+      let expected: Complex<f32> = (0..5)
+        .map(|n| Complex::from_polar(frame[n] * window[n], (-2.0 * PI * ((k * n) as f32)) / 5.0))
+        .sum();
+      assert_abs_diff_eq!(bin.re, expected.re, epsilon = 1e-5);
+      assert_abs_diff_eq!(bin.im, expected.im, epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_windowed_real_fft_tone() {
+    let mut fft = WindowedRealFft::new(64).unwrap();
+    let frame: Vec<f32> = (0..64).map(|n| ((2.0 * PI * 8.0 * (n as f32)) / 64.0).sin()).collect();
+
+    assert_eq!(fft.bin_count(), 33);
+    let spectrum = fft.process(&frame).unwrap();
+
+    let peak = spectrum[8].norm();
+    assert!(peak > 10.0);
+    assert!(spectrum[20].norm() < 1e-3 * peak);
+  }
+
+  #[test]
+  fn test_windowed_real_fft_length_mismatch() {
+    let mut fft = WindowedRealFft::new(16).unwrap();
+
+    assert_eq!(fft.process(&[0.0; 17]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(WindowedRealFft::new(16).unwrap().with_window(&[1.0; 8]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(WindowedRealFft::new(0).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
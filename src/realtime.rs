@@ -0,0 +1,255 @@
+use crate::{
+  hann_window::HannWindowError,
+  instrument::record_metric,
+  latency::Latency,
+  overlap_add::{ GainCompensation, OverlapAdd },
+  streaming_windower::StreamingWindower,
+};
+
+/// A block-in, block-out frame processor that is safe to call from a real-time audio callback.
+///
+/// Input blocks are split into windowed frames by a [`StreamingWindower`], every frame is handed to a
+/// processing closure, and the processed frames are overlap-added into the output by an [`OverlapAdd`]
+/// normalized with the analysis window, unless the gain compensation is changed with
+/// [`RealtimeProcessor::set_gain_compensation`]. The output is the reconstructed signal delayed by
+/// [`RealtimeProcessor::latency`] samples, the latency of the windower and the overlap-add combined.
+///
+/// Every buffer and window is allocated by [`RealtimeProcessor::new`]. After construction,
+/// [`RealtimeProcessor::process`] never allocates, never takes a lock and never blocks, so it cannot
+/// cause allocator hits or priority inversion on the audio thread. This also holds for
+/// [`StreamingWindower::process`] and [`OverlapAdd::push`] when they are used on their own.
+#[derive(Debug, Clone)]
+pub struct RealtimeProcessor {
+  windower: StreamingWindower,
+  overlap_add: OverlapAdd,
+  frame: Vec<f32>,
+  output_queue: SampleQueue,
+}
+
+impl RealtimeProcessor {
+  /// Create a processor analyzing and resynthesizing frames with `window`, advancing by `hop_size` samples.
+  ///
+  /// The frame length is the length of the window. An error is returned if the window is empty or the
+  /// hop size is 0.
+  pub fn new(window: &[f32], hop_size: usize) -> Result<Self, HannWindowError> {
+    let windower = StreamingWindower::new(window.len(), hop_size)?.with_window(window)?;
+    let overlap_add = OverlapAdd::new(window.len(), hop_size)?.with_window(window)?.with_normalization(window)?;
+
+    // A frame is finished `frame_length - 1` samples after its first sample was received, start the
+    // output queue with that many samples of silence so that every block can be answered right away
+    let latency = window.len() - 1;
+    let mut output_queue = SampleQueue::new(latency + 2 * hop_size);
+    output_queue.push_zeros(latency);
+
+    Ok(RealtimeProcessor {
+      windower,
+      overlap_add,
+      frame: vec![0.0; window.len()],
+      output_queue,
+    })
+  }
+
+  /// The length of the processed frames.
+  pub fn frame_length(&self) -> usize {
+    self.windower.frame_length()
+  }
+
+  /// The number of samples between two processed frames.
+  pub fn hop_size(&self) -> usize {
+    self.windower.hop_size()
+  }
+
+  /// The delay in samples between the input and the reconstructed output, the same as
+  /// [`Latency::latency`].
+  pub fn latency(&self) -> usize {
+    self.windower.latency() + self.overlap_add.latency()
+  }
+
+  /// Compensate the reconstructed output as given by `gain_compensation`, see [`GainCompensation`].
+  pub fn with_gain_compensation(mut self, gain_compensation: GainCompensation) -> Self {
+    self.set_gain_compensation(gain_compensation);
+    self
+  }
+
+  /// Switch the gain compensation of the reconstructed output, which is [`GainCompensation::Envelope`]
+  /// by default. This does not allocate.
+  pub fn set_gain_compensation(&mut self, gain_compensation: GainCompensation) {
+    self.overlap_add.set_gain_compensation(gain_compensation);
+  }
+
+  /// The gain compensation of the reconstructed output.
+  pub fn gain_compensation(&self) -> GainCompensation {
+    self.overlap_add.gain_compensation()
+  }
+
+  /// Process a block of samples, calling `process_frame` with every windowed frame completed by it.
+  ///
+  /// The closure may modify the frame in place before it is overlap-added into the output. The output
+  /// block must have the same length as the input block, otherwise an error is returned. This does not
+  /// allocate, lock or block.
+  pub fn process<F: FnMut(&mut [f32])>(
+    &mut self,
+    input: &[f32],
+    output: &mut [f32],
+    mut process_frame: F
+  ) -> Result<(), HannWindowError> {
+    // Every input sample produces exactly one output sample
+    if input.len() != output.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    // Work through the block one hop at a time, which bounds the number of queued output samples
+    let hop_size = self.hop_size();
+    for (input, output) in input.chunks(hop_size).zip(output.chunks_mut(hop_size)) {
+      let RealtimeProcessor { windower, overlap_add, frame, output_queue } = &mut *self;
+      windower.process(input, |windowed_frame| {
+        #[cfg(feature = "prometheus")]
+        let started = std::time::Instant::now();
+        frame.copy_from_slice(windowed_frame);
+        process_frame(frame);
+        let finished = overlap_add.push(frame).expect("Frame length matches the overlap-add");
+        output_queue.push(finished);
+        record_metric!(record_frames, StreamingProcessor::RealtimeProcessor, 1);
+        record_metric!(record_processing_time, started.elapsed());
+      });
+      output_queue.pop_into(output);
+    }
+
+    Ok(())
+  }
+
+  /// Discard every buffered sample and start over with a silent output.
+  pub fn reset(&mut self) {
+    self.windower.reset();
+    self.overlap_add.reset();
+    self.output_queue.clear();
+    self.output_queue.push_zeros(self.latency());
+  }
+}
+
+/// The output is the reconstructed input delayed by `frame_length - 1` samples.
+impl Latency for RealtimeProcessor {
+  fn latency(&self) -> usize {
+    RealtimeProcessor::latency(self)
+  }
+
+  fn group_delay(&self) -> f64 {
+    self.windower.group_delay() + self.overlap_add.group_delay()
+  }
+}
+
+// A fixed-capacity first-in first-out queue of samples
+#[derive(Debug, Clone)]
+struct SampleQueue {
+  buffer: Vec<f32>,
+  read_position: usize,
+  length: usize,
+}
+
+impl SampleQueue {
+  fn new(capacity: usize) -> Self {
+    SampleQueue { buffer: vec![0.0; capacity], read_position: 0, length: 0 }
+  }
+
+  fn push(&mut self, samples: &[f32]) {
+    assert!(self.length + samples.len() <= self.buffer.len(), "Sample queue overflow");
+    for &sample in samples {
+      let write_position = (self.read_position + self.length) % self.buffer.len();
+      self.buffer[write_position] = sample;
+      self.length += 1;
+    }
+  }
+
+  fn push_zeros(&mut self, count: usize) {
+    for _ in 0..count {
+      self.push(&[0.0]);
+    }
+  }
+
+  fn pop_into(&mut self, output: &mut [f32]) {
+    assert!(output.len() <= self.length, "Sample queue underflow");
+    for sample in output.iter_mut() {
+      *sample = self.buffer[self.read_position];
+      self.read_position = (self.read_position + 1) % self.buffer.len();
+      self.length -= 1;
+    }
+  }
+
+  fn clear(&mut self) {
+    self.read_position = 0;
+    self.length = 0;
+  }
+}
+
+#[cfg(test)]
+mod test_realtime {
+  use approx::assert_abs_diff_eq;
+
+  use crate::{ get_hann_window, normalization::get_window_envelope };
+
+  use super::*;
+
+  #[test]
+  fn test_realtime_processor_identity() {
+    let signal: Vec<f32> = (0..300).map(|i| ((i as f32) * 0.05).sin()).collect();
+    let window = get_hann_window(32).unwrap();
+    let mut processor = RealtimeProcessor::new(&window, 8).unwrap();
+
+    // Process the signal in blocks of varying sizes without modifying the frames
+    let mut output = vec![0.0; signal.len()];
+    let mut position = 0;
+    for &block_size in [1, 7, 64, 13, 3].iter().cycle() {
+      if position >= signal.len() {
+        break;
+      }
+      let end = (position + block_size).min(signal.len());
+      processor.process(&signal[position..end], &mut output[position..end], |_| {}).unwrap();
+      position = end;
+    }
+
+    // The output is the input delayed by the latency, except for the first sample which is multiplied
+    // by a zero window value
+    let latency = processor.latency();
+    assert!(output[..latency].iter().all(|&sample| sample == 0.0));
+    for i in latency + 1..signal.len() {
+      assert_abs_diff_eq!(output[i], signal[i - latency], epsilon = 1e-5);
+    }
+  }
+
+  #[test]
+  fn test_realtime_processor_modifies_frames() {
+    let window = get_hann_window(16).unwrap();
+    let mut processor = RealtimeProcessor::new(&window, 4).unwrap();
+    let input = [1.0; 64];
+    let mut output = [0.0; 64];
+
+    processor.process(&input, &mut output, |frame| frame.iter_mut().for_each(|sample| *sample *= 0.5)).unwrap();
+
+    assert_abs_diff_eq!(output[63], 0.5, epsilon = 1e-5);
+  }
+
+  #[test]
+  fn test_realtime_processor_without_gain_compensation() {
+    let window = get_hann_window(16).unwrap();
+    let mut processor = RealtimeProcessor::new(&window, 4).unwrap().with_gain_compensation(GainCompensation::None);
+    let input = [1.0; 64];
+    let mut output = [0.0; 64];
+
+    processor.process(&input, &mut output, |_| {}).unwrap();
+
+    // The raw output carries the overlap gain of the squared window, delayed by the latency
+    let squared: Vec<f32> = window.iter().map(|value| value * value).collect();
+    let envelope = get_window_envelope(&squared, 4, 64).unwrap();
+    assert_eq!(processor.gain_compensation(), GainCompensation::None);
+    assert_abs_diff_eq!(output[63], envelope[63 - processor.latency()], epsilon = 1e-5);
+  }
+
+  #[test]
+  fn test_realtime_processor_length_mismatch() {
+    let window = get_hann_window(16).unwrap();
+    let mut processor = RealtimeProcessor::new(&window, 4).unwrap();
+
+    assert_eq!(processor.process(&[0.0; 4], &mut [0.0; 3], |_| {}).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(RealtimeProcessor::new(&window, 0).unwrap_err(), HannWindowError::InvalidHopSize);
+  }
+}
@@ -0,0 +1,376 @@
+use std::f64::consts::PI;
+
+use crate::{
+  hann_window::HannWindowError,
+  instrument::trace_allocation,
+  latency::Latency,
+  window_spec::{ bessel_i0, kaiser_beta },
+};
+
+// The number of fractional delays the kernel is tabulated at, the kernel is interpolated in between
+const PHASE_COUNT: usize = 512;
+// The default number of input samples on each side of the kernel when upsampling
+const DEFAULT_HALF_LENGTH: usize = 64;
+// The default stopband attenuation of the kernel
+const DEFAULT_ATTENUATION_DB: f32 = 100.0;
+
+/// The largest number of input samples on each side of the kernel of a [`Resampler`], after the
+/// stretching by the downsampling ratio. The filter bank holds `513 * 2 * half_length` taps, 64 MiB at
+/// this maximum, which the default kernel reaches at a downsampling ratio of 1/256.
+pub const MAX_RESAMPLER_HALF_LENGTH: usize = 16384;
+
+/// A streaming sample-rate converter for arbitrary ratios, using a Kaiser-windowed sinc kernel.
+///
+/// The kernel is tabulated at 512 fractional delays (the polyphase filter bank) and interpolated
+/// linearly between them, so that any ratio between the sample rates is supported, including
+/// irrational ones. The cutoff of the kernel is placed so that its transition band ends at the Nyquist
+/// frequency of the lower of the two sample rates, which removes aliasing when downsampling. The
+/// output is aligned with the input: output sample `m` represents the instant `m / output_sample_rate`
+/// of the input, and is produced once the samples `half_length` input samples later have been
+/// received, see [`Latency`]. The input samples the kernel still needs are kept in a ring buffer
+/// allocated with the filter bank, processing samples does not allocate.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+  input_sample_rate: u32,
+  output_sample_rate: u32,
+  // The number of input samples on each side of the kernel
+  half_length: usize,
+  // `PHASE_COUNT + 1` rows of `2 * half_length` taps, one row per fractional delay
+  filter_bank: Vec<f32>,
+  // The last `2 * half_length` input samples, stored twice back to back so that the kernel always
+  // covers a contiguous slice
+  ring_buffer: Vec<f32>,
+  // The number of input samples received, counting the `half_length - 1` samples of silence the
+  // stream starts with
+  received: u64,
+  // The position of the next output sample, as the index of the input sample before it and the
+  // remaining fraction of an input sample in units of `1 / output_sample_rate`
+  index: u64,
+  remainder: u32,
+}
+
+impl Resampler {
+  /// Create a resampler converting from `input_sample_rate` to `output_sample_rate` with the default
+  /// kernel of 64 input samples on each side and 100 dB of stopband attenuation.
+  ///
+  /// An error is returned if a sample rate is 0 or if the downsampling ratio stretches the kernel
+  /// beyond [`MAX_RESAMPLER_HALF_LENGTH`].
+  pub fn new(input_sample_rate: u32, output_sample_rate: u32) -> Result<Self, HannWindowError> {
+    Resampler::with_quality(input_sample_rate, output_sample_rate, DEFAULT_HALF_LENGTH, DEFAULT_ATTENUATION_DB)
+  }
+
+  /// Create a resampler with a kernel of `half_length` input samples on each side when upsampling and
+  /// `attenuation_db` decibels of stopband attenuation.
+  ///
+  /// When downsampling, the kernel is stretched by the ratio of the sample rates to keep the same
+  /// transition band relative to the output sample rate. Longer kernels give narrower transition
+  /// bands at the cost of latency and computation. An error is returned if a sample rate is 0, if
+  /// the half length is 0 or if the stretched half length exceeds [`MAX_RESAMPLER_HALF_LENGTH`].
+  pub fn with_quality(
+    input_sample_rate: u32,
+    output_sample_rate: u32,
+    half_length: usize,
+    attenuation_db: f32
+  ) -> Result<Self, HannWindowError> {
+    // Both streams must have a sample rate
+    if input_sample_rate == 0 || output_sample_rate == 0 {
+      return Err(HannWindowError::InvalidSampleRate);
+    }
+    // The kernel must cover at least one sample on each side
+    if half_length == 0 {
+      return Err(HannWindowError::WindowLengthTooSmall);
+    }
+
+    // Stretch the kernel by the downsampling ratio, and end the transition band at the lower Nyquist frequency
+    let scale = (f64::from(output_sample_rate) / f64::from(input_sample_rate)).min(1.0);
+    let half_length = ((half_length as f64) / scale).ceil();
+    // The filter bank grows with the stretched kernel and must stay of a reasonable size
+    if half_length > MAX_RESAMPLER_HALF_LENGTH as f64 {
+      return Err(HannWindowError::WindowLengthTooLarge);
+    }
+    let half_length = half_length as usize;
+    let tap_count = 2 * half_length;
+    let transition_width = (f64::from(attenuation_db) - 7.95).max(0.0) / (14.36 * ((tap_count - 1) as f64));
+    let cutoff = (0.5 * scale - transition_width / 2.0).max(0.05 * scale);
+
+    let filter_bank = design_filter_bank(half_length, cutoff, attenuation_db);
+
+    Ok(Resampler {
+      input_sample_rate,
+      output_sample_rate,
+      half_length,
+      filter_bank,
+      ring_buffer: vec![0.0; 2 * tap_count],
+      received: (half_length - 1) as u64,
+      index: (half_length - 1) as u64,
+      remainder: 0,
+    })
+  }
+
+  /// The sample rate of the input samples in Hz.
+  pub fn input_sample_rate(&self) -> u32 {
+    self.input_sample_rate
+  }
+
+  /// The sample rate of the output samples in Hz.
+  pub fn output_sample_rate(&self) -> u32 {
+    self.output_sample_rate
+  }
+
+  /// The number of output samples per input sample.
+  pub fn ratio(&self) -> f64 {
+    f64::from(self.output_sample_rate) / f64::from(self.input_sample_rate)
+  }
+
+  /// The largest number of output samples a block of `input_length` samples can complete, the
+  /// length `output` must have in [`Resampler::process`].
+  pub fn max_output_length(&self, input_length: usize) -> usize {
+    // The output instants are `input_sample_rate / output_sample_rate` input samples apart
+    let scaled = (input_length as u64).saturating_mul(u64::from(self.output_sample_rate));
+    usize::try_from(scaled.div_ceil(u64::from(self.input_sample_rate))).unwrap_or(usize::MAX).saturating_add(1)
+  }
+
+  /// Resample a block of input samples and write the output samples it completes to the start of
+  /// `output`.
+  ///
+  /// Blocks of any length can be given, the output does not depend on how the input is split. Returns
+  /// the number of written samples. An error is returned if `output` is shorter than
+  /// [`Resampler::max_output_length`] of the block, and the block is not consumed. This does not
+  /// allocate.
+  pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<usize, HannWindowError> {
+    // Every output sample the block may complete must have its place
+    if output.len() < self.max_output_length(input.len()) {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    let mut count = 0;
+    for &sample in input {
+      self.push(sample);
+      // Compute every output sample whose kernel is covered by the received samples
+      while self.index + (self.half_length as u64) < self.received {
+        output[count] = self.interpolate();
+        self.advance();
+        count += 1;
+      }
+    }
+    Ok(count)
+  }
+
+  /// The largest number of output samples [`Resampler::flush`] writes.
+  pub fn max_flush_length(&self) -> usize {
+    self.max_output_length(self.half_length)
+  }
+
+  /// Write the output samples still depending on the last received samples to the start of `output`
+  /// and reset the resampler.
+  ///
+  /// The input is padded with silence past its end, and the output stops at the instant of the end
+  /// of the input. Returns the number of written samples. An error is returned if `output` is shorter
+  /// than [`Resampler::max_flush_length`], and the resampler is left untouched.
+  pub fn flush(&mut self, output: &mut [f32]) -> Result<usize, HannWindowError> {
+    // Every remaining output sample must have its place
+    if output.len() < self.max_flush_length() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    // Compute the output samples lying before the end of the input
+    let end = self.received;
+    let mut count = 0;
+    while self.index < end {
+      while self.index + (self.half_length as u64) >= self.received {
+        self.push(0.0);
+      }
+      output[count] = self.interpolate();
+      self.advance();
+      count += 1;
+    }
+
+    self.reset();
+    Ok(count)
+  }
+
+  /// Discard every buffered sample and start over.
+  pub fn reset(&mut self) {
+    self.ring_buffer.fill(0.0);
+    self.received = (self.half_length - 1) as u64;
+    self.index = (self.half_length - 1) as u64;
+    self.remainder = 0;
+  }
+
+  // Append an input sample to the ring buffer, overwriting the oldest one
+  fn push(&mut self, sample: f32) {
+    let tap_count = 2 * self.half_length;
+    let slot = (self.received % (tap_count as u64)) as usize;
+    self.ring_buffer[slot] = sample;
+    self.ring_buffer[slot + tap_count] = sample;
+    self.received += 1;
+  }
+
+  // Move the position to the next output sample, exactly
+  fn advance(&mut self) {
+    let remainder = u64::from(self.remainder) + u64::from(self.input_sample_rate);
+    let output_sample_rate = u64::from(self.output_sample_rate);
+    self.index += remainder / output_sample_rate;
+    self.remainder = (remainder % output_sample_rate) as u32;
+  }
+
+  // Evaluate the output sample at the current position
+  fn interpolate(&self) -> f32 {
+    let tap_count = 2 * self.half_length;
+    // The kernel ends at the last received sample, and its first sample follows it in the ring buffer
+    let first = (self.received % (tap_count as u64)) as usize;
+
+    // Interpolate the kernel between the two closest fractional delays
+    let phase = (f64::from(self.remainder) * (PHASE_COUNT as f64)) / f64::from(self.output_sample_rate);
+    let row = phase as usize;
+    let weight = (phase - (row as f64)) as f32;
+    let lower = &self.filter_bank[row * tap_count..(row + 1) * tap_count];
+    let upper = &self.filter_bank[(row + 1) * tap_count..(row + 2) * tap_count];
+
+    self.ring_buffer[first..first + tap_count]
+      .iter()
+      .zip(lower.iter().zip(upper.iter()))
+      .map(|(sample, (lower, upper))| sample * (lower + (upper - lower) * weight))
+      .sum()
+  }
+}
+
+/// An output sample is produced once `half_length` input samples past its instant have been received.
+impl Latency for Resampler {
+  fn latency(&self) -> usize {
+    self.half_length
+  }
+}
+
+// Tabulate the Kaiser-windowed sinc kernel at `PHASE_COUNT + 1` fractional delays in [0, 1]
+fn design_filter_bank(half_length: usize, cutoff: f64, attenuation_db: f32) -> Vec<f32> {
+  let tap_count = 2 * half_length;
+  trace_allocation("Resampler", (PHASE_COUNT + 1) * tap_count * std::mem::size_of::<f32>());
+  let beta = f64::from(kaiser_beta(attenuation_db));
+  let normalization = bessel_i0(beta);
+
+  let mut filter_bank = vec![0.0; (PHASE_COUNT + 1) * tap_count];
+  for (phase, row) in filter_bank.chunks_exact_mut(tap_count).enumerate() {
+    let fraction = (phase as f64) / (PHASE_COUNT as f64);
+    for (k, tap) in row.iter_mut().enumerate() {
+      // The distance between the output instant and the input sample of the tap
+      let t = fraction + ((half_length - 1) as f64) - (k as f64);
+      let sinc = if t == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * t).sin() / (PI * t) };
+      // The window is centered on the output instant and spans the whole kernel
+      let x = t / (half_length as f64);
+      let window = bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / normalization;
+      *tap = (sinc * window) as f32;
+    }
+    // Every fractional delay passes 0 Hz with unit gain
+    let gain: f32 = row.iter().sum();
+    row.iter_mut().for_each(|tap| {
+      *tap /= gain;
+    });
+  }
+
+  filter_bank
+}
+
+#[cfg(test)]
+mod test_resampler {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  fn tone(frequency: f64, sample_rate: f64, length: usize) -> Vec<f32> {
+    (0..length).map(|n| ((2.0 * PI * frequency * (n as f64)) / sample_rate).sin() as f32).collect()
+  }
+
+  // Resample a block and append the output to `output`
+  fn process(resampler: &mut Resampler, input: &[f32], output: &mut Vec<f32>) {
+    let mut buffer = vec![0.0; resampler.max_output_length(input.len())];
+    let count = resampler.process(input, &mut buffer).unwrap();
+    output.extend_from_slice(&buffer[..count]);
+  }
+
+  // Flush the resampler and append the output to `output`
+  fn flush(resampler: &mut Resampler, output: &mut Vec<f32>) {
+    let mut buffer = vec![0.0; resampler.max_flush_length()];
+    let count = resampler.flush(&mut buffer).unwrap();
+    output.extend_from_slice(&buffer[..count]);
+  }
+
+  #[test]
+  fn test_resampler_tone() {
+    let mut resampler = Resampler::new(48000, 44100).unwrap();
+    let mut output = Vec::new();
+
+    process(&mut resampler, &tone(1000.0, 48000.0, 4800), &mut output);
+    flush(&mut resampler, &mut output);
+
+    // The output is the same tone sampled at the new rate
+    let expected = tone(1000.0, 44100.0, 4410);
+    assert_eq!(output.len(), 4410);
+    for i in 100..4300 {
+      assert_abs_diff_eq!(output[i], expected[i], epsilon = 1e-4);
+    }
+  }
+
+  #[test]
+  fn test_resampler_blocks() {
+    let input = tone(440.0, 8000.0, 3000);
+    let mut whole = Resampler::new(8000, 11025).unwrap();
+    let mut blocks = Resampler::new(8000, 11025).unwrap();
+
+    let mut expected = Vec::new();
+    process(&mut whole, &input, &mut expected);
+    let mut output = Vec::new();
+    for block in input.chunks(37) {
+      process(&mut blocks, block, &mut output);
+    }
+
+    assert_eq!(output, expected);
+    assert_abs_diff_eq!(whole.ratio(), 11025.0 / 8000.0, epsilon = 1e-12);
+  }
+
+  #[test]
+  fn test_resampler_downsampling_rejects_aliases() {
+    let mut resampler = Resampler::new(48000, 16000).unwrap();
+    let mut output = Vec::new();
+
+    // A tone above the output Nyquist frequency would alias to 4 kHz
+    process(&mut resampler, &tone(12000.0, 48000.0, 9600), &mut output);
+
+    let rms = (output[200..].iter().map(|sample| sample * sample).sum::<f32>() / ((output.len() - 200) as f32)).sqrt();
+    assert!(rms < 1e-4);
+  }
+
+  #[test]
+  fn test_resampler_identity_ratio() {
+    let input = tone(1000.0, 8000.0, 500);
+    let mut resampler = Resampler::new(8000, 8000).unwrap();
+    let mut output = Vec::new();
+
+    process(&mut resampler, &input, &mut output);
+    flush(&mut resampler, &mut output);
+
+    // Away from the onset of the tone, the signal is passed through
+    assert_eq!(output.len(), input.len());
+    for i in 100..400 {
+      assert_abs_diff_eq!(output[i], input[i], epsilon = 1e-5);
+    }
+    assert_eq!(resampler.latency(), 64);
+  }
+
+  #[test]
+  fn test_resampler_invalid_parameters() {
+    assert_eq!(Resampler::new(0, 8000).unwrap_err(), HannWindowError::InvalidSampleRate);
+    assert_eq!(Resampler::new(8000, 0).unwrap_err(), HannWindowError::InvalidSampleRate);
+    assert_eq!(Resampler::with_quality(8000, 16000, 0, 80.0).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(Resampler::new(100_000, 1).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+    assert_eq!(Resampler::new(257, 1).unwrap_err(), HannWindowError::WindowLengthTooLarge);
+    assert!(Resampler::new(256, 1).is_ok());
+    assert_eq!(
+      Resampler::with_quality(8000, 8000, MAX_RESAMPLER_HALF_LENGTH + 1, 80.0).unwrap_err(),
+      HannWindowError::WindowLengthTooLarge
+    );
+    let mut resampler = Resampler::new(8000, 16000).unwrap();
+    assert_eq!(resampler.process(&[0.0; 100], &mut [0.0; 200]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(resampler.flush(&mut [0.0; 10]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
@@ -0,0 +1,81 @@
+use crate::hann_window::HannWindowError;
+
+/// The encoding of raw PCM samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SampleFormat {
+  /// 32-bit little-endian IEEE 754 floating point samples.
+  F32Le,
+  /// 16-bit little-endian signed integer samples, scaled to [-1.0, 1.0).
+  I16Le,
+}
+
+impl SampleFormat {
+  /// The number of bytes occupied by one sample.
+  pub fn bytes_per_sample(&self) -> usize {
+    match self {
+      SampleFormat::F32Le => 4,
+      SampleFormat::I16Le => 2,
+    }
+  }
+}
+
+/// Decode raw PCM bytes into `f32` samples.
+///
+/// This function takes a slice `bytes` holding whole samples encoded as `format` and writes one `f32`
+/// sample per encoded sample into `samples`. An error is returned if the number of bytes is not a
+/// multiple of the sample size or if `samples` does not hold exactly the decoded samples.
+pub fn decode_samples(bytes: &[u8], format: SampleFormat, samples: &mut [f32]) -> Result<(), HannWindowError> {
+  // The bytes must contain whole samples and the output must hold all of them
+  let bytes_per_sample = format.bytes_per_sample();
+  if !bytes.len().is_multiple_of(bytes_per_sample) || bytes.len() / bytes_per_sample != samples.len() {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Convert every encoded sample to an `f32` sample
+  for (sample, encoded) in samples.iter_mut().zip(bytes.chunks_exact(bytes_per_sample)) {
+    *sample = match format {
+      SampleFormat::F32Le => f32::from_le_bytes([encoded[0], encoded[1], encoded[2], encoded[3]]),
+      SampleFormat::I16Le => i16_to_f32(i16::from_le_bytes([encoded[0], encoded[1]])),
+    };
+  }
+
+  Ok(())
+}
+
+/// Convert a 16-bit integer sample to an `f32` sample in [-1.0, 1.0).
+pub fn i16_to_f32(sample: i16) -> f32 {
+  (sample as f32) / 32768.0
+}
+
+#[cfg(test)]
+mod test_sample_format {
+  use super::*;
+
+  #[test]
+  fn test_decode_samples_f32() {
+    let bytes: Vec<u8> = [0.5f32, -1.25].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let mut samples = [0.0; 2];
+
+    decode_samples(&bytes, SampleFormat::F32Le, &mut samples).unwrap();
+
+    assert_eq!(samples, [0.5, -1.25]);
+  }
+
+  #[test]
+  fn test_decode_samples_i16() {
+    let bytes: Vec<u8> = [i16::MIN, 0, 16384].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let mut samples = [1.0; 3];
+
+    decode_samples(&bytes, SampleFormat::I16Le, &mut samples).unwrap();
+
+    assert_eq!(samples, [-1.0, 0.0, 0.5]);
+  }
+
+  #[test]
+  fn test_decode_samples_length_mismatch() {
+    assert_eq!(decode_samples(&[0; 3], SampleFormat::I16Le, &mut [0.0; 1]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(decode_samples(&[0; 4], SampleFormat::I16Le, &mut [0.0; 1]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
@@ -0,0 +1,119 @@
+use crate::{ frames::reflect_index, hann_window::HannWindowError, window_spec::WindowSpec };
+
+/// Handling of the samples of the kernel reaching past the edges of the signal in [`smooth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EdgeMode {
+  /// The signal is mirrored around its edges, without repeating the edge sample.
+  Reflect,
+  /// The edge samples are repeated past the edges.
+  Extend,
+  /// The kernel is cut at the edges and renormalized over the samples it still covers.
+  Shrink,
+}
+
+/// Smooth a signal by convolving it with a normalized window kernel.
+///
+/// This function takes the `signal`, the `kernel_length` and the `window` shape of the kernel, and
+/// returns the weighted moving average of the signal, with the same length. The kernel is normalized
+/// to unit sum, so that constant signals are left unchanged, and centered on each sample; kernels of
+/// even length lag by half a sample. The `edge_mode` sets how the kernel is applied near the edges.
+/// Note that windows ending with zeros, like the Hann window, only weight `kernel_length - 2` samples.
+/// An error is returned if the kernel length is less than or equal to 1 or too large, or if the kernel
+/// sums to 0.
+pub fn smooth(signal: &[f32], kernel_length: usize, window: WindowSpec, edge_mode: EdgeMode) -> Result<Vec<f32>, HannWindowError> {
+  let kernel = window.generate(kernel_length)?;
+  let kernel_sum: f64 = kernel.iter().map(|&weight| f64::from(weight)).sum();
+  // A kernel without weight cannot be normalized
+  if kernel_sum <= 0.0 {
+    return Err(HannWindowError::WindowLengthTooSmall);
+  }
+  let center = ((kernel_length - 1) / 2) as isize;
+  let signal_length = signal.len() as isize;
+
+  let smoothed = (0..signal_length)
+    .map(|i| {
+      let mut sum = 0.0;
+      let mut weight_sum = 0.0;
+      for (j, &weight) in kernel.iter().enumerate() {
+        let index = i + (j as isize) - center;
+        let sample = if (0..signal_length).contains(&index) {
+          signal[index as usize]
+        } else {
+          match edge_mode {
+            EdgeMode::Reflect => signal[reflect_index(index, signal.len())],
+            EdgeMode::Extend => signal[index.clamp(0, signal_length - 1) as usize],
+            // The samples past the edges are left out of the average
+            EdgeMode::Shrink => {
+              continue;
+            }
+          }
+        };
+        sum += f64::from(weight) * f64::from(sample);
+        weight_sum += f64::from(weight);
+      }
+      // Keep the sample when only the zero weights of the kernel cover the signal
+      if weight_sum > 0.0 { (sum / weight_sum) as f32 } else { signal[i as usize] }
+    })
+    .collect();
+
+  Ok(smoothed)
+}
+
+#[cfg(test)]
+mod test_smooth {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  const SIGNAL: [f32; 6] = [1.0, 3.0, 2.0, 5.0, 4.0, 6.0];
+
+  #[test]
+  fn test_smooth_interior() {
+    // The Hann kernel of length 5 weights the neighbors by 0.5 and the sample by 1
+    let smoothed = smooth(&SIGNAL, 5, WindowSpec::Hann, EdgeMode::Shrink).unwrap();
+
+    assert_eq!(smoothed.len(), SIGNAL.len());
+    assert_abs_diff_eq!(smoothed[2], (3.0 * 0.5 + 2.0 + 5.0 * 0.5) / 2.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(smoothed[3], (2.0 * 0.5 + 5.0 + 4.0 * 0.5) / 2.0, epsilon = 1e-6);
+  }
+
+  #[test]
+  fn test_smooth_edge_modes() {
+    let reflect = smooth(&SIGNAL, 3, WindowSpec::Rectangular, EdgeMode::Reflect).unwrap();
+    let extend = smooth(&SIGNAL, 3, WindowSpec::Rectangular, EdgeMode::Extend).unwrap();
+    let shrink = smooth(&SIGNAL, 3, WindowSpec::Rectangular, EdgeMode::Shrink).unwrap();
+
+    // Before the first sample, the signal is mirrored, repeated or left out
+    assert_abs_diff_eq!(reflect[0], (3.0 + 1.0 + 3.0) / 3.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(extend[0], (1.0 + 1.0 + 3.0) / 3.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(shrink[0], (1.0 + 3.0) / 2.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(reflect[5], (4.0 + 6.0 + 4.0) / 3.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(extend[5], (4.0 + 6.0 + 6.0) / 3.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(shrink[5], (4.0 + 6.0) / 2.0, epsilon = 1e-6);
+    for i in 1..5 {
+      assert_eq!(reflect[i], extend[i]);
+      assert_eq!(reflect[i], shrink[i]);
+    }
+  }
+
+  #[test]
+  fn test_smooth_constant_signal() {
+    let signal = [2.5; 10];
+
+    for edge_mode in [EdgeMode::Reflect, EdgeMode::Extend, EdgeMode::Shrink] {
+      for sample in smooth(&signal, 7, WindowSpec::Blackman, edge_mode).unwrap() {
+        assert_abs_diff_eq!(sample, 2.5, epsilon = 1e-6);
+      }
+    }
+    assert!(smooth(&[], 3, WindowSpec::Hann, EdgeMode::Reflect).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_smooth_invalid_kernel() {
+    assert_eq!(smooth(&SIGNAL, 1, WindowSpec::Hann, EdgeMode::Reflect).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    // The Hann window of length 2 is all zeros
+    assert_eq!(smooth(&SIGNAL, 2, WindowSpec::Hann, EdgeMode::Reflect).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
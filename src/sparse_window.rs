@@ -0,0 +1,190 @@
+use std::ops::Range;
+
+use crate::{ apply_window::multiply_by_window, hann_window::HannWindowError };
+
+// The shortest run of zeros or ones handled as a flat region, shorter runs are multiplied
+const MIN_FLAT_RUN: usize = 32;
+
+// The kind of a region of a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+  Zeros,
+  Ones,
+  Taper,
+}
+
+/// A window split into its flat and tapered regions, applied by multiplying the tapers only.
+///
+/// Windows with long runs of exact zeros or ones, like Tukey windows with a small `alpha`,
+/// Planck-taper windows or windows shifted within a zero-padded frame, are applied by zeroing the
+/// zero regions, leaving or copying the regions of ones, and multiplying only the tapered regions. A
+/// 1M-sample record with 1% tapers thus costs about as much as zeroing and copying it. The regions
+/// are found once, when the window is split. Samples in zero regions are set to 0 even when they are
+/// not finite, unlike with [`crate::windows::apply_window`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseWindow {
+  window: Vec<f32>,
+  regions: Vec<(Region, Range<usize>)>,
+}
+
+impl SparseWindow {
+  /// Split `window` into its regions of zeros, ones and tapers.
+  ///
+  /// Runs of zeros or ones shorter than 32 values are counted as tapers, since skipping them saves
+  /// less than the bookkeeping costs.
+  pub fn new(window: &[f32]) -> Self {
+    let kind = |value: f32| {
+      if value == 0.0 {
+        Region::Zeros
+      } else if value == 1.0 {
+        Region::Ones
+      } else {
+        Region::Taper
+      }
+    };
+
+    let mut regions: Vec<(Region, Range<usize>)> = Vec::new();
+    let mut start = 0;
+    while start < window.len() {
+      let region = kind(window[start]);
+      let end = window[start..].iter().position(|&value| kind(value) != region).map_or(window.len(), |length| start + length);
+      // Short flat runs are multiplied along with the tapers around them
+      let region = if region != Region::Taper && end - start < MIN_FLAT_RUN { Region::Taper } else { region };
+      match regions.last_mut() {
+        Some((Region::Taper, range)) if region == Region::Taper => range.end = end,
+        _ => regions.push((region, start..end)),
+      }
+      start = end;
+    }
+
+    SparseWindow { window: window.to_vec(), regions }
+  }
+
+  /// The number of window values.
+  pub fn window_length(&self) -> usize {
+    self.window.len()
+  }
+
+  /// The window values.
+  pub fn window(&self) -> &[f32] {
+    &self.window
+  }
+
+  /// The fraction of the window values in tapered regions, which are multiplied.
+  pub fn taper_fraction(&self) -> f32 {
+    let tapered: usize = self.regions.iter().filter(|(region, _)| *region == Region::Taper).map(|(_, range)| range.len()).sum();
+    (tapered as f32) / (self.window.len().max(1) as f32)
+  }
+
+  /// Multiply a signal by the window in place.
+  ///
+  /// An error is returned if the length of the signal is different from the window length, and the
+  /// signal is left untouched.
+  pub fn apply(&self, signal: &mut [f32]) -> Result<(), HannWindowError> {
+    // The window must cover the signal sample for sample
+    if signal.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    for (region, range) in &self.regions {
+      match region {
+        Region::Zeros => signal[range.clone()].fill(0.0),
+        // Multiplying by one leaves the samples as they are
+        Region::Ones => {}
+        Region::Taper => multiply_by_window(&mut signal[range.clone()], &self.window[range.clone()]),
+      }
+    }
+    Ok(())
+  }
+
+  /// Write the windowed `input` to `output`.
+  ///
+  /// An error is returned if the length of the input or the output is different from the window
+  /// length.
+  pub fn apply_to(&self, input: &[f32], output: &mut [f32]) -> Result<(), HannWindowError> {
+    // The window must cover both buffers sample for sample
+    if input.len() != self.window.len() || output.len() != self.window.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    for (region, range) in &self.regions {
+      match region {
+        Region::Zeros => output[range.clone()].fill(0.0),
+        Region::Ones => output[range.clone()].copy_from_slice(&input[range.clone()]),
+        Region::Taper => {
+          output[range.clone()].copy_from_slice(&input[range.clone()]);
+          multiply_by_window(&mut output[range.clone()], &self.window[range.clone()]);
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test_sparse_window {
+  use crate::{ apply_window::apply_window, window_spec::WindowSpec };
+
+  use super::*;
+
+  // A signal without zeros, so that every region is visible in the windowed signal
+  fn signal(length: usize) -> Vec<f32> {
+    (0..length).map(|n| 1.5 + ((n as f32) * 0.01).sin()).collect()
+  }
+
+  #[test]
+  fn test_sparse_window_tukey() {
+    let window = WindowSpec::Tukey { alpha: 0.02 }.generate(100_000).unwrap();
+    let sparse = SparseWindow::new(&window);
+    let mut expected = signal(100_000);
+    let mut windowed = expected.clone();
+
+    apply_window(&mut expected, &window).unwrap();
+    sparse.apply(&mut windowed).unwrap();
+
+    // Only the 1% tapers at either end are multiplied, to the same result
+    assert!((0.019..0.021).contains(&sparse.taper_fraction()));
+    assert_eq!(windowed, expected);
+  }
+
+  #[test]
+  fn test_sparse_window_shifted() {
+    // A Hann window shifted within a zero-padded frame
+    let mut window = vec![0.0; 1000];
+    window[300..556].copy_from_slice(&WindowSpec::Hann.generate(256).unwrap());
+    let sparse = SparseWindow::new(&window);
+    let input = signal(1000);
+    let mut expected = input.clone();
+    let mut output = vec![f32::NAN; 1000];
+
+    apply_window(&mut expected, &window).unwrap();
+    sparse.apply_to(&input, &mut output).unwrap();
+
+    assert_eq!(output, expected);
+    // The zero end points of the Hann window join the zero regions
+    assert!((0.253..0.255).contains(&sparse.taper_fraction()));
+  }
+
+  #[test]
+  fn test_sparse_window_short_runs() {
+    // The runs of ones are too short to skip, and every value is multiplied
+    let window = [0.5, 1.0, 1.0, 0.5, 1.0, 0.0, 0.25];
+    let sparse = SparseWindow::new(&window);
+    let mut windowed = [2.0; 7];
+
+    sparse.apply(&mut windowed).unwrap();
+
+    assert_eq!(sparse.taper_fraction(), 1.0);
+    assert_eq!(windowed, [1.0, 2.0, 2.0, 1.0, 2.0, 0.0, 0.5]);
+  }
+
+  #[test]
+  fn test_sparse_window_length_mismatch() {
+    let sparse = SparseWindow::new(&[1.0; 64]);
+    let mut signal = [2.0; 63];
+
+    assert_eq!(sparse.apply(&mut signal).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(sparse.apply_to(&[1.0; 64], &mut signal).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(signal, [2.0; 63]);
+  }
+}
@@ -0,0 +1,186 @@
+use realfft::RealFftPlanner;
+
+use crate::{ frames::{ frames, PaddingMode }, hann_window::{ check_window_length, HannWindowError }, real_fft::WindowedRealFft };
+
+/// Estimate the power spectral density of a signal with Welch's method.
+///
+/// This function takes the `signal`, the `window` setting the length and the taper of the segments and
+/// the `hop_size` between segments, and returns the one-sided density of the `window.len() / 2 + 1`
+/// bins from 0 Hz to the Nyquist frequency, bin `k` being the frequency `k / window.len()` cycles per
+/// sample. The periodograms of the segments lying entirely inside the signal are averaged and scaled
+/// by the power of the window, so that the density summed over the bins and divided by the window
+/// length is the mean power of the signal. Divide the density by the sample rate for a density per Hz.
+/// The segments are transformed by a [`WindowedRealFft`]. An error is returned if the window length is
+/// less than or equal to 1, if the hop size is 0, or if the signal is shorter than the window.
+pub fn welch(signal: &[f32], window: &[f32], hop_size: usize) -> Result<Vec<f32>, HannWindowError> {
+  let mut fft = WindowedRealFft::new(window.len())?.with_window(window)?;
+  let mut segments = frames(signal, window.len(), hop_size, PaddingMode::None)?;
+  // At least one segment must fit in the signal
+  let segment_count = segments.len();
+  if segment_count == 0 {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // Sum the periodograms of the segments
+  let mut density = vec![0.0f64; fft.bin_count()];
+  let mut segment = vec![0.0; window.len()];
+  while segments.next_into(&mut segment)? {
+    for (power, bin) in density.iter_mut().zip(fft.process(&segment)?.iter()) {
+      *power += f64::from(bin.norm_sqr());
+    }
+  }
+
+  // Average the periodograms, scale them by the window power and fold the negative frequencies over
+  let window_power: f64 = window.iter().map(|&value| f64::from(value) * f64::from(value)).sum();
+  let nyquist_bin = window.len().is_multiple_of(2).then_some(window.len() / 2);
+  let density = density
+    .iter()
+    .enumerate()
+    .map(|(k, power)| {
+      let sides = if k == 0 || Some(k) == nyquist_bin { 1.0 } else { 2.0 };
+      ((sides * power) / ((segment_count as f64) * window_power)) as f32
+    })
+    .collect();
+
+  Ok(density)
+}
+
+/// Compute the power spectrogram of a signal.
+///
+/// This function takes the `signal`, the `window` setting the length and the taper of the frames, the
+/// `hop_size` between frames and the `padding_mode` of the edges, see [`frames`], and returns the
+/// power `|X[k]|^2` of the `window.len() / 2 + 1` bins from 0 Hz to the Nyquist frequency of every
+/// frame, one spectrum per frame. The frames are transformed by a [`WindowedRealFft`]. An error is
+/// returned if the window length is less than or equal to 1 or if the hop size is 0.
+pub fn spectrogram(
+  signal: &[f32],
+  window: &[f32],
+  hop_size: usize,
+  padding_mode: PaddingMode
+) -> Result<Vec<Vec<f32>>, HannWindowError> {
+  let mut fft = WindowedRealFft::new(window.len())?.with_window(window)?;
+  let mut frames = frames(signal, window.len(), hop_size, padding_mode)?;
+
+  let mut spectra = Vec::with_capacity(frames.len());
+  let mut frame = vec![0.0; window.len()];
+  while frames.next_into(&mut frame)? {
+    spectra.push(fft.process(&frame)?.iter().map(|bin| bin.norm_sqr()).collect());
+  }
+
+  Ok(spectra)
+}
+
+/// Compute the magnitude response of a window in decibels.
+///
+/// This function takes the `window` and the `fft_length` it is zero-padded to, and returns the
+/// magnitude of the `fft_length / 2 + 1` bins from 0 Hz to the Nyquist frequency, bin `k` being the
+/// frequency `k / fft_length` cycles per sample, in decibels relative to the largest bin. A longer FFT
+/// samples the response more finely, e.g. 16 times the window length to resolve the sidelobes. Bins of
+/// zero magnitude are clamped to the smallest positive `f32` instead of minus infinity. An error is
+/// returned if the FFT length is less than or equal to 1 or shorter than the window.
+pub fn frequency_response(window: &[f32], fft_length: usize) -> Result<Vec<f32>, HannWindowError> {
+  check_window_length(fft_length)?;
+  // The window must fit in the transform
+  if window.len() > fft_length {
+    return Err(HannWindowError::LengthMismatch);
+  }
+
+  // The window is transformed as is, zero-padded to the FFT length
+  let fft = RealFftPlanner::<f32>::new().plan_fft_forward(fft_length);
+  let mut frame = fft.make_input_vec();
+  frame[..window.len()].copy_from_slice(window);
+  let mut spectrum = fft.make_output_vec();
+  fft.process(&mut frame, &mut spectrum).expect("The buffers are made by the plan");
+
+  // Normalize the magnitudes by the largest one
+  let magnitudes: Vec<f32> = spectrum.iter().map(|bin| bin.norm()).collect();
+  let peak = magnitudes.iter().copied().fold(f32::MIN_POSITIVE, f32::max);
+  let response = magnitudes.iter().map(|magnitude| 20.0 * (magnitude.max(f32::MIN_POSITIVE) / peak).log10()).collect();
+
+  Ok(response)
+}
+
+#[cfg(test)]
+mod test_spectral {
+  use std::f32::consts::PI;
+
+  use approx::assert_abs_diff_eq;
+
+  use crate::hann_window::get_hann_window;
+
+  use super::*;
+
+  #[test]
+  fn test_welch_tone_power() {
+    let signal: Vec<f32> = (0..4096).map(|n| 2.0 * ((2.0 * PI * 0.125 * (n as f32)) + 0.3).cos()).collect();
+    let window = get_hann_window(256).unwrap();
+
+    let density = welch(&signal, &window, 128).unwrap();
+
+    // The density peaks at the tone and sums to its mean power of 2
+    assert_eq!(density.len(), 129);
+    let peak = density.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert_eq!(peak, 32);
+    assert_abs_diff_eq!(density.iter().sum::<f32>() / 256.0, 2.0, epsilon = 1e-3);
+  }
+
+  #[test]
+  fn test_welch_white_noise() {
+    // A deterministic uniform noise with a mean power of 1 / 3
+    let mut state = 12345u32;
+    let signal: Vec<f32> = (0..65536)
+      .map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state as f32) / (u32::MAX as f32) * 2.0 - 1.0
+      })
+      .collect();
+
+    let density = welch(&signal, &get_hann_window(128).unwrap(), 64).unwrap();
+
+    // The one-sided density of white noise is flat at twice its power
+    for power in &density[1..64] {
+      assert_abs_diff_eq!(*power, 2.0 / 3.0, epsilon = 0.15);
+    }
+  }
+
+  #[test]
+  fn test_spectrogram_frames() {
+    let signal: Vec<f32> = (0..1000).map(|n| (2.0 * PI * 0.25 * (n as f32)).sin()).collect();
+    let window = get_hann_window(64).unwrap();
+
+    let spectra = spectrogram(&signal, &window, 32, PaddingMode::CenterZero).unwrap();
+
+    assert_eq!(spectra.len(), frames(&signal, 64, 32, PaddingMode::CenterZero).unwrap().len());
+    assert!(spectra.iter().all(|spectrum| spectrum.len() == 33));
+    // Away from the edges, every frame peaks at the tone
+    let peak = spectra[10].iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert_eq!(peak, 16);
+  }
+
+  #[test]
+  fn test_frequency_response() {
+    let window = get_hann_window(64).unwrap();
+
+    let response = frequency_response(&window, 1024).unwrap();
+
+    // The response peaks at 0 Hz, and the first sidelobe of the Hann window is 31.5 dB down
+    assert_eq!(response.len(), 513);
+    assert_eq!(response[0], 0.0);
+    let first_null = (1..response.len()).find(|&k| response[k] > response[k - 1]).unwrap();
+    let sidelobe = response[first_null..].iter().copied().fold(f32::MIN, f32::max);
+    assert_abs_diff_eq!(sidelobe, -31.5, epsilon = 0.3);
+  }
+
+  #[test]
+  fn test_spectral_invalid_parameters() {
+    let window = get_hann_window(64).unwrap();
+
+    assert_eq!(welch(&[0.0; 32], &window, 16).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(welch(&[0.0; 128], &window, 0).unwrap_err(), HannWindowError::InvalidHopSize);
+    assert_eq!(spectrogram(&[0.0; 128], &[1.0], 16, PaddingMode::None).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(frequency_response(&window, 32).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(frequency_response(&window, 1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+}
@@ -0,0 +1,24 @@
+//! The short-time analysis and synthesis of signals.
+//!
+//! This module gathers framing, frame length rounding, overlap-add resynthesis, the mel filterbank
+//! and, with the `rustfft`, `realfft` and `microfft` features, the windowed transforms and the spectral
+//! estimates built on them, and with the `toml` and `yaml` features, the analysis pipelines built from
+//! configuration files. Without the `std` feature, only the `MicroWindowedFft` of the `microfft`
+//! feature is available.
+
+#[cfg(feature = "std")]
+pub use crate::{
+  fft_length::{ nearest_power_of_two, next_fast_length, LengthAdjustment, LengthRounding },
+  frames::{ frames, get_frame_count, Frames, PaddingMode },
+  mel::{ hz_to_mel, mel_to_hz, MelFilterbank },
+  normalization::{ get_window_envelope, normalize_by_envelope },
+  overlap_add::{ GainCompensation, OverlapAdd },
+};
+#[cfg(feature = "rustfft")]
+pub use crate::fft::WindowedFft;
+#[cfg(feature = "microfft")]
+pub use crate::micro_fft::{ MicroFftFrame, MicroWindowedFft };
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub use crate::pipeline::{ AnalysisConfig, FramingConfig, OutputConfig, Pipeline, PipelineConfig, PipelineError, SampleCount };
+#[cfg(feature = "realfft")]
+pub use crate::{ real_fft::WindowedRealFft, spectral::{ frequency_response, spectrogram, welch } };
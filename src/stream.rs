@@ -0,0 +1,215 @@
+use std::{ collections::VecDeque, pin::Pin, task::{ Context, Poll } };
+
+use futures_core::{ stream::FusedStream, Stream };
+
+use crate::{ latency::Latency, streaming_windower::StreamingWindower };
+
+/// A chunk of samples produced by a stream, either a single sample or a buffer of samples.
+pub trait SampleChunk {
+  /// The samples of the chunk.
+  fn samples(&self) -> &[f32];
+}
+
+impl SampleChunk for f32 {
+  fn samples(&self) -> &[f32] {
+    std::slice::from_ref(self)
+  }
+}
+
+impl SampleChunk for Vec<f32> {
+  fn samples(&self) -> &[f32] {
+    self
+  }
+}
+
+impl SampleChunk for Box<[f32]> {
+  fn samples(&self) -> &[f32] {
+    self
+  }
+}
+
+impl SampleChunk for &[f32] {
+  fn samples(&self) -> &[f32] {
+    self
+  }
+}
+
+impl<const N: usize> SampleChunk for [f32; N] {
+  fn samples(&self) -> &[f32] {
+    self
+  }
+}
+
+/// A stream of windowed frames produced from a stream of samples or sample chunks.
+///
+/// Created by [`WindowedStreamExt::windowed_frames`]. The frames are produced by a [`StreamingWindower`],
+/// so they do not depend on how the input stream is chunked. The input stream is only polled once every
+/// frame completed by the previous chunk has been consumed, so a slow consumer applies backpressure to
+/// the input chunk by chunk. Every frame completed by one chunk is buffered at once, about
+/// `chunk_length / hop_size` frames, so the memory held is bounded by the chunk length of the input
+/// and not by its total length, and long chunks should be split to keep it small.
+///
+/// The input stream must be `Unpin` to be polled. The stream is fused: once the input stream has ended
+/// it is never polled again, and every later poll returns `None`.
+#[derive(Debug)]
+pub struct WindowedFrames<S> {
+  stream: S,
+  windower: StreamingWindower,
+  pending_frames: VecDeque<Vec<f32>>,
+  finished: bool,
+}
+
+impl<S> WindowedFrames<S> {
+  /// Create a stream of frames cut from `stream` by `windower`.
+  pub fn new(stream: S, windower: StreamingWindower) -> Self {
+    WindowedFrames { stream, windower, pending_frames: VecDeque::new(), finished: false }
+  }
+
+  /// The windower producing the frames.
+  pub fn windower(&self) -> &StreamingWindower {
+    &self.windower
+  }
+
+  /// Consume the adapter and return the wrapped stream.
+  pub fn into_inner(self) -> S {
+    self.stream
+  }
+}
+
+impl<S> Stream for WindowedFrames<S> where S: Stream + Unpin, S::Item: SampleChunk {
+  type Item = Vec<f32>;
+
+  fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = &mut *self;
+    loop {
+      // Hand out the frames completed by the previous chunk first
+      if let Some(frame) = this.pending_frames.pop_front() {
+        return Poll::Ready(Some(frame));
+      }
+
+      // Never poll the input stream again once it has ended
+      if this.finished {
+        return Poll::Ready(None);
+      }
+
+      // Only pull more samples once every completed frame has been consumed
+      match Pin::new(&mut this.stream).poll_next(context) {
+        Poll::Ready(Some(chunk)) => {
+          let pending_frames = &mut this.pending_frames;
+          this.windower.process(chunk.samples(), |frame| pending_frames.push_back(frame.to_vec()));
+        }
+        Poll::Ready(None) => {
+          this.finished = true;
+          return Poll::Ready(None);
+        }
+        Poll::Pending => {
+          return Poll::Pending;
+        }
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let length = self.pending_frames.len();
+    (length, self.finished.then_some(length))
+  }
+}
+
+impl<S> FusedStream for WindowedFrames<S> where S: Stream + Unpin, S::Item: SampleChunk {
+  fn is_terminated(&self) -> bool {
+    self.finished && self.pending_frames.is_empty()
+  }
+}
+
+impl<S> Latency for WindowedFrames<S> {
+  fn latency(&self) -> usize {
+    self.windower.latency()
+  }
+
+  fn group_delay(&self) -> f64 {
+    self.windower.group_delay()
+  }
+}
+
+/// An extension trait turning streams of samples into streams of windowed frames.
+pub trait WindowedStreamExt: Stream + Sized {
+  /// Cut the samples of this stream into frames with `windower`.
+  ///
+  /// The frames can only be polled if the stream is `Unpin`, wrap it with `Box::pin` or
+  /// `std::pin::pin!` otherwise.
+  fn windowed_frames(self, windower: StreamingWindower) -> WindowedFrames<Self> {
+    WindowedFrames::new(self, windower)
+  }
+}
+
+impl<S> WindowedStreamExt for S where S: Stream + Sized, S::Item: SampleChunk {}
+
+#[cfg(test)]
+mod test_stream {
+  use futures::{ executor::block_on, stream, StreamExt };
+
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  #[test]
+  fn test_windowed_frames_from_samples() {
+    let signal: Vec<f32> = (0..40).map(|i| i as f32).collect();
+    let window = get_hann_window(8).unwrap();
+    let windower = StreamingWindower::new(8, 4).unwrap().with_window(&window).unwrap();
+
+    let emitted: Vec<Vec<f32>> = block_on(stream::iter(signal.clone()).windowed_frames(windower).collect());
+    let expected: Vec<Vec<f32>> = frames(&signal, 8, 4, PaddingMode::None)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(emitted, expected);
+  }
+
+  #[test]
+  fn test_windowed_frames_from_chunks() {
+    let chunks = vec![vec![1.0; 3], vec![2.0; 10], vec![], vec![3.0; 3]];
+    let windower = StreamingWindower::new(4, 4).unwrap();
+
+    let emitted: Vec<Vec<f32>> = block_on(stream::iter(chunks).windowed_frames(windower).collect());
+
+    assert_eq!(emitted, vec![vec![1.0, 1.0, 1.0, 2.0], vec![2.0; 4], vec![2.0, 2.0, 2.0, 2.0], vec![2.0, 3.0, 3.0, 3.0]]);
+  }
+
+  #[test]
+  fn test_windowed_frames_backpressure() {
+    let windower = StreamingWindower::new(2, 1).unwrap();
+    let mut frames = stream::iter(vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0]]).windowed_frames(windower);
+
+    // The second chunk is not pulled while frames of the first one are pending
+    assert_eq!(block_on(frames.next()), Some(vec![1.0, 2.0]));
+    assert_eq!(frames.size_hint().0, 2);
+    assert_eq!(block_on(frames.next()), Some(vec![2.0, 3.0]));
+    assert_eq!(block_on(frames.next()), Some(vec![3.0, 4.0]));
+    assert_eq!(block_on(frames.next()), Some(vec![4.0, 5.0]));
+    assert_eq!(block_on(frames.next()), None);
+  }
+
+  #[test]
+  fn test_windowed_frames_fused() {
+    let windower = StreamingWindower::new(2, 2).unwrap();
+    // An input stream panicking if it is polled after it has ended
+    let mut polls = 0;
+    let input = stream::poll_fn(move |_| {
+      polls += 1;
+      assert!(polls <= 2, "The input stream was polled after it had ended");
+      Poll::Ready((polls == 1).then(|| vec![1.0, 2.0]))
+    });
+    let mut frames = input.windowed_frames(windower);
+
+    assert!(!frames.is_terminated());
+    assert_eq!(block_on(frames.next()), Some(vec![1.0, 2.0]));
+    assert_eq!(block_on(frames.next()), None);
+    assert!(frames.is_terminated());
+    assert_eq!(frames.size_hint(), (0, Some(0)));
+    assert_eq!(block_on(frames.next()), None);
+  }
+}
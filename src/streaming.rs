@@ -0,0 +1,24 @@
+//! The processing of sample streams, block by block.
+//!
+//! This module gathers the streaming windowers and processors, their latency reporting, the
+//! multichannel helpers, the resampler and, with the `futures`, `tokio` and `cpal` features, the
+//! asynchronous and device front-ends.
+
+pub use crate::{
+  downmix::{ downmix, Downmix },
+  envelope::{ EnvelopeFollower, EnvelopeMode },
+  frame_queue::{ frame_queue, FrameConsumer, FrameProducer },
+  latency::{ Latency, PipelineLatency },
+  multichannel::{ MultichannelFrame, MultichannelWindower },
+  planar::{ deinterleave, interleave, PlanarBuffer },
+  realtime::RealtimeProcessor,
+  resampler::{ Resampler, MAX_RESAMPLER_HALF_LENGTH },
+  sample_format::{ decode_samples, i16_to_f32, SampleFormat },
+  streaming_windower::{ StreamFrame, StreamingWindower },
+};
+#[cfg(feature = "tokio")]
+pub use crate::async_reader::AsyncFrameReader;
+#[cfg(feature = "cpal")]
+pub use crate::capture::{ CaptureError, LiveCapture };
+#[cfg(feature = "futures")]
+pub use crate::stream::{ SampleChunk, WindowedFrames, WindowedStreamExt };
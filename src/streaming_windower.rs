@@ -0,0 +1,499 @@
+use crate::{
+  apply_window::apply_window,
+  hann_window::HannWindowError,
+  instrument::{ record_metric, trace_allocation },
+  latency::Latency,
+};
+
+/// A frame emitted by a [`StreamingWindower`], with its position in the stream and its crossfade gain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamFrame<'a> {
+  /// The index of the first sample of the frame, counted from the start of the stream.
+  pub start: u64,
+  /// The (windowed) frame samples.
+  pub samples: &'a [f32],
+  /// The weight of the frame during a crossfaded reconfiguration, 1.0 outside of transitions.
+  ///
+  /// While a transition is running, the frames of the outgoing and the incoming configuration are
+  /// both emitted, and the gains of an outgoing and an incoming frame completed by the same sample
+  /// add up to 1.0.
+  pub gain: f32,
+  /// Whether the frame belongs to the outgoing configuration of a crossfaded reconfiguration.
+  pub outgoing: bool,
+}
+
+// The framing parameters of a streaming windower
+#[derive(Debug, Clone)]
+struct FrameConfig {
+  frame_length: usize,
+  hop_size: usize,
+  window: Option<Vec<f32>>,
+  // Number of samples left to receive before the next frame of this configuration is emitted
+  until_next_frame: usize,
+}
+
+impl FrameConfig {
+  fn new(frame_length: usize, hop_size: usize, window: Option<&[f32]>) -> Result<Self, HannWindowError> {
+    // A frame must contain at least one sample
+    if frame_length == 0 {
+      return Err(HannWindowError::InvalidFrameLength);
+    }
+    // The frames must advance by at least one sample
+    if hop_size == 0 {
+      return Err(HannWindowError::InvalidHopSize);
+    }
+    // The window must cover the frame sample for sample
+    if window.is_some_and(|window| window.len() != frame_length) {
+      return Err(HannWindowError::LengthMismatch);
+    }
+
+    Ok(FrameConfig { frame_length, hop_size, window: window.map(<[f32]>::to_vec), until_next_frame: frame_length })
+  }
+}
+
+/// A windower turning a stream of arbitrarily sized chunks into frames emitted every `hop_size` samples.
+///
+/// Incoming samples are written into an internal ring buffer holding the last `frame_length` samples.
+/// The first frame is emitted once `frame_length` samples have been received, and a new frame every
+/// `hop_size` samples after that, regardless of how the stream is chunked. The frames are identical to
+/// those yielded by `frames(signal, frame_length, hop_size, PaddingMode::None)` over the concatenated
+/// stream. All buffers are allocated on construction, processing samples does not allocate.
+///
+/// The frame length, hop size and window can be changed while streaming with
+/// [`StreamingWindower::reconfigure`], see there for how the transition is handled.
+#[derive(Debug, Clone)]
+pub struct StreamingWindower {
+  current: FrameConfig,
+  // Configuration taking over at the next frame boundary, with its crossfade length
+  pending: Option<(FrameConfig, usize)>,
+  // Configuration still emitting crossfaded frames after a reconfiguration
+  outgoing: Option<FrameConfig>,
+  switch_position: u64,
+  crossfade_length: usize,
+  ring: FrameRing,
+  // Number of samples received since the start of the stream
+  received: u64,
+}
+
+// The buffered samples of a streaming windower and the buffer frames are assembled in
+#[derive(Debug, Clone)]
+struct FrameRing {
+  ring_buffer: Vec<f32>,
+  // Index of the oldest sample in the ring buffer, which is also where the next sample is written
+  write_position: usize,
+  frame: Vec<f32>,
+}
+
+impl StreamingWindower {
+  /// Create a streaming windower emitting frames of `frame_length` samples every `hop_size` samples.
+  ///
+  /// An error is returned if the frame length or the hop size is 0.
+  pub fn new(frame_length: usize, hop_size: usize) -> Result<Self, HannWindowError> {
+    let current = FrameConfig::new(frame_length, hop_size, None)?;
+    trace_allocation("StreamingWindower", 2 * frame_length * std::mem::size_of::<f32>());
+
+    Ok(StreamingWindower {
+      current,
+      pending: None,
+      outgoing: None,
+      switch_position: 0,
+      crossfade_length: 0,
+      ring: FrameRing {
+        ring_buffer: vec![0.0; frame_length],
+        write_position: 0,
+        frame: vec![0.0; frame_length],
+      },
+      received: 0,
+    })
+  }
+
+  /// Multiply every emitted frame by `window`.
+  ///
+  /// An error is returned if the length of the window is different from the frame length.
+  pub fn with_window(mut self, window: &[f32]) -> Result<Self, HannWindowError> {
+    // The window must cover the frame sample for sample
+    if window.len() != self.current.frame_length {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    self.current.window = Some(window.to_vec());
+    Ok(self)
+  }
+
+  /// The length of every emitted frame.
+  pub fn frame_length(&self) -> usize {
+    self.current.frame_length
+  }
+
+  /// The number of samples between two emitted frames.
+  pub fn hop_size(&self) -> usize {
+    self.current.hop_size
+  }
+
+  /// The number of samples left to receive before the next frame is emitted.
+  pub fn samples_until_next_frame(&self) -> usize {
+    self.current.until_next_frame
+  }
+
+  /// The number of samples received since the start of the stream.
+  pub fn position(&self) -> u64 {
+    self.received
+  }
+
+  /// Switch to a new frame length, hop size and window at the next frame boundary.
+  ///
+  /// The frame completed at the next boundary of the current configuration is the first frame of the
+  /// new one, and the new configuration emits a frame every `hop_size` samples after that. Buffered
+  /// samples are kept, so the first new frame includes samples received before the switch. If fewer
+  /// than `frame_length` samples have been received by then, the first new frame is emitted as soon
+  /// as enough samples are available.
+  ///
+  /// With a `crossfade_length` greater than 0, the outgoing configuration keeps emitting frames for
+  /// `crossfade_length` samples after the switch, starting with the frame completed at the switch
+  /// itself. These frames are only passed to [`StreamingWindower::process_frames`], flagged as
+  /// outgoing, and their gains fade out while the gains of the incoming frames fade in.
+  ///
+  /// Unlike [`StreamingWindower::process`], this allocates the buffers of the new configuration. An
+  /// error is returned if the frame length or the hop size is 0, or if the window length is different
+  /// from the frame length. A reconfiguration that has not taken effect yet is replaced.
+  pub fn reconfigure(
+    &mut self,
+    frame_length: usize,
+    hop_size: usize,
+    window: Option<&[f32]>,
+    crossfade_length: usize
+  ) -> Result<(), HannWindowError> {
+    let config = FrameConfig::new(frame_length, hop_size, window)?;
+
+    // Grow the ring buffer to hold the longest frame in use, keeping the buffered samples
+    self.ring.grow(frame_length);
+
+    self.pending = Some((config, crossfade_length));
+    Ok(())
+  }
+
+  /// Feed a chunk of samples and call `on_frame` with every frame completed by it.
+  ///
+  /// The chunk may have any length, including 0. Frames of an outgoing configuration are not passed to
+  /// `on_frame`. Returns the number of emitted frames.
+  pub fn process<F: FnMut(&[f32])>(&mut self, samples: &[f32], mut on_frame: F) -> usize {
+    let mut frame_count = 0;
+    self.process_frames(samples, |frame| {
+      if !frame.outgoing {
+        on_frame(frame.samples);
+        frame_count += 1;
+      }
+    });
+    frame_count
+  }
+
+  /// Feed a chunk of samples and call `on_frame` with every frame completed by it, including its position.
+  ///
+  /// During a crossfaded reconfiguration, the frames of the outgoing configuration are passed as well.
+  /// Frames completed by the same sample are passed outgoing frame first. Returns the number of
+  /// emitted frames.
+  pub fn process_frames<F: FnMut(StreamFrame<'_>)>(&mut self, samples: &[f32], mut on_frame: F) -> usize {
+    let mut remaining = samples;
+    let mut frame_count = 0;
+
+    while !remaining.is_empty() {
+      // Consume samples up to the next frame boundary of either configuration
+      let mut count = remaining.len().min(self.current.until_next_frame);
+      if let Some(outgoing) = &self.outgoing {
+        count = count.min(outgoing.until_next_frame);
+      }
+      self.ring.write(&remaining[..count]);
+      remaining = &remaining[count..];
+      self.received += count as u64;
+      self.current.until_next_frame -= count;
+
+      // Emit the frames of the outgoing configuration until the crossfade is over
+      let elapsed = self.received - self.switch_position;
+      if let Some(mut outgoing) = self.outgoing.take() {
+        outgoing.until_next_frame -= count;
+        if elapsed < (self.crossfade_length as u64) {
+          if outgoing.until_next_frame == 0 {
+            let gain = 1.0 - self.incoming_gain(elapsed);
+            self.ring.emit(&outgoing, self.received, gain, true, &mut on_frame);
+            outgoing.until_next_frame = outgoing.hop_size;
+            frame_count += 1;
+          }
+          self.outgoing = Some(outgoing);
+        }
+      }
+
+      // Emit a frame of the current configuration once its boundary is reached
+      if self.current.until_next_frame == 0 {
+        if let Some((config, crossfade_length)) = self.pending.take() {
+          self.switch(config, crossfade_length, &mut on_frame, &mut frame_count);
+          // Wait for enough samples if the new frame is longer than the received stream
+          if self.received < (self.current.frame_length as u64) {
+            self.current.until_next_frame = self.current.frame_length - (self.received as usize);
+            continue;
+          }
+        }
+        let gain = self.incoming_gain(self.received - self.switch_position);
+        self.ring.emit(&self.current, self.received, gain, false, &mut on_frame);
+        self.current.until_next_frame = self.current.hop_size;
+        frame_count += 1;
+      }
+    }
+
+    record_metric!(record_frames, StreamingProcessor::StreamingWindower, frame_count);
+    frame_count
+  }
+
+  /// Discard every buffered sample and pending reconfiguration, the next frame is emitted after
+  /// `frame_length` new samples.
+  pub fn reset(&mut self) {
+    self.ring.ring_buffer.fill(0.0);
+    self.ring.write_position = 0;
+    self.received = 0;
+    self.pending = None;
+    self.outgoing = None;
+    self.switch_position = 0;
+    self.crossfade_length = 0;
+    self.current.until_next_frame = self.current.frame_length;
+  }
+
+  // Make the pending configuration current, emitting the last frame of the outgoing one when crossfading
+  fn switch<F: FnMut(StreamFrame<'_>)>(
+    &mut self,
+    config: FrameConfig,
+    crossfade_length: usize,
+    on_frame: &mut F,
+    frame_count: &mut usize
+  ) {
+    let mut outgoing = std::mem::replace(&mut self.current, config);
+    self.switch_position = self.received;
+    self.crossfade_length = crossfade_length;
+    self.outgoing = None;
+
+    if crossfade_length > 0 {
+      let gain = 1.0 - self.incoming_gain(0);
+      self.ring.emit(&outgoing, self.received, gain, true, on_frame);
+      outgoing.until_next_frame = outgoing.hop_size;
+      self.outgoing = Some(outgoing);
+      *frame_count += 1;
+    }
+  }
+
+  // Compute the gain of an incoming frame completed `elapsed` samples after the last switch
+  fn incoming_gain(&self, elapsed: u64) -> f32 {
+    if elapsed >= (self.crossfade_length as u64) {
+      1.0
+    } else {
+      ((elapsed + 1) as f32) / ((self.crossfade_length + 1) as f32)
+    }
+  }
+}
+
+/// A frame is emitted `frame_length - 1` samples after its first sample and `(frame_length - 1) / 2`
+/// samples after its center, for the current configuration.
+impl Latency for StreamingWindower {
+  fn latency(&self) -> usize {
+    self.current.frame_length - 1
+  }
+
+  fn group_delay(&self) -> f64 {
+    ((self.current.frame_length - 1) as f64) / 2.0
+  }
+}
+
+impl FrameRing {
+  // Grow the ring buffer to hold at least `capacity` samples, keeping the buffered samples in order
+  fn grow(&mut self, capacity: usize) {
+    let length = self.ring_buffer.len();
+    if capacity <= length {
+      return;
+    }
+    trace_allocation("StreamingWindower", 2 * capacity * std::mem::size_of::<f32>());
+    let mut ring_buffer = vec![0.0; capacity];
+    let offset = capacity - length;
+    let oldest = length - self.write_position;
+    ring_buffer[offset..offset + oldest].copy_from_slice(&self.ring_buffer[self.write_position..]);
+    ring_buffer[offset + oldest..].copy_from_slice(&self.ring_buffer[..self.write_position]);
+    self.ring_buffer = ring_buffer;
+    self.write_position = 0;
+    self.frame.resize(capacity, 0.0);
+  }
+
+  // Write samples into the ring buffer, overwriting the oldest ones
+  fn write(&mut self, samples: &[f32]) {
+    // Only the last ring buffer length of samples can end up in a frame
+    let capacity = self.ring_buffer.len();
+    let samples = &samples[samples.len().saturating_sub(capacity)..];
+
+    // Copy the samples in at most two parts, wrapping around the end of the ring buffer
+    let first_part = samples.len().min(capacity - self.write_position);
+    self.ring_buffer[self.write_position..self.write_position + first_part].copy_from_slice(
+      &samples[..first_part]
+    );
+    self.ring_buffer[..samples.len() - first_part].copy_from_slice(&samples[first_part..]);
+    self.write_position = (self.write_position + samples.len()) % capacity;
+  }
+
+  // Assemble the newest frame of the given configuration and pass it to `on_frame`
+  fn emit<F: FnMut(StreamFrame<'_>)>(
+    &mut self,
+    config: &FrameConfig,
+    received: u64,
+    gain: f32,
+    outgoing: bool,
+    on_frame: &mut F
+  ) {
+    // Copy the newest samples in chronological order, in at most two parts
+    let capacity = self.ring_buffer.len();
+    let frame_length = config.frame_length;
+    let start = (self.write_position + capacity - frame_length) % capacity;
+    let first_part = frame_length.min(capacity - start);
+    self.frame[..first_part].copy_from_slice(&self.ring_buffer[start..start + first_part]);
+    self.frame[first_part..frame_length].copy_from_slice(&self.ring_buffer[..frame_length - first_part]);
+
+    // Multiply the frame by the window, the lengths were checked on construction
+    if let Some(window) = &config.window {
+      apply_window(&mut self.frame[..frame_length], window).expect("Window length was checked on construction");
+    }
+
+    on_frame(StreamFrame {
+      start: received - (frame_length as u64),
+      samples: &self.frame[..frame_length],
+      gain,
+      outgoing,
+    });
+  }
+}
+
+#[cfg(test)]
+mod test_streaming_windower {
+  use approx::assert_abs_diff_eq;
+
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  // Feed the signal in chunks of the given sizes and collect the emitted frames
+  fn collect_frames(windower: &mut StreamingWindower, signal: &[f32], chunk_sizes: &[usize]) -> Vec<Vec<f32>> {
+    let mut emitted = Vec::new();
+    let mut position = 0;
+    for &chunk_size in chunk_sizes.iter().cycle() {
+      if position >= signal.len() {
+        break;
+      }
+      let end = (position + chunk_size).min(signal.len());
+      windower.process(&signal[position..end], |frame| emitted.push(frame.to_vec()));
+      position = end;
+    }
+    emitted
+  }
+
+  #[test]
+  fn test_streaming_windower_matches_frames() {
+    let signal: Vec<f32> = (0..200).map(|i| i as f32).collect();
+    let window = get_hann_window(32).unwrap();
+    let mut windower = StreamingWindower::new(32, 12).unwrap().with_window(&window).unwrap();
+
+    let emitted = collect_frames(&mut windower, &signal, &[5, 64, 1, 0, 17]);
+    let expected: Vec<Vec<f32>> = frames(&signal, 32, 12, PaddingMode::None)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(emitted, expected);
+  }
+
+  #[test]
+  fn test_streaming_windower_hop_larger_than_frame() {
+    let signal: Vec<f32> = (0..20).map(|i| i as f32).collect();
+    let mut windower = StreamingWindower::new(3, 7).unwrap();
+
+    let emitted = collect_frames(&mut windower, &signal, &[4]);
+
+    assert_eq!(emitted, vec![vec![0.0, 1.0, 2.0], vec![7.0, 8.0, 9.0], vec![14.0, 15.0, 16.0]]);
+    assert_eq!(windower.samples_until_next_frame(), 4);
+  }
+
+  #[test]
+  fn test_streaming_windower_reset() {
+    let mut windower = StreamingWindower::new(4, 2).unwrap();
+
+    assert_eq!(windower.process(&[1.0, 2.0, 3.0], |_| {}), 0);
+    windower.reset();
+    assert_eq!(windower.process(&[1.0, 2.0, 3.0], |_| {}), 0);
+    assert_eq!(windower.process(&[4.0, 5.0], |frame| assert_eq!(frame, &[1.0, 2.0, 3.0, 4.0])), 1);
+  }
+
+  #[test]
+  fn test_streaming_windower_reconfigure() {
+    let signal: Vec<f32> = (0..40).map(|i| i as f32).collect();
+    let mut windower = StreamingWindower::new(4, 4).unwrap();
+    let mut emitted = Vec::new();
+
+    // Switch to longer frames after the first frame, the buffered samples are kept
+    windower.process_frames(&signal[..6], |frame| emitted.push((frame.start, frame.samples.to_vec())));
+    windower.reconfigure(6, 3, None, 0).unwrap();
+    windower.process_frames(&signal[6..], |frame| emitted.push((frame.start, frame.samples.to_vec())));
+
+    assert_eq!(emitted[0], (0, vec![0.0, 1.0, 2.0, 3.0]));
+    assert_eq!(emitted[1], (2, vec![2.0, 3.0, 4.0, 5.0, 6.0, 7.0]));
+    assert_eq!(emitted[2], (5, vec![5.0, 6.0, 7.0, 8.0, 9.0, 10.0]));
+    assert!(emitted.iter().skip(1).all(|(_, samples)| samples.len() == 6));
+    assert_eq!(windower.frame_length(), 6);
+    assert_eq!(windower.hop_size(), 3);
+  }
+
+  #[test]
+  fn test_streaming_windower_reconfigure_before_first_frame() {
+    let mut windower = StreamingWindower::new(2, 2).unwrap();
+
+    // The first boundary is reached before enough samples for the new frame length arrived
+    windower.reconfigure(5, 5, None, 0).unwrap();
+    let mut starts = Vec::new();
+    windower.process_frames(&[0.0; 12], |frame| starts.push(frame.start));
+
+    assert_eq!(starts, vec![0, 5]);
+  }
+
+  #[test]
+  fn test_streaming_windower_crossfade() {
+    let short_window = get_hann_window(4).unwrap();
+    let long_window = get_hann_window(8).unwrap();
+    let mut windower = StreamingWindower::new(4, 2).unwrap().with_window(&short_window).unwrap();
+    let mut emitted = Vec::new();
+
+    windower.reconfigure(8, 4, Some(&long_window), 6).unwrap();
+    let frame_count = windower.process_frames(&[1.0; 24], |frame| {
+      emitted.push((frame.start + (frame.samples.len() as u64), frame.samples.len(), frame.gain, frame.outgoing));
+    });
+
+    // The switch happens at sample 4, the outgoing frames fade out for 6 samples
+    assert_eq!(frame_count, emitted.len());
+    assert_eq!(emitted[0], (4, 4, 1.0 - 1.0 / 7.0, true));
+    assert_eq!(emitted[1], (6, 4, 1.0 - 3.0 / 7.0, true));
+    assert_eq!(emitted[2], (8, 4, 1.0 - 5.0 / 7.0, true));
+    assert_eq!(emitted[3], (8, 8, 5.0 / 7.0, false));
+    assert!(emitted.iter().filter(|frame| frame.3).all(|frame| frame.0 < 10));
+    assert!(emitted.iter().filter(|frame| !frame.3).all(|frame| frame.1 == 8));
+    assert_eq!(emitted.last().unwrap().2, 1.0);
+
+    // Outgoing and incoming frames completed by the same sample have complementary gains
+    let outgoing = emitted.iter().find(|frame| frame.3 && frame.0 == 8).unwrap();
+    let incoming = emitted.iter().find(|frame| !frame.3 && frame.0 == 8).unwrap();
+    assert_abs_diff_eq!(outgoing.2 + incoming.2, 1.0, epsilon = 1e-6);
+  }
+
+  #[test]
+  fn test_streaming_windower_invalid_parameters() {
+    assert_eq!(StreamingWindower::new(0, 1).unwrap_err(), HannWindowError::InvalidFrameLength);
+    assert_eq!(StreamingWindower::new(4, 0).unwrap_err(), HannWindowError::InvalidHopSize);
+    assert_eq!(
+      StreamingWindower::new(4, 2).unwrap().with_window(&[1.0]).unwrap_err(),
+      HannWindowError::LengthMismatch
+    );
+    assert_eq!(
+      StreamingWindower::new(4, 2).unwrap().reconfigure(3, 1, Some(&[1.0]), 0).unwrap_err(),
+      HannWindowError::LengthMismatch
+    );
+  }
+}
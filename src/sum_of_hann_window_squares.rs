@@ -1,8 +1,7 @@
 use lazy_static::lazy_static;
 use std::collections::HashMap;
-use std::usize;
 
-use crate::hann_window::HANN_WINDOW_LOOKUP_TABLE;
+use crate::{ hann_window::HANN_WINDOW_LOOKUP_TABLE, instrument::{ record_metric, trace_event } };
 
 // Defining a lazy_static block for the HANN_LOOKUP_TABLE
 lazy_static! {
@@ -35,13 +34,16 @@ lazy_static! {
 /// using a precomputed lookup table for Hann windows of length 512, 1024, 2048, and 4096. If the
 /// length of the input `hann_window` is not in the lookup table, the sum of squares is computed
 /// using `map` and `sum`.
-pub fn get_hann_window_sum_squares(hann_window: &Vec<f32>) -> f32 {
+pub fn get_hann_window_sum_squares(hann_window: &[f32]) -> f32 {
   // Check if the sum-of-squares for the input Hann window length is in the lookup table
   if let Some(sum_squares) = HANN_WINDOW_SUM_OF_SQUARES.get(&hann_window.len()) {
     // If it is, return the precomputed value
-    sum_squares.clone()
+    record_metric!(record_lookup, LookupTable::HannWindowSumSquares, true);
+    *sum_squares
   } else {
     // Otherwise, compute the sum-of-squares using `map` and `sum`
+    record_metric!(record_lookup, LookupTable::HannWindowSumSquares, false);
+    trace_event!(DEBUG, window_length = hann_window.len(), "Hann window sum of squares lookup table miss");
     hann_window
       .iter()
       .map(|&x| x.powi(2))
@@ -49,7 +51,9 @@ pub fn get_hann_window_sum_squares(hann_window: &Vec<f32>) -> f32 {
   }
 }
 
+// The tests of the first release are kept as they were written
 #[cfg(test)]
+#[allow(clippy::clone_on_copy)]
 mod test_hann_window {
   use approx::relative_eq;
 
@@ -0,0 +1,245 @@
+use std::{ collections::VecDeque, error::Error, fmt, fs::File, io::{ BufReader, Read }, path::Path };
+
+use hound::{ SampleFormat, WavReader, WavSpec };
+
+use crate::{
+  downmix::{ downmix, Downmix },
+  hann_window::HannWindowError,
+  instrument::trace_span,
+  streaming_windower::StreamingWindower,
+};
+
+// The number of sample frames decoded at once
+const WAV_CHUNK_FRAMES: usize = 4096;
+
+/// Error type for the WAV frame reader.
+#[derive(Debug)]
+pub enum WavError {
+  Wav(hound::Error),
+  Windower(HannWindowError),
+}
+
+// Implement the Error trait for the WavError enum
+impl Error for WavError {}
+
+// Implement the Display trait for the WavError enum
+impl fmt::Display for WavError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Write the error message to the Formatter
+    match self {
+      WavError::Wav(error) => {
+        write!(f, "WavError: Failed to read the WAV file: {}", error)
+      }
+      WavError::Windower(error) => {
+        write!(f, "WavError: {}", error)
+      }
+    }
+  }
+}
+
+/// A frame source reading a WAV file and yielding windowed frames.
+///
+/// Integer samples of any bit depth (8, 16, 24 or 32 bits) are scaled to [-1.0, 1.0) and 32-bit float
+/// samples are read as is. The channels are reduced to one as described by a [`Downmix`] and cut into
+/// frames by a [`StreamingWindower`]. The file is decoded in chunks, so that long files are never held
+/// in memory. Samples that do not fill a whole frame at the end of the file are discarded.
+pub struct WavFrameReader<R> {
+  reader: WavReader<R>,
+  windower: StreamingWindower,
+  downmix: Downmix,
+  interleaved: Vec<f32>,
+  mono: Vec<f32>,
+  pending_frames: VecDeque<Vec<f32>>,
+  finished: bool,
+}
+
+impl WavFrameReader<BufReader<File>> {
+  /// Open the WAV file at `path` and frame its samples with `windower`.
+  pub fn open<P: AsRef<Path>>(path: P, windower: StreamingWindower, downmix: Downmix) -> Result<Self, WavError> {
+    let reader = WavReader::open(path).map_err(WavError::Wav)?;
+    WavFrameReader::from_wav_reader(reader, windower, downmix)
+  }
+}
+
+impl<R: Read> WavFrameReader<R> {
+  /// Read a WAV file from `reader` and frame its samples with `windower`.
+  ///
+  /// An error is returned if the WAV header is invalid or if the selected downmix channel does not exist.
+  pub fn new(reader: R, windower: StreamingWindower, downmix: Downmix) -> Result<Self, WavError> {
+    let reader = WavReader::new(reader).map_err(WavError::Wav)?;
+    WavFrameReader::from_wav_reader(reader, windower, downmix)
+  }
+
+  // Wrap a WAV reader whose header was read successfully
+  fn from_wav_reader(reader: WavReader<R>, windower: StreamingWindower, downmix: Downmix) -> Result<Self, WavError> {
+    let channel_count = usize::from(reader.spec().channels);
+    // The selected channel must exist
+    if let Downmix::Channel(channel) = downmix {
+      if channel >= channel_count {
+        return Err(WavError::Windower(HannWindowError::InvalidChannelCount));
+      }
+    }
+
+    Ok(WavFrameReader {
+      reader,
+      windower,
+      downmix,
+      interleaved: vec![0.0; WAV_CHUNK_FRAMES * channel_count],
+      mono: vec![0.0; WAV_CHUNK_FRAMES],
+      pending_frames: VecDeque::new(),
+      finished: false,
+    })
+  }
+
+  /// The format of the WAV file, including its sample rate and number of channels.
+  pub fn spec(&self) -> WavSpec {
+    self.reader.spec()
+  }
+
+  /// Consume the frame source and return the wrapped reader.
+  pub fn into_inner(self) -> R {
+    self.reader.into_inner()
+  }
+
+  // Decode the next chunk of the file and queue the frames it completes
+  fn read_chunk(&mut self) -> Result<(), hound::Error> {
+    trace_span!(DEBUG, "wav_read_chunk");
+    let spec = self.reader.spec();
+    let channel_count = usize::from(spec.channels);
+
+    // Decode up to one chunk of interleaved samples, scaling integers by their bit depth
+    let mut read_samples = 0;
+    match spec.sample_format {
+      SampleFormat::Float => {
+        for (sample, decoded) in self.interleaved.iter_mut().zip(self.reader.samples::<f32>()) {
+          *sample = decoded?;
+          read_samples += 1;
+        }
+      }
+      SampleFormat::Int => {
+        let scale = 1.0 / ((1u64 << (spec.bits_per_sample - 1)) as f32);
+        for (sample, decoded) in self.interleaved.iter_mut().zip(self.reader.samples::<i32>()) {
+          *sample = (decoded? as f32) * scale;
+          read_samples += 1;
+        }
+      }
+    }
+
+    // The end of the file is reached once no whole sample frame is left
+    let sample_frames = read_samples / channel_count;
+    if sample_frames == 0 {
+      self.finished = true;
+      return Ok(());
+    }
+
+    let interleaved = &self.interleaved[..sample_frames * channel_count];
+    let mono = &mut self.mono[..sample_frames];
+    downmix(interleaved, channel_count, self.downmix, mono).expect("Buffers hold whole sample frames");
+
+    let pending_frames = &mut self.pending_frames;
+    self.windower.process(mono, |frame| pending_frames.push_back(frame.to_vec()));
+
+    Ok(())
+  }
+}
+
+impl<R: Read> Iterator for WavFrameReader<R> {
+  type Item = Result<Vec<f32>, WavError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      // Hand out the frames completed by the previous chunk first
+      if let Some(frame) = self.pending_frames.pop_front() {
+        return Some(Ok(frame));
+      }
+      if self.finished {
+        return None;
+      }
+      // Stop reading after the first decoding error
+      if let Err(error) = self.read_chunk() {
+        self.finished = true;
+        return Some(Err(WavError::Wav(error)));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test_wav_reader {
+  use std::io::Cursor;
+
+  use hound::WavWriter;
+
+  use crate::{ frames::{ frames, PaddingMode }, get_hann_window };
+
+  use super::*;
+
+  // Encode interleaved samples as an in-memory WAV file
+  fn encode<S: hound::Sample + Copy>(spec: WavSpec, samples: &[S]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = WavWriter::new(Cursor::new(&mut bytes), spec).unwrap();
+    for sample in samples {
+      writer.write_sample(*sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    bytes
+  }
+
+  #[test]
+  fn test_wav_frame_reader_matches_frames() {
+    let signal: Vec<f32> = (0..10000).map(|i| ((i as f32) * 0.01).sin()).collect();
+    let spec = WavSpec { channels: 1, sample_rate: 48000, bits_per_sample: 32, sample_format: SampleFormat::Float };
+    let window = get_hann_window(512).unwrap();
+    let windower = StreamingWindower::new(512, 128).unwrap().with_window(&window).unwrap();
+
+    let reader = WavFrameReader::new(Cursor::new(encode(spec, &signal)), windower, Downmix::Average).unwrap();
+    let emitted: Vec<Vec<f32>> = reader.map(Result::unwrap).collect();
+    let expected: Vec<Vec<f32>> = frames(&signal, 512, 128, PaddingMode::None)
+      .unwrap()
+      .with_window(&window)
+      .unwrap()
+      .map(|frame| frame.to_vec())
+      .collect();
+
+    assert_eq!(emitted, expected);
+  }
+
+  #[test]
+  fn test_wav_frame_reader_i16_stereo_downmix() {
+    let spec = WavSpec { channels: 2, sample_rate: 44100, bits_per_sample: 16, sample_format: SampleFormat::Int };
+    let bytes = encode(spec, &[16384i16, 0, -16384, -16384, 8192, 8192, 0, 16384]);
+
+    let average = WavFrameReader::new(Cursor::new(&bytes), StreamingWindower::new(4, 4).unwrap(), Downmix::Average)
+      .unwrap()
+      .map(Result::unwrap)
+      .collect::<Vec<_>>();
+    let right = WavFrameReader::new(Cursor::new(&bytes), StreamingWindower::new(4, 4).unwrap(), Downmix::Channel(1))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect::<Vec<_>>();
+
+    assert_eq!(average, vec![vec![0.25, -0.5, 0.25, 0.25]]);
+    assert_eq!(right, vec![vec![0.0, -0.5, 0.25, 0.5]]);
+  }
+
+  #[test]
+  fn test_wav_frame_reader_i24() {
+    let spec = WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 24, sample_format: SampleFormat::Int };
+    let bytes = encode(spec, &[-8388608i32, 4194304]);
+
+    let reader = WavFrameReader::new(Cursor::new(bytes), StreamingWindower::new(2, 2).unwrap(), Downmix::Average).unwrap();
+
+    assert_eq!(reader.spec().bits_per_sample, 24);
+    assert_eq!(reader.map(Result::unwrap).collect::<Vec<_>>(), vec![vec![-1.0, 0.5]]);
+  }
+
+  #[test]
+  fn test_wav_frame_reader_invalid_channel() {
+    let spec = WavSpec { channels: 2, sample_rate: 44100, bits_per_sample: 16, sample_format: SampleFormat::Int };
+    let bytes = encode(spec, &[0i16; 4]);
+
+    let error = WavFrameReader::new(Cursor::new(bytes), StreamingWindower::new(2, 2).unwrap(), Downmix::Channel(2));
+
+    assert!(matches!(error, Err(WavError::Windower(HannWindowError::InvalidChannelCount))));
+  }
+}
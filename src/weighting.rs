@@ -0,0 +1,326 @@
+use crate::hann_window::HannWindowError;
+
+// The pole frequencies of the weighting curves in Hz, as given by IEC 61672-1
+const POLE_LOW: f64 = 20.598997;
+const POLE_A_LOW: f64 = 107.65265;
+const POLE_A_HIGH: f64 = 737.86223;
+const POLE_HIGH: f64 = 12194.217;
+
+// The frequency at which every weighting curve is normalized to 0 dB, and around which the octave
+// bands are centered
+const REFERENCE_FREQUENCY: f64 = 1000.0;
+
+/// The frequency weightings of sound level meters, as specified by IEC 61672-1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FrequencyWeighting {
+  /// The A-weighting, following the sensitivity of hearing at low levels, from -39.4 dB at 31.5 Hz to
+  /// +1.3 dB around 2.5 kHz.
+  A,
+  /// The C-weighting, flat over most of the audio band and falling by 3 dB at 31.5 Hz and 8 kHz, for
+  /// high levels and peak measurements.
+  C,
+  /// The zero weighting, flat at 0 dB.
+  Z,
+}
+
+impl FrequencyWeighting {
+  /// The amplitude gain of the weighting at `frequency` Hz, 1.0 at 1 kHz.
+  pub fn gain(self, frequency: f32) -> f32 {
+    match self {
+      FrequencyWeighting::Z => 1.0,
+      _ => (self.response(f64::from(frequency.abs())) / self.response(REFERENCE_FREQUENCY)) as f32,
+    }
+  }
+
+  /// The gain of the weighting at `frequency` Hz in decibels, 0 dB at 1 kHz and minus infinity at
+  /// 0 Hz for the A- and C-weightings.
+  pub fn gain_db(self, frequency: f32) -> f32 {
+    20.0 * self.gain(frequency).log10()
+  }
+
+  // The unnormalized magnitude response of the analog weighting filter
+  fn response(self, frequency: f64) -> f64 {
+    let square = frequency * frequency;
+    let high = POLE_HIGH * POLE_HIGH;
+    let low = (square + POLE_LOW * POLE_LOW) * (square + high);
+    match self {
+      FrequencyWeighting::A => {
+        let middle = ((square + POLE_A_LOW * POLE_A_LOW) * (square + POLE_A_HIGH * POLE_A_HIGH)).sqrt();
+        (high * square * square) / (low * middle)
+      }
+      FrequencyWeighting::C => (high * square) / low,
+      FrequencyWeighting::Z => 1.0,
+    }
+  }
+}
+
+/// A frequency weighting sampled at the bin frequencies of a power spectrum.
+///
+/// The curve weights the `fft_length / 2 + 1` bins of a power spectrum, e.g. a row of `spectrogram`
+/// or `welch`, by the squared gain of the weighting at their frequencies, so that summing the weighted
+/// bins over an octave or a third-octave band with [`OctaveBands`] gives the weighted band level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightingCurve {
+  weighting: FrequencyWeighting,
+  // The power gain of every bin
+  gains: Vec<f32>,
+}
+
+impl WeightingCurve {
+  /// Sample `weighting` at the bins of the spectra of frames of `fft_length` samples at `sample_rate`
+  /// Hz.
+  ///
+  /// An error is returned if the sample rate is 0 or if the FFT length is less than or equal to 1.
+  pub fn new(weighting: FrequencyWeighting, sample_rate: u32, fft_length: usize) -> Result<Self, HannWindowError> {
+    if sample_rate == 0 {
+      return Err(HannWindowError::InvalidSampleRate);
+    }
+    if fft_length <= 1 {
+      return Err(HannWindowError::WindowLengthTooSmall);
+    }
+
+    let bin_width = (sample_rate as f32) / (fft_length as f32);
+    let gains = (0..(fft_length / 2 + 1)).map(|k| weighting.gain((k as f32) * bin_width).powi(2)).collect();
+
+    Ok(WeightingCurve { weighting, gains })
+  }
+
+  /// The weighting of the curve.
+  pub fn weighting(&self) -> FrequencyWeighting {
+    self.weighting
+  }
+
+  /// The number of bins of the spectra the curve applies to, `fft_length / 2 + 1`.
+  pub fn bin_count(&self) -> usize {
+    self.gains.len()
+  }
+
+  /// The power gain of every bin.
+  pub fn gains(&self) -> &[f32] {
+    &self.gains
+  }
+
+  /// Weight a power spectrum.
+  ///
+  /// An error is returned if the spectrum does not have `bin_count` bins.
+  pub fn apply(&self, power: &[f32]) -> Result<Vec<f32>, HannWindowError> {
+    let mut weighted = power.to_vec();
+    self.apply_in_place(&mut weighted)?;
+    Ok(weighted)
+  }
+
+  /// Weight a power spectrum in place.
+  ///
+  /// An error is returned if the spectrum does not have `bin_count` bins.
+  pub fn apply_in_place(&self, power: &mut [f32]) -> Result<(), HannWindowError> {
+    // The spectrum must match the curve bin for bin
+    if power.len() != self.gains.len() {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    for (power, gain) in power.iter_mut().zip(&self.gains) {
+      *power *= gain;
+    }
+    Ok(())
+  }
+}
+
+/// The width of the bands of an [`OctaveBands`] summation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BandResolution {
+  /// Octave bands, nominally 31.5 Hz, 63 Hz, ..., 16 kHz.
+  Octave,
+  /// Third-octave bands, nominally 25 Hz, 31.5 Hz, 40 Hz, ..., 20 kHz.
+  ThirdOctave,
+}
+
+impl BandResolution {
+  // The number of bands per octave
+  fn bands_per_octave(self) -> i32 {
+    match self {
+      BandResolution::Octave => 1,
+      BandResolution::ThirdOctave => 3,
+    }
+  }
+}
+
+/// The summation of the bins of a power spectrum over octave or third-octave bands.
+///
+/// The bands are the base-10 bands of IEC 61260-1: the band `k` bands from 1 kHz is centered at
+/// `1000 * 10^(3k / 10b)` Hz for `b` bands per octave, and spans `10^(3 / 20b)` times below and above
+/// its center, so that consecutive bands meet. Every one of the `fft_length / 2 + 1` bins of a power
+/// spectrum is summed into the band its frequency falls in, at the same bin frequencies as a
+/// [`WeightingCurve`], so that summing a weighted spectrum gives the weighted band powers. A band
+/// narrower than the bin spacing may hold no bin, and has the power 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OctaveBands {
+  resolution: BandResolution,
+  // The first and the last bin, exclusive, of every band
+  bins: Vec<(usize, usize)>,
+  center_frequencies: Vec<f32>,
+  bin_count: usize,
+}
+
+impl OctaveBands {
+  /// Create the bands of `resolution` whose center frequencies lie between `min_frequency` and
+  /// `max_frequency` Hz, for the spectra of frames of `fft_length` samples at `sample_rate` Hz.
+  ///
+  /// The bins above the Nyquist frequency do not exist, so the highest band may be cut short. An error
+  /// is returned if the sample rate is 0, if the FFT length is less than or equal to 1, or if the
+  /// frequencies are not increasing from above 0 to the Nyquist frequency.
+  pub fn new(
+    resolution: BandResolution,
+    sample_rate: u32,
+    fft_length: usize,
+    min_frequency: f32,
+    max_frequency: f32
+  ) -> Result<Self, HannWindowError> {
+    if sample_rate == 0 {
+      return Err(HannWindowError::InvalidSampleRate);
+    }
+    if fft_length <= 1 {
+      return Err(HannWindowError::WindowLengthTooSmall);
+    }
+    let nyquist = (sample_rate as f32) / 2.0;
+    if !(0.0 < min_frequency && min_frequency < max_frequency && max_frequency <= nyquist) {
+      return Err(HannWindowError::InvalidCutoff);
+    }
+
+    // The frequency `steps` half bands from 1 kHz, so that the centers are the even steps and the
+    // edges the odd ones
+    let b = f64::from(resolution.bands_per_octave());
+    let frequency = |steps: i32| REFERENCE_FREQUENCY * 10.0f64.powf(0.15 * f64::from(steps) / b);
+    let first = (10.0 * b / 3.0 * (f64::from(min_frequency) / REFERENCE_FREQUENCY).log10()).ceil() as i32;
+    let last = (10.0 * b / 3.0 * (f64::from(max_frequency) / REFERENCE_FREQUENCY).log10()).floor() as i32;
+
+    // Assign the bins to the bands by their frequencies, the same as those of `WeightingCurve`
+    let bin_count = fft_length / 2 + 1;
+    let bin_width = (sample_rate as f32) / (fft_length as f32);
+    let first_bin = |edge: f64| ((edge as f32) / bin_width).ceil().clamp(0.0, bin_count as f32) as usize;
+    let (bins, center_frequencies) = (first..=last)
+      .map(|k| ((first_bin(frequency(2 * k - 1)), first_bin(frequency(2 * k + 1))), frequency(2 * k) as f32))
+      .unzip();
+
+    Ok(OctaveBands { resolution, bins, center_frequencies, bin_count })
+  }
+
+  /// The width of the bands.
+  pub fn resolution(&self) -> BandResolution {
+    self.resolution
+  }
+
+  /// The number of bands.
+  pub fn band_count(&self) -> usize {
+    self.bins.len()
+  }
+
+  /// The number of bins of the spectra the bands apply to, `fft_length / 2 + 1`.
+  pub fn bin_count(&self) -> usize {
+    self.bin_count
+  }
+
+  /// The exact center frequency of every band, in Hz.
+  pub fn center_frequencies(&self) -> &[f32] {
+    &self.center_frequencies
+  }
+
+  /// Sum a power spectrum over every band.
+  ///
+  /// An error is returned if the spectrum does not have `bin_count` bins.
+  pub fn apply(&self, power: &[f32]) -> Result<Vec<f32>, HannWindowError> {
+    // The spectrum must match the bands bin for bin
+    if power.len() != self.bin_count {
+      return Err(HannWindowError::LengthMismatch);
+    }
+    Ok(self.bins.iter().map(|&(start, end)| power[start..end].iter().sum()).collect())
+  }
+}
+
+#[cfg(test)]
+mod test_weighting {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  // The exact center frequency of the octave band `k` octaves from 1 kHz
+  fn octave_frequency(k: i32) -> f32 {
+    1000.0 * 10.0f32.powf(0.3 * (k as f32))
+  }
+
+  #[test]
+  fn test_a_weighting() {
+    // The table of IEC 61672-1 at the exact octave frequencies 1000 * 10^(3k / 10), nominally 31.5 Hz
+    // to 16 kHz
+    let expected = [-39.4, -26.2, -16.1, -8.6, -3.2, 0.0, 1.2, 1.0, -1.1, -6.6];
+
+    for (k, expected) in (-5..=4).zip(expected) {
+      assert_abs_diff_eq!(FrequencyWeighting::A.gain_db(octave_frequency(k)), expected, epsilon = 0.05);
+    }
+    assert_eq!(FrequencyWeighting::A.gain(0.0), 0.0);
+  }
+
+  #[test]
+  fn test_c_and_z_weighting() {
+    let expected = [-3.0, -0.8, -0.2, 0.0, 0.0, 0.0, -0.2, -0.8, -3.0, -8.5];
+
+    for (k, expected) in (-5..=4).zip(expected) {
+      assert_abs_diff_eq!(FrequencyWeighting::C.gain_db(octave_frequency(k)), expected, epsilon = 0.05);
+      assert_eq!(FrequencyWeighting::Z.gain_db(octave_frequency(k)), 0.0);
+    }
+  }
+
+  #[test]
+  fn test_weighting_curve() {
+    let curve = WeightingCurve::new(FrequencyWeighting::A, 8000, 16).unwrap();
+
+    // The bins lie every 500 Hz, and bin 2 at 1 kHz is left unchanged
+    let weighted = curve.apply(&[1.0; 9]).unwrap();
+    assert_eq!(curve.bin_count(), 9);
+    assert_eq!(weighted[0], 0.0);
+    assert_abs_diff_eq!(weighted[2], 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(10.0 * weighted[1].log10(), FrequencyWeighting::A.gain_db(500.0), epsilon = 1e-4);
+    assert_eq!(WeightingCurve::new(FrequencyWeighting::Z, 8000, 16).unwrap().gains(), [1.0; 9]);
+  }
+
+  #[test]
+  fn test_octave_bands() {
+    let bands = OctaveBands::new(BandResolution::Octave, 8000, 16, 400.0, 4000.0).unwrap();
+
+    // The bins lie every 500 Hz, one in the bands of 500 Hz and 1 kHz and three in the higher ones
+    assert_eq!(bands.band_count(), 4);
+    assert_abs_diff_eq!(bands.center_frequencies()[..], [501.19, 1000.0, 1995.26, 3981.07][..], epsilon = 0.01);
+    assert_eq!(bands.apply(&[1.0; 9]).unwrap(), [1.0, 1.0, 3.0, 3.0]);
+
+    // The weighted band levels sum the weighted bins
+    let curve = WeightingCurve::new(FrequencyWeighting::A, 8000, 16).unwrap();
+    let levels = bands.apply(&curve.apply(&[1.0; 9]).unwrap()).unwrap();
+    assert_abs_diff_eq!(levels[1], 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(levels[3], curve.gains()[6..].iter().sum::<f32>(), epsilon = 1e-6);
+  }
+
+  #[test]
+  fn test_third_octave_bands() {
+    let bands = OctaveBands::new(BandResolution::ThirdOctave, 48000, 4096, 22.0, 22000.0).unwrap();
+
+    // The bands from 25 Hz to 20 kHz meet, so every bin between their outer edges, 22.4 Hz and
+    // 22.4 kHz, from bin 2 to bin 1910 is summed once
+    let sums = bands.apply(&[1.0; 2049]).unwrap();
+    assert_eq!(bands.band_count(), 30);
+    assert_abs_diff_eq!(bands.center_frequencies()[0], 25.12, epsilon = 0.01);
+    assert_abs_diff_eq!(bands.center_frequencies()[29], 19952.62, epsilon = 0.1);
+    assert_eq!(sums.iter().sum::<f32>(), 1909.0);
+  }
+
+  #[test]
+  fn test_weighting_curve_invalid_parameters() {
+    assert_eq!(WeightingCurve::new(FrequencyWeighting::C, 0, 16).unwrap_err(), HannWindowError::InvalidSampleRate);
+    assert_eq!(WeightingCurve::new(FrequencyWeighting::C, 8000, 1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(WeightingCurve::new(FrequencyWeighting::C, 8000, 16).unwrap().apply(&[1.0; 8]).unwrap_err(), HannWindowError::LengthMismatch);
+    assert_eq!(OctaveBands::new(BandResolution::Octave, 8000, 16, 0.0, 4000.0).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(OctaveBands::new(BandResolution::Octave, 8000, 16, 400.0, 5000.0).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(OctaveBands::new(BandResolution::Octave, 8000, 16, 400.0, 4000.0).unwrap().apply(&[1.0; 8]).unwrap_err(), HannWindowError::LengthMismatch);
+  }
+}
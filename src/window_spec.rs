@@ -0,0 +1,644 @@
+use std::{ f64::consts::PI, str::FromStr, time::Duration };
+
+use crate::{
+  duration::{ duration_to_samples, DurationRounding },
+  hann_window::{ check_window_length, get_hann_window, HannWindowError },
+};
+
+/// The largest number of terms of a [`WindowSpec::CosineSum`] window.
+pub const MAX_COSINE_SUM_TERMS: usize = 8;
+
+/// The largest `beta` of a [`WindowSpec::Kaiser`] window. The window is normalized by the Bessel
+/// function `I0(beta)`, which grows like `exp(beta)` and overflows `f64` a little above 700.
+pub const MAX_KAISER_BETA: f32 = 700.0;
+
+/// The names of the windows parsed by [`WindowSpec::from_name`] and the [`FromStr`] implementation of
+/// [`WindowSpec`], in the order of the variants.
+pub const WINDOW_NAMES: [&str; 9] =
+  ["rectangular", "hann", "hamming", "blackman", "kaiser", "gaussian", "tukey", "planck-taper", "power-of-cosine"];
+
+// The distance in ULPs from 1.0 within which the middle value of a window is taken as a unity peak
+const UNITY_PEAK_ULPS: i64 = 4;
+
+/// A window function and its shape parameters.
+///
+/// Every window is generated symmetric, like [`get_hann_window`], which is what filter design and
+/// other uses relying on linear phase require. Rounding can still break the symmetry by one ULP,
+/// which [`WindowSpec::generate_exact`] rules out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WindowSpec {
+  /// The rectangular window, all ones.
+  Rectangular,
+  /// The Hann window `0.5 - 0.5 * cos(2π * n / (N - 1))`.
+  Hann,
+  /// The Hamming window `0.54 - 0.46 * cos(2π * n / (N - 1))`.
+  Hamming,
+  /// The Blackman window `0.42 - 0.5 * cos(2π * n / (N - 1)) + 0.08 * cos(4π * n / (N - 1))`.
+  Blackman,
+  /// The Kaiser window with shape parameter `beta`, from 0 to [`MAX_KAISER_BETA`], see
+  /// [`kaiser_beta`].
+  Kaiser { beta: f32 },
+  /// The Gaussian window `exp(-0.5 * (x / sigma)^2)`, where `x` runs from -1 to 1 over the window, so
+  /// that `sigma` is the standard deviation relative to half the window length.
+  Gaussian { sigma: f32 },
+  /// The Tukey window, flat at 1 in the middle and tapered by half a Hann window over the fraction
+  /// `alpha` of its length, half at either end, so that `alpha` 0 is rectangular and 1 is Hann.
+  Tukey { alpha: f32 },
+  /// The Planck-taper window, flat at 1 in the middle and rising from 0 over the fraction `epsilon`
+  /// of its length at either end along a smooth step `1 / (1 + exp(ε / x - ε / (ε - x)))`, with
+  /// `epsilon` up to 0.5.
+  PlanckTaper { epsilon: f32 },
+  /// The power-of-cosine window `sin(π * n / (N - 1))^alpha`, for any real `alpha` not less than 0, so
+  /// that `alpha` 0 is rectangular, 1 is the cosine window, 2 is Hann and larger values taper ever
+  /// more steeply.
+  PowerOfCosine { alpha: f32 },
+  /// The generalized cosine-sum window `a0 - a1 * cos(2π * n / (N - 1)) + a2 * cos(4π * n / (N - 1)) -
+  /// ...`, with up to [`MAX_COSINE_SUM_TERMS`] coefficients and the unused ones 0, e.g. as designed by
+  /// [`crate::windows::fit_cosine_sum`]. Hann is `[0.5, 0.5]` and Blackman `[0.42, 0.5, 0.08]`.
+  CosineSum { coefficients: [f32; MAX_COSINE_SUM_TERMS] },
+}
+
+impl WindowSpec {
+  /// The window of the given name, one of [`WINDOW_NAMES`], with its shape parameter.
+  ///
+  /// The shape parameter is the `beta` of the Kaiser window, the `sigma` of the Gaussian window, the
+  /// `alpha` of the Tukey and the power-of-cosine windows and the `epsilon` of the Planck-taper window,
+  /// and defaults to 8.6, 0.4, 0.5, 2.0 and 0.1 respectively when it is `None`. An error is returned if
+  /// the name is unknown, if a parameter is given for a window that has none, or if the parameter is
+  /// out of the range [`WindowSpec::generate`] accepts. Cosine-sum windows have no name, since their
+  /// coefficients are not a single parameter.
+  pub fn from_name(name: &str, parameter: Option<f32>) -> Result<WindowSpec, HannWindowError> {
+    let spec = match name {
+      "rectangular" => WindowSpec::Rectangular,
+      "hann" => WindowSpec::Hann,
+      "hamming" => WindowSpec::Hamming,
+      "blackman" => WindowSpec::Blackman,
+      "kaiser" => WindowSpec::Kaiser { beta: parameter.unwrap_or(8.6) },
+      "gaussian" => WindowSpec::Gaussian { sigma: parameter.unwrap_or(0.4) },
+      "tukey" => WindowSpec::Tukey { alpha: parameter.unwrap_or(0.5) },
+      "planck-taper" => WindowSpec::PlanckTaper { epsilon: parameter.unwrap_or(0.1) },
+      "power-of-cosine" => WindowSpec::PowerOfCosine { alpha: parameter.unwrap_or(2.0) },
+      _ => return Err(HannWindowError::UnknownWindow),
+    };
+    // Only the windows with a shape parameter take one
+    if parameter.is_some() && matches!(spec, WindowSpec::Rectangular | WindowSpec::Hann | WindowSpec::Hamming | WindowSpec::Blackman) {
+      return Err(HannWindowError::InvalidShapeParameter);
+    }
+    spec.check_shape_parameters()?;
+    Ok(spec)
+  }
+
+  /// Generate the window with `window_length` values.
+  ///
+  /// An error is returned if the window length is less than or equal to 1, or too large, if the `beta`
+  /// of a Kaiser window is not between 0 and [`MAX_KAISER_BETA`], if the `sigma` of a Gaussian window
+  /// is not greater than 0, if the `alpha` of a Tukey window is not between 0 and 1, if the `epsilon`
+  /// of a Planck-taper window is not greater than 0 and up to 0.5,
+  /// if the `alpha` of a power-of-cosine window is negative or not finite, or if a coefficient of a
+  /// cosine-sum window is not finite.
+  pub fn generate(&self, window_length: usize) -> Result<Vec<f32>, HannWindowError> {
+    check_window_length(window_length)?;
+    self.check_shape_parameters()?;
+    let last = (window_length - 1) as f64;
+
+    let window = match *self {
+      // The Hann window is served from the lookup table
+      WindowSpec::Hann => get_hann_window(window_length)?,
+      WindowSpec::Rectangular => vec![1.0; window_length],
+      WindowSpec::Hamming => cosine_sum(window_length, &[0.54, 0.46]),
+      WindowSpec::Blackman => cosine_sum(window_length, &[0.42, 0.5, 0.08]),
+      WindowSpec::Kaiser { beta } => {
+        let beta = f64::from(beta);
+        let normalization = bessel_i0(beta);
+        (0..window_length)
+          .map(|n| {
+            // The position of the sample on [-1, 1]
+            let x = (2.0 * (n as f64)) / last - 1.0;
+            (bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / normalization) as f32
+          })
+          .collect()
+      }
+      WindowSpec::Gaussian { sigma } => {
+        let sigma = f64::from(sigma);
+        (0..window_length)
+          .map(|n| {
+            let x = (2.0 * (n as f64)) / last - 1.0;
+            (-0.5 * (x / sigma).powi(2)).exp() as f32
+          })
+          .collect()
+      }
+      WindowSpec::Tukey { alpha } => {
+        let half_taper = f64::from(alpha) / 2.0;
+        symmetric(window_length, |x| {
+          if x < half_taper { 0.5 - 0.5 * (PI * x / half_taper).cos() } else { 1.0 }
+        })
+      }
+      WindowSpec::PlanckTaper { epsilon } => {
+        let epsilon = f64::from(epsilon);
+        symmetric(window_length, |x| {
+          if x == 0.0 {
+            0.0
+          } else if x < epsilon {
+            // The exponent grows without bound towards the edge, where the window vanishes
+            1.0 / (1.0 + (epsilon / x - epsilon / (epsilon - x)).exp())
+          } else {
+            1.0
+          }
+        })
+      }
+      WindowSpec::PowerOfCosine { alpha } => {
+        let alpha = f64::from(alpha);
+        symmetric(window_length, |x| (PI * x).sin().powf(alpha))
+      }
+      WindowSpec::CosineSum { coefficients } => cosine_sum(window_length, &coefficients.map(f64::from)),
+    };
+
+    Ok(window)
+  }
+
+  // Check that the shape parameters are in the range of the window
+  fn check_shape_parameters(&self) -> Result<(), HannWindowError> {
+    let is_valid = match *self {
+      // The normalization I0(beta) must not overflow
+      WindowSpec::Kaiser { beta } => (0.0..=MAX_KAISER_BETA).contains(&beta),
+      // The width of the window must be positive
+      WindowSpec::Gaussian { sigma } => sigma > 0.0 && sigma.is_finite(),
+      // The tapers cannot be longer than the window
+      WindowSpec::Tukey { alpha } => (0.0..=1.0).contains(&alpha),
+      // The tapers must not overlap
+      WindowSpec::PlanckTaper { epsilon } => epsilon > 0.0 && epsilon <= 0.5,
+      // Negative powers would grow without bound towards the edges
+      WindowSpec::PowerOfCosine { alpha } => alpha >= 0.0 && alpha.is_finite(),
+      // Non-finite coefficients would turn the whole window into NaN
+      WindowSpec::CosineSum { coefficients } => coefficients.iter().all(|coefficient| coefficient.is_finite()),
+      WindowSpec::Rectangular | WindowSpec::Hann | WindowSpec::Hamming | WindowSpec::Blackman => true,
+    };
+    if !is_valid {
+      return Err(HannWindowError::InvalidShapeParameter);
+    }
+    Ok(())
+  }
+
+  /// Generate the window spanning `duration` at `sample_rate` Hz.
+  ///
+  /// The duration is converted into a window length by [`duration_to_samples`] with `rounding`. An
+  /// error is returned if the sample rate is 0, or like [`WindowSpec::generate`] for the window length.
+  pub fn generate_duration(&self, duration: Duration, sample_rate: u32, rounding: DurationRounding) -> Result<Vec<f32>, HannWindowError> {
+    // Durations cannot be converted without samples
+    if sample_rate == 0 {
+      return Err(HannWindowError::InvalidSampleRate);
+    }
+    self.generate(duration_to_samples(duration, sample_rate, rounding))
+  }
+
+  /// Generate the Hann window spanning `duration` at `sample_rate` Hz, e.g. 1200 values for 25 ms at
+  /// 48 kHz.
+  ///
+  /// The window length is rounded to the nearest number of samples, see
+  /// [`WindowSpec::generate_duration`] for the other rounding policies.
+  pub fn hann_duration(duration: Duration, sample_rate: u32) -> Result<Vec<f32>, HannWindowError> {
+    WindowSpec::Hann.generate_duration(duration, sample_rate, DurationRounding::Nearest)
+  }
+
+  /// Generate the window with `window_length` values, exactly symmetric and with an exact unity peak.
+  ///
+  /// The window is generated like [`WindowSpec::generate`], and then [`enforce_symmetry`] mirrors its
+  /// first half onto its second half bit for bit, and rounds the middle value of odd lengths to exactly
+  /// 1.0 when it peaks at 1.0 up to rounding, so that checks of linear phase are not thrown off by
+  /// rounding. The same errors are returned.
+  pub fn generate_exact(&self, window_length: usize) -> Result<Vec<f32>, HannWindowError> {
+    let mut window = self.generate(window_length)?;
+    enforce_symmetry(&mut window);
+    Ok(window)
+  }
+}
+
+// Parse "name" or "name:parameter", e.g. "kaiser:8.6", with the names and defaults of from_name
+impl FromStr for WindowSpec {
+  type Err = HannWindowError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.split_once(':') {
+      Some((name, parameter)) => {
+        let parameter = parameter.trim().parse::<f32>().map_err(|_error| HannWindowError::InvalidShapeParameter)?;
+        WindowSpec::from_name(name, Some(parameter))
+      }
+      None => WindowSpec::from_name(value, None),
+    }
+  }
+}
+
+/// Make a window exactly symmetric, with an exact unity peak for odd lengths.
+///
+/// This function takes the `window` and copies every value of its first half onto its mirror in the
+/// second half, so that `window[i] == window[N - 1 - i]` holds bit for bit. The middle value of a window
+/// of odd length is rounded to exactly 1.0 when it is within a few ULPs of 1.0, and is left untouched
+/// otherwise, so that windows that do not peak at 1.0, like a [`WindowSpec::CosineSum`] whose
+/// coefficients do not sum to 1, keep their peak. This is meant for windows that are symmetric up to
+/// rounding, like the ones of [`WindowSpec`].
+pub fn enforce_symmetry(window: &mut [f32]) {
+  let length = window.len();
+  for i in 0..(length / 2) {
+    window[length - 1 - i] = window[i];
+  }
+  // The middle value of an odd length is its own mirror, and only rounding keeps it from a unity peak
+  if length % 2 == 1 {
+    let middle = &mut window[length / 2];
+    if (i64::from(middle.to_bits()) - i64::from(1.0f32.to_bits())).abs() <= UNITY_PEAK_ULPS {
+      *middle = 1.0;
+    }
+  }
+}
+
+/// The Kaiser window `beta` reaching a stopband attenuation of `attenuation_db` decibels.
+///
+/// This is the empirical formula of Kaiser, also used by SciPy: `0.1102 * (A - 8.7)` above 50 dB,
+/// `0.5842 * (A - 21)^0.4 + 0.07886 * (A - 21)` between 21 dB and 50 dB, and 0 below.
+pub fn kaiser_beta(attenuation_db: f32) -> f32 {
+  if attenuation_db > 50.0 {
+    0.1102 * (attenuation_db - 8.7)
+  } else if attenuation_db > 21.0 {
+    0.5842 * (attenuation_db - 21.0).powf(0.4) + 0.07886 * (attenuation_db - 21.0)
+  } else {
+    0.0
+  }
+}
+
+/// The number of taps of a Kaiser-windowed FIR filter reaching `attenuation_db` decibels of stopband
+/// attenuation with a transition band of `transition_width`.
+///
+/// The transition width is given in cycles per sample, that is as a fraction of the sample rate. This
+/// is Kaiser's estimate `(A - 7.95) / (14.36 * transition_width) + 1`, rounded up. An error is
+/// returned if the transition width is not in (0, 0.5].
+pub fn kaiser_num_taps(attenuation_db: f32, transition_width: f32) -> Result<usize, HannWindowError> {
+  // The transition band must lie between 0 and the Nyquist frequency
+  if !(transition_width > 0.0 && transition_width <= 0.5) {
+    return Err(HannWindowError::InvalidCutoff);
+  }
+  let taps = (f64::from(attenuation_db) - 7.95) / (2.285 * 2.0 * PI * f64::from(transition_width)) + 1.0;
+
+  Ok((taps.ceil() as usize).max(2))
+}
+
+/// Generate a Gaussian-windowed tone burst.
+///
+/// This function takes the `burst_length` in samples, the `center_frequency` of the oscillator in cycles
+/// per sample and the standard deviation `sigma` of the Gaussian envelope in samples, and returns the
+/// burst `cos(2π * f * t) * exp(-0.5 * (t / sigma)^2)`, where `t` is the time from the middle of the
+/// burst. The burst peaks at 1 in its middle, and its spectrum is a Gaussian centered on the frequency,
+/// see [`gaussian_sigma`] to obtain `sigma` from a bandwidth. An error is returned if the burst length
+/// is less than or equal to 1, or too large, if the frequency is not in (0, 0.5), or if `sigma` is not
+/// greater than 0.
+pub fn gaussian_tone_burst(burst_length: usize, center_frequency: f32, sigma: f32) -> Result<Vec<f32>, HannWindowError> {
+  // The oscillator must lie between 0 and the Nyquist frequency
+  if !(center_frequency > 0.0 && center_frequency < 0.5) {
+    return Err(HannWindowError::InvalidCutoff);
+  }
+  if !(sigma > 0.0 && sigma.is_finite()) {
+    return Err(HannWindowError::InvalidShapeParameter);
+  }
+  // The envelope is the Gaussian window, whose width is relative to half the burst length
+  let middle = ((burst_length.max(2) - 1) as f64) / 2.0;
+  let envelope = WindowSpec::Gaussian { sigma: ((f64::from(sigma)) / middle) as f32 }.generate(burst_length)?;
+
+  let burst = envelope
+    .iter()
+    .enumerate()
+    .map(|(n, &envelope)| {
+      let t = (n as f64) - middle;
+      ((2.0 * PI * f64::from(center_frequency) * t).cos() * f64::from(envelope)) as f32
+    })
+    .collect();
+
+  Ok(burst)
+}
+
+/// The standard deviation in samples of a Gaussian envelope whose spectrum has the `bandwidth` at
+/// `level_db` decibels.
+///
+/// The bandwidth is given in cycles per sample, and the level is relative to the peak of the spectrum,
+/// for example -6 dB, the usual reference for the fractional bandwidth of ultrasound pulses. The
+/// spectrum `exp(-2 * (π * sigma * f)^2)` of the envelope falls to the level at half the bandwidth, so
+/// that `sigma = sqrt(-2 * ln(level)) / (π * bandwidth)`. An error is returned if the bandwidth is not
+/// in (0, 1) or if the level is not below 0 dB.
+pub fn gaussian_sigma(bandwidth: f32, level_db: f32) -> Result<f32, HannWindowError> {
+  // The band must fit within the sample rate and the level below the peak
+  if !(bandwidth > 0.0 && bandwidth < 1.0) {
+    return Err(HannWindowError::InvalidCutoff);
+  }
+  if !(level_db < 0.0 && level_db.is_finite()) {
+    return Err(HannWindowError::InvalidShapeParameter);
+  }
+  let level = 10f64.powf(f64::from(level_db) / 20.0);
+
+  Ok(((-2.0 * level.ln()).sqrt() / (PI * f64::from(bandwidth))) as f32)
+}
+
+// Generate a symmetric window `a0 - a1 * cos(2π * n / (N - 1)) + a2 * cos(4π * n / (N - 1)) - ...`
+fn cosine_sum(window_length: usize, coefficients: &[f64]) -> Vec<f32> {
+  let last = (window_length - 1) as f64;
+  (0..window_length)
+    .map(|n| {
+      let phase = (2.0 * PI * (n as f64)) / last;
+      let value: f64 = coefficients
+        .iter()
+        .enumerate()
+        .map(|(k, coefficient)| {
+          let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+          sign * coefficient * ((k as f64) * phase).cos()
+        })
+        .sum();
+      value as f32
+    })
+    .collect()
+}
+
+// Generate a symmetric window from its values at the distances `x` from the nearest edge, from 0 at
+// the edges to 0.5 in the middle, in fractions of the window length
+fn symmetric<F: Fn(f64) -> f64>(window_length: usize, shape: F) -> Vec<f32> {
+  let last = window_length - 1;
+  (0..window_length).map(|n| shape((n.min(last - n) as f64) / (last as f64)) as f32).collect()
+}
+
+// The modified Bessel function of the first kind of order 0, summed from its power series
+pub(crate) fn bessel_i0(x: f64) -> f64 {
+  let quarter_square = (x * x) / 4.0;
+  let mut term = 1.0;
+  let mut sum = 1.0;
+  let mut k = 1.0;
+  // The terms decrease quickly once k exceeds x / 2
+  while term > sum * 1e-16 {
+    term *= quarter_square / (k * k);
+    sum += term;
+    k += 1.0;
+  }
+  sum
+}
+
+#[cfg(test)]
+mod test_window_spec {
+  use approx::assert_abs_diff_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_window_spec_values() {
+    let hamming = WindowSpec::Hamming.generate(5).unwrap();
+    let blackman = WindowSpec::Blackman.generate(5).unwrap();
+
+    assert_eq!(WindowSpec::Hann.generate(1024).unwrap(), get_hann_window(1024).unwrap());
+    assert_eq!(WindowSpec::Rectangular.generate(3).unwrap(), vec![1.0; 3]);
+    for (value, expected) in hamming.iter().zip([0.08, 0.54, 1.0, 0.54, 0.08]) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-6);
+    }
+    for (value, expected) in blackman.iter().zip([0.0, 0.34, 1.0, 0.34, 0.0]) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-6);
+    }
+  }
+
+  #[test]
+  fn test_kaiser_window() {
+    let window = WindowSpec::Kaiser { beta: 8.6 }.generate(9).unwrap();
+
+    // The window is symmetric, peaks at 1 in the middle and ends at 1 / I0(beta)
+    assert_abs_diff_eq!(window[4], 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(window[0], (1.0 / bessel_i0(8.6)) as f32, epsilon = 1e-7);
+    for i in 0..4 {
+      assert_abs_diff_eq!(window[i], window[8 - i], epsilon = 1e-7);
+    }
+    assert_eq!(WindowSpec::Kaiser { beta: 0.0 }.generate(4).unwrap(), vec![1.0; 4]);
+  }
+
+  #[test]
+  fn test_kaiser_window_invalid_beta() {
+    // The largest beta still gives a finite window, vanishing at the edges
+    let window = WindowSpec::Kaiser { beta: MAX_KAISER_BETA }.generate(9).unwrap();
+    assert!(bessel_i0(f64::from(MAX_KAISER_BETA)).is_finite());
+    assert!(window.iter().all(|value| value.is_finite()));
+    assert_abs_diff_eq!(window[4], 1.0, epsilon = 1e-6);
+
+    for beta in [-1.0, 800.0, f32::INFINITY, f32::NAN] {
+      assert_eq!(WindowSpec::Kaiser { beta }.generate(9).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    }
+    for name in ["kaiser:-1", "kaiser:800", "kaiser:inf", "kaiser:NaN"] {
+      assert_eq!(name.parse::<WindowSpec>().unwrap_err(), HannWindowError::InvalidShapeParameter);
+    }
+  }
+
+  #[test]
+  fn test_kaiser_design_helpers() {
+    assert_abs_diff_eq!(kaiser_beta(60.0), 5.65326, epsilon = 1e-5);
+    assert_abs_diff_eq!(kaiser_beta(40.0), 3.395321, epsilon = 1e-5);
+    assert_eq!(kaiser_beta(10.0), 0.0);
+    assert_eq!(kaiser_num_taps(60.0, 0.05).unwrap(), 74);
+    assert_eq!(kaiser_num_taps(60.0, 0.0).unwrap_err(), HannWindowError::InvalidCutoff);
+  }
+
+  #[test]
+  fn test_gaussian_window() {
+    let window = WindowSpec::Gaussian { sigma: 0.5 }.generate(5).unwrap();
+
+    // The window is exp(-0.5 * (x / 0.5)^2) at x = -1, -0.5, 0, 0.5 and 1
+    for (value, expected) in window.iter().zip([(-2.0f32).exp(), (-0.5f32).exp(), 1.0, (-0.5f32).exp(), (-2.0f32).exp()]) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-6);
+    }
+    assert_eq!(WindowSpec::Gaussian { sigma: 0.0 }.generate(5).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_gaussian_tone_burst() {
+    let sigma = gaussian_sigma(0.05, -6.0).unwrap();
+    let burst = gaussian_tone_burst(201, 0.1, sigma).unwrap();
+
+    // The burst peaks in its middle and its envelope falls to exp(-0.5) one sigma away
+    assert_abs_diff_eq!(burst[100], 1.0, epsilon = 1e-6);
+    assert_abs_diff_eq!(burst[110], ((-0.5 * (10.0 / sigma).powi(2)).exp() * (2.0 * std::f32::consts::PI).cos()), epsilon = 1e-5);
+    for i in 0..100 {
+      assert_abs_diff_eq!(burst[i], burst[200 - i], epsilon = 1e-6);
+    }
+
+    // The spectrum falls by 6 dB at half the bandwidth from the center frequency
+    let magnitude = |frequency: f64| {
+      let (real, imaginary) = burst.iter().enumerate().fold((0.0, 0.0), |(real, imaginary), (n, &sample)| {
+        let phase = 2.0 * PI * frequency * (n as f64);
+        (real + f64::from(sample) * phase.cos(), imaginary - f64::from(sample) * phase.sin())
+      });
+      (real * real + imaginary * imaginary).sqrt()
+    };
+    assert_abs_diff_eq!(20.0 * (magnitude(0.125) / magnitude(0.1)).log10(), -6.0, epsilon = 0.01);
+  }
+
+  #[test]
+  fn test_gaussian_invalid_parameters() {
+    assert_eq!(gaussian_tone_burst(64, 0.5, 4.0).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(gaussian_tone_burst(64, 0.1, -1.0).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!(gaussian_tone_burst(1, 0.1, 4.0).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+    assert_eq!(gaussian_sigma(0.0, -6.0).unwrap_err(), HannWindowError::InvalidCutoff);
+    assert_eq!(gaussian_sigma(0.1, 3.0).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_bessel_i0() {
+    assert_eq!(bessel_i0(0.0), 1.0);
+    assert_abs_diff_eq!(bessel_i0(1.0), 1.2660658777520082, epsilon = 1e-12);
+    assert_abs_diff_eq!(bessel_i0(10.0), 2815.716628466254, epsilon = 1e-8);
+  }
+
+  #[test]
+  fn test_generate_exact() {
+    let specs = [
+      WindowSpec::Rectangular,
+      WindowSpec::Hann,
+      WindowSpec::Hamming,
+      WindowSpec::Blackman,
+      WindowSpec::Kaiser { beta: 8.6 },
+      WindowSpec::Gaussian { sigma: 0.4 },
+      WindowSpec::Tukey { alpha: 0.3 },
+      WindowSpec::PlanckTaper { epsilon: 0.1 },
+      WindowSpec::PowerOfCosine { alpha: 1.7 },
+      WindowSpec::CosineSum { coefficients: [0.35875, 0.48829, 0.14128, 0.01168, 0.0, 0.0, 0.0, 0.0] },
+      // The coefficients do not sum to 1, and the window peaks at 0.8
+      WindowSpec::CosineSum { coefficients: [0.4, 0.4, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0] },
+    ];
+
+    for spec in specs {
+      for window_length in 2..=257 {
+        let window = spec.generate_exact(window_length).unwrap();
+        let generated = spec.generate(window_length).unwrap();
+        for i in 0..window_length {
+          assert_eq!(window[i].to_bits(), window[window_length - 1 - i].to_bits());
+          assert_abs_diff_eq!(window[i], generated[i], epsilon = 1e-6);
+        }
+        // Only a peak of 1.0 up to rounding is rounded to exactly 1.0
+        if window_length % 2 == 1 && (generated[window_length / 2] - 1.0).abs() < 1e-6 {
+          assert_eq!(window[window_length / 2], 1.0);
+        } else if window_length % 2 == 1 {
+          assert_eq!(window[window_length / 2], generated[window_length / 2]);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_window_spec_from_str() {
+    assert_eq!("kaiser:5".parse::<WindowSpec>().unwrap(), WindowSpec::Kaiser { beta: 5.0 });
+    assert_eq!("kaiser".parse::<WindowSpec>().unwrap(), WindowSpec::Kaiser { beta: 8.6 });
+    assert_eq!("planck-taper:0.2".parse::<WindowSpec>().unwrap(), WindowSpec::PlanckTaper { epsilon: 0.2 });
+    assert_eq!("power-of-cosine:1.5".parse::<WindowSpec>().unwrap(), WindowSpec::PowerOfCosine { alpha: 1.5 });
+    assert_eq!("kaiser:x".parse::<WindowSpec>().unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!("gaussian:0".parse::<WindowSpec>().unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!("hann:2".parse::<WindowSpec>().unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!("hanning".parse::<WindowSpec>().unwrap_err(), HannWindowError::UnknownWindow);
+
+    // Every name is parsed
+    for name in WINDOW_NAMES {
+      assert!(name.parse::<WindowSpec>().is_ok());
+    }
+  }
+
+  #[test]
+  fn test_enforce_symmetry() {
+    let mut window = [0.1, 0.6, 0.9999999, 0.6000001, 0.1000001];
+
+    enforce_symmetry(&mut window);
+
+    assert_eq!(window, [0.1, 0.6, 1.0, 0.6, 0.1]);
+
+    // A middle value far from 1.0 is only mirrored
+    let mut window = [0.1, 0.5, 0.8, 0.5000001, 0.1];
+    enforce_symmetry(&mut window);
+    assert_eq!(window, [0.1, 0.5, 0.8, 0.5, 0.1]);
+  }
+
+  #[test]
+  fn test_tukey_window() {
+    let tukey = WindowSpec::Tukey { alpha: 0.5 }.generate(9).unwrap();
+
+    // The tapers cover a quarter of the window at either end
+    for (value, expected) in tukey.iter().zip([0.0, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0]) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-6);
+    }
+    assert_eq!(WindowSpec::Tukey { alpha: 0.0 }.generate(8).unwrap(), vec![1.0; 8]);
+    let hann = WindowSpec::Tukey { alpha: 1.0 }.generate(64).unwrap();
+    for (value, expected) in hann.iter().zip(get_hann_window(64).unwrap()) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-6);
+    }
+    assert_eq!(WindowSpec::Tukey { alpha: 1.5 }.generate(8).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_planck_taper_window() {
+    let window = WindowSpec::PlanckTaper { epsilon: 0.25 }.generate(9).unwrap();
+
+    // The step is halfway at half the taper, x = 0.125 or n = 1
+    assert_eq!(window[0], 0.0);
+    assert_abs_diff_eq!(window[1], 0.5, epsilon = 1e-6);
+    assert_eq!(window[2..7], [1.0; 5]);
+    assert_eq!(window[8], 0.0);
+    assert!(WindowSpec::PlanckTaper { epsilon: 0.1 }.generate(1001).unwrap().windows(2).take(500).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(WindowSpec::PlanckTaper { epsilon: 0.0 }.generate(8).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    assert_eq!(WindowSpec::PlanckTaper { epsilon: 0.6 }.generate(8).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_cosine_sum_window() {
+    let mut coefficients = [0.0; MAX_COSINE_SUM_TERMS];
+    coefficients[..3].copy_from_slice(&[0.42, 0.5, 0.08]);
+
+    let window = WindowSpec::CosineSum { coefficients }.generate(64).unwrap();
+
+    // The coefficients are rounded to `f32`
+    for (value, expected) in window.iter().zip(WindowSpec::Blackman.generate(64).unwrap()) {
+      assert_abs_diff_eq!(*value, expected, epsilon = 1e-7);
+    }
+    coefficients[5] = f32::NAN;
+    assert_eq!(WindowSpec::CosineSum { coefficients }.generate(64).unwrap_err(), HannWindowError::InvalidShapeParameter);
+    coefficients[5] = f32::INFINITY;
+    assert_eq!(WindowSpec::CosineSum { coefficients }.generate(64).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_power_of_cosine_window() {
+    let hann = get_hann_window(65).unwrap();
+    let squared = WindowSpec::PowerOfCosine { alpha: 2.0 }.generate(65).unwrap();
+    let steep = WindowSpec::PowerOfCosine { alpha: 2.5 }.generate(65).unwrap();
+
+    // The powers 1 and 2 are the cosine and Hann windows, and fractional powers lie in between
+    for (n, (value, expected)) in squared.iter().zip(&hann).enumerate() {
+      assert_abs_diff_eq!(*value, *expected, epsilon = 1e-6);
+      assert_abs_diff_eq!(steep[n], expected.powf(1.25), epsilon = 1e-6);
+    }
+    assert_abs_diff_eq!(WindowSpec::PowerOfCosine { alpha: 1.0 }.generate(5).unwrap()[1], 0.5f32.sqrt(), epsilon = 1e-7);
+    assert_eq!(WindowSpec::PowerOfCosine { alpha: 0.0 }.generate(8).unwrap(), vec![1.0; 8]);
+    assert_eq!(WindowSpec::PowerOfCosine { alpha: -0.5 }.generate(8).unwrap_err(), HannWindowError::InvalidShapeParameter);
+  }
+
+  #[test]
+  fn test_generate_duration() {
+    let hann = WindowSpec::hann_duration(Duration::from_millis(25), 48_000).unwrap();
+    let kaiser = WindowSpec::Kaiser { beta: 8.6 }.generate_duration(Duration::from_millis(25), 22_050, DurationRounding::Up).unwrap();
+
+    assert_eq!(hann, get_hann_window(1200).unwrap());
+    assert_eq!(kaiser.len(), 552);
+    assert_eq!(WindowSpec::hann_duration(Duration::from_millis(25), 0).unwrap_err(), HannWindowError::InvalidSampleRate);
+    assert_eq!(WindowSpec::hann_duration(Duration::from_micros(10), 48_000).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+
+  #[test]
+  fn test_window_spec_invalid_length() {
+    assert_eq!(WindowSpec::Hamming.generate(1).unwrap_err(), HannWindowError::WindowLengthTooSmall);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_window_spec_serde() {
+    let specs = [WindowSpec::Hann, WindowSpec::Kaiser { beta: 8.6 }];
+
+    let json = serde_json::to_string(&specs).unwrap();
+
+    assert_eq!(json, r#"["hann",{"kaiser":{"beta":8.6}}]"#);
+    assert_eq!(serde_json::from_str::<Vec<WindowSpec>>(&json).unwrap(), specs);
+  }
+}
@@ -0,0 +1,33 @@
+//! The window functions: generating, designing and applying windows.
+//!
+//! This module gathers the items of the crate concerned with the windows themselves, the
+//! [`WindowSpec`] catalogue and its design helpers, the functions multiplying signals by windows and
+//! the designs built from windows: windowed-sinc FIR filters, polyphase filter bank prototypes, pulse
+//! shaping filters, OFDM symbol tapers and window smoothing. The Hann window itself,
+//! [`get_hann_window`](crate::get_hann_window), and [`HannWindowError`](crate::HannWindowError) stay
+//! at the root of the crate.
+
+pub use crate::{
+  apply_window::{ apply_window, apply_window_array },
+  cosine_fit::{ fit_cosine_sum, CosineSumTarget },
+  duration::{ duration_to_samples, DurationRounding },
+  fir::{ fir_bandpass, fir_bandstop, fir_highpass, fir_hilbert, fir_lowpass },
+  hann_window::{ calculate_hann_window_into, WindowLength },
+  integer_window::{ apply_window_i16, apply_window_i32, TpdfDither },
+  ofdm::{ ofdm_edge_taper, window_ofdm_symbols },
+  polyphase::{ pfb_prototype, polyphase_matrix },
+  pulse_shaping::{ raised_cosine, root_raised_cosine },
+  smooth::{ smooth, EdgeMode },
+  sparse_window::SparseWindow,
+  window_spec::{
+    enforce_symmetry,
+    gaussian_sigma,
+    gaussian_tone_burst,
+    kaiser_beta,
+    kaiser_num_taps,
+    WindowSpec,
+    MAX_COSINE_SUM_TERMS,
+    MAX_KAISER_BETA,
+    WINDOW_NAMES,
+  },
+};
@@ -0,0 +1,126 @@
+use std::{ alloc::{ GlobalAlloc, Layout, System }, cell::Cell };
+
+use hann_rs::{ get_hann_window, stft::OverlapAdd, streaming::{ frame_queue, RealtimeProcessor, Resampler, StreamingWindower } };
+
+// A global allocator counting the allocations made by the current thread
+struct CountingAllocator;
+
+thread_local! {
+  static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    unsafe { System.dealloc(ptr, layout) }
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+    unsafe { System.realloc(ptr, layout, new_size) }
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Count the allocations made by the current thread while running `f`
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+  let before = ALLOCATIONS.with(Cell::get);
+  f();
+  ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_realtime_processor_does_not_allocate() {
+  let window = get_hann_window(1024).unwrap();
+  let mut processor = RealtimeProcessor::new(&window, 256).unwrap();
+  let input = vec![0.25; 4096];
+  let mut output = vec![0.0; 4096];
+
+  let allocations = count_allocations(|| {
+    for (input, output) in input.chunks(100).zip(output.chunks_mut(100)) {
+      processor.process(input, output, |frame| frame.iter_mut().for_each(|sample| *sample *= 0.5)).unwrap();
+    }
+    processor.reset();
+  });
+
+  assert_eq!(allocations, 0);
+}
+
+#[test]
+fn test_streaming_windower_does_not_allocate() {
+  let window = get_hann_window(512).unwrap();
+  let mut windower = StreamingWindower::new(512, 128).unwrap().with_window(&window).unwrap();
+  let input = vec![0.25; 4096];
+  let mut energy = 0.0;
+
+  let allocations = count_allocations(|| {
+    for chunk in input.chunks(64) {
+      windower.process(chunk, |frame| energy += frame.iter().sum::<f32>());
+    }
+  });
+
+  assert_eq!(allocations, 0);
+  assert!(energy > 0.0);
+}
+
+#[test]
+fn test_overlap_add_does_not_allocate() {
+  let window = get_hann_window(512).unwrap();
+  let mut overlap_add = OverlapAdd::new(512, 128).unwrap().with_window(&window).unwrap().with_normalization(&window).unwrap();
+  let frame = vec![0.25; 512];
+  let mut total = 0.0;
+
+  let allocations = count_allocations(|| {
+    for _ in 0..32 {
+      total += overlap_add.push(&frame).unwrap().iter().sum::<f32>();
+    }
+    total += overlap_add.flush().iter().sum::<f32>();
+  });
+
+  assert_eq!(allocations, 0);
+  assert!(total > 0.0);
+}
+
+#[test]
+fn test_frame_queue_does_not_allocate() {
+  let (mut producer, mut consumer) = frame_queue(4, 512).unwrap();
+  let frame = vec![0.25; 512];
+  let mut received = vec![0.0; 512];
+  let mut popped = 0;
+
+  let allocations = count_allocations(|| {
+    for _ in 0..32 {
+      producer.push(&frame).unwrap();
+      if consumer.pop(&mut received).unwrap() {
+        popped += 1;
+      }
+    }
+  });
+
+  assert_eq!(allocations, 0);
+  assert_eq!(popped, 32);
+}
+
+#[test]
+fn test_resampler_does_not_allocate() {
+  let mut resampler = Resampler::new(48000, 44100).unwrap();
+  let input = vec![0.25; 4800];
+  let mut output = vec![0.0; resampler.max_output_length(100)];
+  let mut total = 0;
+
+  let allocations = count_allocations(|| {
+    for block in input.chunks(100) {
+      total += resampler.process(block, &mut output).unwrap();
+    }
+    total += resampler.flush(&mut output).unwrap();
+  });
+
+  assert_eq!(allocations, 0);
+  assert_eq!(total, 4410);
+}